@@ -14,7 +14,8 @@ use std::fs::File;
 use std::mem;
 use std::path::Path;
 use std::slice;
-use surfman::{Connection, ContextAttributeFlags, ContextAttributes, GLApi, GLVersion};
+use surfman::{Connection, ContextAttributeFlags, ContextAttributes, ContextPriority};
+use surfman::{GLApi, GLVersion};
 use surfman::{SurfaceAccess, SurfaceType};
 
 mod common;
@@ -103,6 +104,7 @@ fn main() {
     let context_attributes = ContextAttributes {
         version: GLVersion::new(3, 3),
         flags: ContextAttributeFlags::empty(),
+        priority: ContextPriority::Medium,
     };
     let context_descriptor = device
         .create_context_descriptor(&context_attributes)
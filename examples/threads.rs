@@ -17,7 +17,7 @@ use surfman::{Adapter, Connection, Context, ContextDescriptor, Device, GLApi, Su
 use self::common::FilesystemResourceLoader;
 
 #[cfg(not(target_os = "android"))]
-use surfman::{ContextAttributeFlags, ContextAttributes, GLVersion};
+use surfman::{ContextAttributeFlags, ContextAttributes, ContextPriority, GLVersion};
 #[cfg(not(target_os = "android"))]
 use winit::{
     dpi::PhysicalSize,
@@ -144,6 +144,7 @@ fn main() {
     let context_attributes = ContextAttributes {
         version: GLVersion::new(3, 0),
         flags: ContextAttributeFlags::ALPHA,
+        priority: ContextPriority::Medium,
     };
     let context_descriptor = device
         .create_context_descriptor(&context_attributes)
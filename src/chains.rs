@@ -16,6 +16,14 @@
 //!
 //! The consumer may *take* the front buffer, display it, then *recycle* it.
 //!
+//! A swap chain may also *broadcast* its front buffer to several consumers. Each consumer
+//! *registers* with the swap chain in the context it reads from, then *acquires* read access to the
+//! front buffer through a surface texture, and *releases* it when it is done. When the producer
+//! swaps buffers, it gives each consumer context its own copy of the new front buffer, so
+//! consumers in different contexts can read it at the same time; consumers in the same context
+//! share one surface texture. A front buffer is only recycled once every consumer reading it has
+//! released it and the producer has swapped in a newer one.
+//!
 //! Each producer context has one *attached* swap chain, whose back buffer is the current surface of the context.
 //! The producer may change the attached swap chain, attaching a currently unattached swap chain,
 //! and detaching the currently attached one.
@@ -23,18 +31,22 @@
 #![allow(missing_docs)]
 
 use crate::device::Device as DeviceAPI;
-use crate::{ContextID, Error, SurfaceAccess, SurfaceInfo, SurfaceType};
+use crate::gl_utils::{GLStateGroups, GLStateSnapshot};
+use crate::{ContextAttributeFlags, ContextID, Error, SurfaceAccess, SurfaceInfo, SurfaceType};
 use euclid::default::Size2D;
 use fnv::{FnvHashMap, FnvHashSet};
 use glow as gl;
 use glow::Context as Gl;
-use glow::HasContext;
-use log::debug;
+use glow::{HasContext, Texture};
+use log::{debug, warn};
+use std::any::Any;
+use std::cell::RefCell;
 use std::collections::hash_map::Entry;
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::mem;
-use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::mem::{self, ManuallyDrop};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak};
 
 // The data stored for each swap chain.
 struct SwapChainData<Device: DeviceAPI> {
@@ -50,10 +62,143 @@ struct SwapChainData<Device: DeviceAPI> {
     pending_surface: Option<Device::Surface>,
     // All of the surfaces that have already been displayed, ready to be recycled.
     recycled_surfaces: Vec<Device::Surface>,
+    // The consumers registered for broadcast, if any, and the contexts they read in.
+    consumers: FnvHashMap<ConsumerID, ContextID>,
+    // The id of the next registered consumer.
+    next_consumer_id: usize,
+    // The front buffers shared between the registered consumers, the most recent one last.
+    shared_surfaces: Vec<SharedSurface<Device>>,
+    // The estimated size of one pixel of a surface, in bytes.
+    bytes_per_pixel: usize,
+    // The collection the swap chain belongs to, if any, which enforces its memory budget.
+    collection: Option<Arc<dyn SwapChainCollection<Device>>>,
+}
+
+// The collection of swap chains that a swap chain belongs to.
+trait SwapChainCollection<Device: DeviceAPI>: Send + Sync {
+    // Trim the swap chains for a producer context if the collection exceeds its memory budget.
+    fn trim_to_memory_budget(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
+    ) -> Result<bool, Error>;
+}
+
+/// An estimate of the GPU memory held by a swap chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SwapChainMemoryReport {
+    /// The number of surfaces held by the swap chain, including front buffers that broadcast
+    /// consumers are reading. Surfaces taken from the swap chain are not included.
+    pub surfaces: usize,
+    /// The estimated size of those surfaces, in bytes.
+    pub bytes: usize,
+    /// The number of idle recycled surfaces, which `trim` would destroy.
+    pub recycled_surfaces: usize,
+    /// The estimated size of the idle recycled surfaces, in bytes.
+    pub recycled_bytes: usize,
 }
 
-pub enum PreserveBuffer<'a> {
-    Yes(&'a Gl),
+/// The id of a consumer registered with a broadcast swap chain.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ConsumerID(usize);
+
+// A front buffer shared between the registered consumers of a swap chain.
+struct SharedSurface<Device: DeviceAPI> {
+    // Identifies the surface textures that consumers read this front buffer through.
+    id: usize,
+    // The copies of the front buffer, indexed by the consumer context that reads them.
+    copies: FnvHashMap<ContextID, SharedSurfaceCopy<Device>>,
+    // The size of the surfaces.
+    size: Size2D<i32>,
+}
+
+// The copy of a shared front buffer that the consumers in one context read.
+struct SharedSurfaceCopy<Device: DeviceAPI> {
+    // The surface, or `None` while consumers are reading it through a surface texture.
+    surface: Option<Device::Surface>,
+    // The consumers currently reading the surface.
+    readers: FnvHashSet<ConsumerID>,
+}
+
+// The id of the next shared front buffer, across all swap chains.
+static NEXT_SHARED_SURFACE_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // The surface textures that consumers on this thread read shared front buffers through,
+    // indexed by the id of the front buffer and the consumer context. Surface textures can't
+    // leave the thread they were created on, so they are kept here rather than in the swap chain.
+    // They are never dropped: one that is never released is leaked when the thread exits.
+    static CONSUMER_SURFACE_TEXTURES: RefCell<FnvHashMap<(usize, ContextID), Box<dyn Any>>> =
+        RefCell::new(FnvHashMap::default());
+}
+
+fn store_consumer_surface_texture<SurfaceTexture: 'static>(
+    key: (usize, ContextID),
+    surface_texture: SurfaceTexture,
+) {
+    CONSUMER_SURFACE_TEXTURES.with(|surface_textures| {
+        surface_textures
+            .borrow_mut()
+            .insert(key, Box::new(ManuallyDrop::new(surface_texture)))
+    });
+}
+
+fn with_consumer_surface_texture<SurfaceTexture: 'static, R>(
+    key: (usize, ContextID),
+    f: impl FnOnce(&SurfaceTexture) -> R,
+) -> Option<R> {
+    CONSUMER_SURFACE_TEXTURES.with(|surface_textures| {
+        let surface_textures = surface_textures.borrow();
+        let surface_texture = surface_textures
+            .get(&key)?
+            .downcast_ref::<ManuallyDrop<SurfaceTexture>>()?;
+        Some(f(surface_texture))
+    })
+}
+
+fn take_consumer_surface_texture<SurfaceTexture: 'static>(
+    key: (usize, ContextID),
+) -> Option<SurfaceTexture> {
+    CONSUMER_SURFACE_TEXTURES.with(|surface_textures| {
+        let surface_texture = surface_textures.borrow_mut().remove(&key)?;
+        let surface_texture = surface_texture
+            .downcast::<ManuallyDrop<SurfaceTexture>>()
+            .ok()?;
+        Some(ManuallyDrop::into_inner(*surface_texture))
+    })
+}
+
+impl<Device: DeviceAPI> SharedSurface<Device> {
+    fn new(size: Size2D<i32>) -> SharedSurface<Device> {
+        SharedSurface {
+            id: NEXT_SHARED_SURFACE_ID.fetch_add(1, Ordering::Relaxed),
+            copies: FnvHashMap::default(),
+            size,
+        }
+    }
+}
+
+impl<Device: DeviceAPI> SharedSurfaceCopy<Device> {
+    fn new(surface: Device::Surface) -> SharedSurfaceCopy<Device> {
+        SharedSurfaceCopy {
+            surface: Some(surface),
+            readers: FnvHashSet::default(),
+        }
+    }
+}
+
+/// Whether, and how, the contents of the old back buffer are copied into the new one.
+///
+/// The copy uses the producer context's own GL functions, so that context must be current.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PreserveBuffer {
+    /// Copy the contents, clipping them to the new size if the sizes differ.
+    /// The contents stay anchored at the origin of the surface, and any area of the new surface
+    /// beyond them is cleared to transparent black, with depth 1 and stencil 0.
+    Yes,
+    /// Copy the contents, scaling them to the new size if the sizes differ.
+    Scaled,
+    /// Discard the contents.
     No,
 }
 
@@ -146,7 +291,7 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         &mut self,
         device: &mut Device,
         context: &mut Device::Context,
-        preserve_buffer: PreserveBuffer<'_>,
+        preserve_buffer: PreserveBuffer,
     ) -> Result<(), Error> {
         debug!("Swap buffers on context {:?}", self.context_id);
         self.validate_context(device, context)?;
@@ -160,24 +305,10 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
             );
             self.recycle_surface(old_front_buffer);
         }
+        self.retire_shared_surfaces();
 
         // Fetch a new back buffer, recycling presented buffers if possible.
-        let new_back_buffer = self
-            .recycled_surfaces
-            .iter()
-            .position(|surface| device.surface_info(surface).size == self.size)
-            .map(|index| {
-                debug!("Recycling surface for context {:?}", self.context_id);
-                Ok(self.recycled_surfaces.swap_remove(index))
-            })
-            .unwrap_or_else(|| {
-                debug!(
-                    "Creating a new surface ({:?}) for context {:?}",
-                    self.size, self.context_id
-                );
-                let surface_type = SurfaceType::Generic { size: self.size };
-                device.create_surface(context, self.surface_access, surface_type)
-            })?;
+        let new_back_buffer = self.recycled_or_new_surface(device, context, self.size)?;
 
         let back_info = device.surface_info(&new_back_buffer);
 
@@ -191,28 +322,8 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         self.back_buffer
             .replace_surface(device, context, new_back_buffer)?;
 
-        if let PreserveBuffer::Yes(gl) = preserve_buffer {
-            let front_info = device.surface_info(&new_front_buffer);
-            unsafe {
-                gl.bind_framebuffer(gl::READ_FRAMEBUFFER, front_info.framebuffer_object);
-                debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
-                gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, back_info.framebuffer_object);
-                debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
-                gl.blit_framebuffer(
-                    0,
-                    0,
-                    front_info.size.width,
-                    front_info.size.height,
-                    0,
-                    0,
-                    back_info.size.width,
-                    back_info.size.height,
-                    gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT,
-                    gl::NEAREST,
-                );
-                debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
-            }
-        }
+        let front_info = device.surface_info(&new_front_buffer);
+        self.copy_surface(device, context, &front_info, &back_info, preserve_buffer);
 
         // Update the state
         debug!(
@@ -220,13 +331,46 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
             device.surface_info(&new_front_buffer).id,
             self.context_id
         );
-        self.pending_surface = Some(new_front_buffer);
+        let result = if self.consumers.is_empty() {
+            self.pending_surface = Some(new_front_buffer);
+            Ok(())
+        } else {
+            self.share_surface(device, context, new_front_buffer)
+        };
         for mut surface in self.recycled_surfaces.drain(..) {
             debug!("Destroying a surface for context {:?}", self.context_id);
             device.destroy_surface(context, &mut surface)?;
         }
 
-        Ok(())
+        result
+    }
+
+    // Take a recycled surface of the given size, or create one if there is none.
+    // Called by the producer.
+    fn recycled_or_new_surface(
+        &mut self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        size: Size2D<i32>,
+    ) -> Result<Device::Surface, Error> {
+        match self
+            .recycled_surfaces
+            .iter()
+            .position(|surface| device.surface_info(surface).size == size)
+        {
+            Some(index) => {
+                debug!("Recycling surface for context {:?}", self.context_id);
+                Ok(self.recycled_surfaces.swap_remove(index))
+            }
+            None => {
+                debug!(
+                    "Creating a new surface ({:?}) for context {:?}",
+                    size, self.context_id
+                );
+                let surface_type = SurfaceType::Generic { size };
+                device.create_surface(context, self.surface_access, surface_type)
+            }
+        }
     }
 
     // Swap the attached swap chain.
@@ -252,9 +396,116 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         Ok(())
     }
 
+    // Copy the contents of one surface into another, as requested by `preserve_buffer`.
+    // Color is filtered linearly when scaling; depth and stencil never are.
+    // When clipping to a larger surface, the rest of it is cleared.
+    // Called by the producer, with the producer context current.
+    fn copy_surface(
+        &mut self,
+        device: &Device,
+        context: &Device::Context,
+        src_info: &SurfaceInfo,
+        dst_info: &SurfaceInfo,
+        preserve_buffer: PreserveBuffer,
+    ) {
+        let (src_size, dst_size) = match preserve_buffer {
+            PreserveBuffer::No => return,
+            PreserveBuffer::Yes => {
+                let size = src_info.size.min(dst_info.size);
+                (size, size)
+            }
+            PreserveBuffer::Scaled => (src_info.size, dst_info.size),
+        };
+        debug!(
+            "Copying surface {:?} ({:?}) to {:?} ({:?}) for context {:?}",
+            src_info.id, src_size, dst_info.id, dst_size, self.context_id
+        );
+
+        // Only copy the buffers that the context's surfaces actually have.
+        let flags = device
+            .context_descriptor_attributes(&device.context_descriptor(context))
+            .flags;
+        let mut depth_stencil_mask = 0;
+        if flags.contains(ContextAttributeFlags::DEPTH) {
+            depth_stencil_mask |= gl::DEPTH_BUFFER_BIT;
+        }
+        if flags.contains(ContextAttributeFlags::STENCIL) {
+            depth_stencil_mask |= gl::STENCIL_BUFFER_BIT;
+        }
+        let color_filter = if src_size == dst_size {
+            gl::NEAREST
+        } else {
+            gl::LINEAR
+        };
+
+        // The context is already current, so this can only fail if the platform lost it.
+        let gl = match device.context_gl(context) {
+            Ok(gl) => gl,
+            Err(err) => {
+                warn!(
+                    "Failed to copy surface {:?} for context {:?}: {:?}",
+                    src_info.id, self.context_id, err
+                );
+                return;
+            }
+        };
+        unsafe {
+            let state = GLStateSnapshot::capture(
+                &gl,
+                GLStateGroups::FRAMEBUFFER_BINDINGS
+                    | GLStateGroups::SCISSOR
+                    | GLStateGroups::CLEAR_VALUES
+                    | GLStateGroups::WRITE_MASKS
+                    | GLStateGroups::RASTERIZER_DISCARD,
+            );
+            gl.disable(gl::SCISSOR_TEST);
+
+            gl.bind_framebuffer(gl::READ_FRAMEBUFFER, src_info.framebuffer_object);
+            debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
+            gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, dst_info.framebuffer_object);
+            debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
+            if dst_size != dst_info.size {
+                gl.clear_color(0., 0., 0., 0.);
+                gl.clear_depth(1.);
+                gl.clear_stencil(0);
+                gl.disable(gl::RASTERIZER_DISCARD);
+                gl.depth_mask(true);
+                gl.stencil_mask(0xFFFFFFFF);
+                gl.color_mask(true, true, true, true);
+                gl.clear(gl::COLOR_BUFFER_BIT | depth_stencil_mask);
+                debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
+            }
+            let blit = |mask, filter| {
+                gl.blit_framebuffer(
+                    0,
+                    0,
+                    src_size.width,
+                    src_size.height,
+                    0,
+                    0,
+                    dst_size.width,
+                    dst_size.height,
+                    mask,
+                    filter,
+                )
+            };
+            if color_filter == gl::NEAREST {
+                blit(gl::COLOR_BUFFER_BIT | depth_stencil_mask, gl::NEAREST);
+            } else {
+                blit(gl::COLOR_BUFFER_BIT, gl::LINEAR);
+                if depth_stencil_mask != 0 {
+                    blit(depth_stencil_mask, gl::NEAREST);
+                }
+            }
+            debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
+
+            state.restore(&gl);
+        }
+    }
+
     // Resize the swap chain.
     // This creates a new back buffer of the appropriate size,
-    // and destroys the old one.
+    // optionally copies the old contents into it, and destroys the old one.
     // Called by the producer.
     // Returns an error if `context` is not the producer context for this swap chain.
     // Returns an error if `size` is smaller than (1, 1).
@@ -263,6 +514,7 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         device: &mut Device,
         context: &mut Device::Context,
         size: Size2D<i32>,
+        preserve_buffer: PreserveBuffer,
     ) -> Result<(), Error> {
         debug!(
             "Resizing context {:?} to {:?}",
@@ -275,9 +527,12 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         }
         let surface_type = SurfaceType::Generic { size };
         let new_back_buffer = device.create_surface(context, self.surface_access, surface_type)?;
+        let new_info = device.surface_info(&new_back_buffer);
         let mut old_back_buffer = self.back_buffer.take_surface(device, context)?;
         self.back_buffer
             .replace_surface(device, context, new_back_buffer)?;
+        let old_info = device.surface_info(&old_back_buffer);
+        self.copy_surface(device, context, &old_info, &new_info, preserve_buffer);
         device.destroy_surface(context, &mut old_back_buffer)?;
         self.size = size;
         Ok(())
@@ -335,6 +590,280 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         self.recycled_surfaces.push(surface)
     }
 
+    // Register a new consumer for broadcast, reading in the context with the given id.
+    // Called by a consumer.
+    fn register_consumer(&mut self, context_id: ContextID) -> ConsumerID {
+        let consumer = ConsumerID(self.next_consumer_id);
+        self.next_consumer_id += 1;
+        self.consumers.insert(consumer, context_id);
+        if let Some(surface) = self.pending_surface.take() {
+            // The size is only needed for memory reports, and a pending surface has the
+            // size of the swap chain unless it has been resized since.
+            let mut shared_surface = SharedSurface::new(self.size);
+            shared_surface
+                .copies
+                .insert(context_id, SharedSurfaceCopy::new(surface));
+            self.shared_surfaces.push(shared_surface);
+        }
+        consumer
+    }
+
+    // Unregister a consumer.
+    // A front buffer the consumer is still reading must still be released by it.
+    // Called by a consumer.
+    fn unregister_consumer(&mut self, consumer: ConsumerID) {
+        self.consumers.remove(&consumer);
+        self.retire_shared_surfaces();
+    }
+
+    // Acquire read access to the current front buffer on behalf of a registered consumer,
+    // releasing any older front buffer it is still reading.
+    // Returns the texture to read from, or `None` if there is no front buffer for the consumer's
+    // context yet.
+    // Returns an error if `context` is not the context the consumer registered in, or if another
+    // thread created the surface texture for that context.
+    // Called by a consumer.
+    fn acquire_front_buffer(
+        &mut self,
+        consumer: ConsumerID,
+        device: &Device,
+        context: &mut Device::Context,
+    ) -> Result<Option<Texture>, Error>
+    where
+        Device: 'static,
+    {
+        let context_id = match self.consumers.get(&consumer) {
+            Some(&context_id) => context_id,
+            None => return Err(Error::Failed),
+        };
+        if context_id != device.context_id(context) {
+            return Err(Error::IncompatibleContext);
+        }
+        let newest = match self.shared_surfaces.len().checked_sub(1) {
+            Some(newest) => newest,
+            None => return Ok(None),
+        };
+        let reading = self.shared_surfaces.iter().position(|shared_surface| {
+            shared_surface
+                .copies
+                .get(&context_id)
+                .is_some_and(|copy| copy.readers.contains(&consumer))
+        });
+        if reading.is_some() && reading != Some(newest) {
+            self.release_front_buffer(consumer, device, context)?;
+        }
+
+        let shared_surface = self.shared_surfaces.last_mut().unwrap();
+        let key = (shared_surface.id, context_id);
+        // Contexts whose first consumer registered after this front buffer was shared only get
+        // a copy of the next one.
+        let copy = match shared_surface.copies.get_mut(&context_id) {
+            Some(copy) => copy,
+            None => return Ok(None),
+        };
+        if let Some(surface) = copy.surface.take() {
+            debug!(
+                "Creating a surface texture for consumers in context {:?}",
+                context_id
+            );
+            match device.create_surface_texture(context, surface) {
+                Ok(surface_texture) => store_consumer_surface_texture(key, surface_texture),
+                Err((err, surface)) => {
+                    copy.surface = Some(surface);
+                    return Err(err);
+                }
+            }
+        }
+        let texture = with_consumer_surface_texture(key, |surface_texture| {
+            device.surface_texture_object(surface_texture)
+        })
+        .ok_or(Error::IncompatibleContext)?;
+        copy.readers.insert(consumer);
+        Ok(texture)
+    }
+
+    // Release the front buffer that a consumer acquired with `acquire_front_buffer`.
+    // The context and thread must be the ones the front buffer was acquired in.
+    // The front buffer is recycled once every consumer reading it has released it and a newer
+    // front buffer exists.
+    // Called by a consumer.
+    fn release_front_buffer(
+        &mut self,
+        consumer: ConsumerID,
+        device: &Device,
+        context: &mut Device::Context,
+    ) -> Result<(), Error>
+    where
+        Device: 'static,
+    {
+        let context_id = device.context_id(context);
+        let (id, copy_context_id, copy) =
+            match self.shared_surfaces.iter_mut().find_map(|shared_surface| {
+                let id = shared_surface.id;
+                shared_surface
+                    .copies
+                    .iter_mut()
+                    .find(|(_, copy)| copy.readers.contains(&consumer))
+                    .map(|(&copy_context_id, copy)| (id, copy_context_id, copy))
+            }) {
+                Some(reading) => reading,
+                None => return Ok(()),
+            };
+        if copy_context_id != context_id {
+            return Err(Error::IncompatibleContext);
+        }
+        copy.readers.remove(&consumer);
+        if copy.readers.is_empty() {
+            // The last reader destroys the surface texture, giving the surface back to the chain.
+            let key = (id, context_id);
+            let surface_texture = match take_consumer_surface_texture(key) {
+                Some(surface_texture) => surface_texture,
+                None => {
+                    copy.readers.insert(consumer);
+                    return Err(Error::IncompatibleContext);
+                }
+            };
+            match device.destroy_surface_texture(context, surface_texture) {
+                Ok(surface) => copy.surface = Some(surface),
+                Err((err, surface_texture)) => {
+                    store_consumer_surface_texture(key, surface_texture);
+                    copy.readers.insert(consumer);
+                    return Err(err);
+                }
+            }
+        }
+        self.retire_shared_surfaces();
+        Ok(())
+    }
+
+    // Make a surface the shared front buffer of the registered consumers.
+    // The first consumer context reads the surface itself, and every other one reads a copy.
+    // Called by the producer.
+    fn share_surface(
+        &mut self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface: Device::Surface,
+    ) -> Result<(), Error> {
+        let info = device.surface_info(&surface);
+        let context_ids: FnvHashSet<ContextID> = self.consumers.values().cloned().collect();
+        let mut shared_surface = SharedSurface::new(info.size);
+        let mut surface = Some(surface);
+        let mut result = Ok(());
+        for context_id in context_ids {
+            let copy = match surface.take() {
+                Some(surface) => surface,
+                None => match self.recycled_or_new_surface(device, context, info.size) {
+                    Ok(copy) => {
+                        let copy_info = device.surface_info(&copy);
+                        self.copy_surface(device, context, &info, &copy_info, PreserveBuffer::Yes);
+                        copy
+                    }
+                    Err(err) => {
+                        result = Err(err);
+                        break;
+                    }
+                },
+            };
+            shared_surface
+                .copies
+                .insert(context_id, SharedSurfaceCopy::new(copy));
+        }
+        self.shared_surfaces.push(shared_surface);
+        self.retire_shared_surfaces();
+        result
+    }
+
+    // Recycle the copies of shared front buffers that no consumer is reading, apart from the
+    // copies of the most recent one that registered consumers will read.
+    fn retire_shared_surfaces(&mut self) {
+        // Without any registered consumers, the front buffer goes back to being pending.
+        if self.consumers.is_empty() && self.pending_surface.is_none() {
+            let unread = self.shared_surfaces.last().is_some_and(|shared_surface| {
+                shared_surface
+                    .copies
+                    .values()
+                    .all(|copy| copy.readers.is_empty())
+            });
+            if unread {
+                let shared_surface = self.shared_surfaces.pop().unwrap();
+                let mut surfaces = shared_surface
+                    .copies
+                    .into_values()
+                    .filter_map(|copy| copy.surface);
+                self.pending_surface = surfaces.next();
+                self.recycled_surfaces.extend(surfaces);
+            }
+        }
+
+        let consumer_contexts: FnvHashSet<ContextID> = self.consumers.values().cloned().collect();
+        let newest = self.shared_surfaces.len().saturating_sub(1);
+        let recycled_surfaces = &mut self.recycled_surfaces;
+        for (index, shared_surface) in self.shared_surfaces.iter_mut().enumerate() {
+            shared_surface.copies.retain(|context_id, copy| {
+                let front = index == newest && consumer_contexts.contains(context_id);
+                if front || !copy.readers.is_empty() {
+                    return true;
+                }
+                recycled_surfaces.extend(copy.surface.take());
+                false
+            });
+        }
+        self.shared_surfaces
+            .retain(|shared_surface| !shared_surface.copies.is_empty());
+    }
+
+    // Report the surfaces held by the swap chain, and an estimate of their memory usage.
+    fn memory_report(&self, device: &Device) -> SwapChainMemoryReport {
+        let bytes =
+            |size: Size2D<i32>| size.width as usize * size.height as usize * self.bytes_per_pixel;
+        let recycled_sizes: Vec<_> = self
+            .recycled_surfaces
+            .iter()
+            .map(|surface| device.surface_info(surface).size)
+            .collect();
+        let back_buffer_size = match self.back_buffer {
+            BackBuffer::Attached => Some(self.size),
+            BackBuffer::Detached(ref surface) => Some(device.surface_info(surface).size),
+            BackBuffer::TakenAttached | BackBuffer::TakenDetached => None,
+        };
+        let sizes = back_buffer_size
+            .into_iter()
+            .chain(
+                self.pending_surface
+                    .iter()
+                    .map(|surface| device.surface_info(surface).size),
+            )
+            .chain(
+                self.shared_surfaces
+                    .iter()
+                    .flat_map(|shared| shared.copies.values().map(move |_| shared.size)),
+            )
+            .chain(recycled_sizes.iter().cloned());
+        let mut report = SwapChainMemoryReport {
+            recycled_surfaces: recycled_sizes.len(),
+            recycled_bytes: recycled_sizes.iter().cloned().map(bytes).sum(),
+            ..SwapChainMemoryReport::default()
+        };
+        for size in sizes {
+            report.surfaces += 1;
+            report.bytes += bytes(size);
+        }
+        report
+    }
+
+    // Destroy the idle recycled surfaces.
+    // Called by the producer.
+    // Returns an error if `context` is not the producer context for this swap chain.
+    fn trim(&mut self, device: &mut Device, context: &mut Device::Context) -> Result<(), Error> {
+        self.validate_context(device, context)?;
+        for mut surface in self.recycled_surfaces.drain(..) {
+            debug!("Trimming a surface for context {:?}", self.context_id);
+            device.destroy_surface(context, &mut surface)?;
+        }
+        Ok(())
+    }
+
     // Clear the current back buffer.
     // Called by the producer.
     // Returns an error if `context` is not the producer context for this swap chain.
@@ -348,27 +877,27 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         self.validate_context(device, context)?;
 
         // Save the current GL state
-        let draw_fbo;
-        let read_fbo;
-        let mut clear_color = [0., 0., 0., 0.];
-        let mut clear_depth = [0.];
-        let mut clear_stencil = [0];
-        let color_mask;
-        let depth_mask;
-        let mut stencil_mask = [0];
-        let scissor_enabled = unsafe { gl.is_enabled(gl::SCISSOR_TEST) };
-        let rasterizer_enabled = unsafe { gl.is_enabled(gl::RASTERIZER_DISCARD) };
-        unsafe {
-            draw_fbo = gl.get_parameter_framebuffer(gl::DRAW_FRAMEBUFFER_BINDING);
-            read_fbo = gl.get_parameter_framebuffer(gl::READ_FRAMEBUFFER_BINDING);
-            gl.get_parameter_f32_slice(gl::COLOR_CLEAR_VALUE, &mut clear_color[..]);
-            gl.get_parameter_f32_slice(gl::DEPTH_CLEAR_VALUE, &mut clear_depth[..]);
-            gl.get_parameter_i32_slice(gl::STENCIL_CLEAR_VALUE, &mut clear_stencil[..]);
-            depth_mask = gl.get_parameter_bool(gl::DEPTH_WRITEMASK);
-            gl.get_parameter_i32_slice(gl::STENCIL_WRITEMASK, &mut stencil_mask[..]);
-            color_mask = gl.get_parameter_bool_array::<4>(gl::COLOR_WRITEMASK);
-        }
+        let state = GLStateSnapshot::capture(
+            gl,
+            GLStateGroups::FRAMEBUFFER_BINDINGS
+                | GLStateGroups::CLEAR_VALUES
+                | GLStateGroups::WRITE_MASKS
+                | GLStateGroups::SCISSOR
+                | GLStateGroups::RASTERIZER_DISCARD,
+        );
+        let result = self.clear_back_buffer(device, context, gl, color);
+        state.restore(gl);
+        result
+    }
 
+    // Clear the back buffer, without preserving GL state.
+    fn clear_back_buffer(
+        &mut self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        gl: &Gl,
+        color: [f32; 4],
+    ) -> Result<(), Error> {
         // Make the back buffer the current surface
         let reattach = if self.is_attached() {
             None
@@ -417,29 +946,6 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
                 .replace_surface(device, context, old_surface)?;
         }
 
-        // Restore the GL state
-        unsafe {
-            gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, draw_fbo);
-            gl.bind_framebuffer(gl::READ_FRAMEBUFFER, read_fbo);
-            gl.clear_color(
-                clear_color[0],
-                clear_color[1],
-                clear_color[2],
-                clear_color[3],
-            );
-            gl.color_mask(color_mask[0], color_mask[1], color_mask[2], color_mask[3]);
-            gl.clear_depth(clear_depth[0] as f64);
-            gl.clear_stencil(clear_stencil[0]);
-            gl.depth_mask(depth_mask);
-            gl.stencil_mask(stencil_mask[0] as _);
-            if scissor_enabled {
-                gl.enable(gl::SCISSOR_TEST);
-            }
-            if rasterizer_enabled {
-                gl.enable(gl::RASTERIZER_DISCARD);
-            }
-        }
-
         Ok(())
     }
 
@@ -454,14 +960,32 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
     // Destroy the swap chain.
     // Called by the producer.
     // Returns an error if `context` is not the producer context for this swap chain.
+    // Returns an error if consumers are still reading a front buffer.
     fn destroy(&mut self, device: &mut Device, context: &mut Device::Context) -> Result<(), Error> {
         self.validate_context(device, context)?;
+        // Only the consumers can destroy the surface textures they read through.
+        let reading = self.shared_surfaces.iter().any(|shared_surface| {
+            shared_surface
+                .copies
+                .values()
+                .any(|copy| !copy.readers.is_empty())
+        });
+        if reading {
+            return Err(Error::Failed);
+        }
         let surfaces = self
             .pending_surface
             .take()
             .into_iter()
             .chain(self.back_buffer.take_surface(device, context).into_iter())
-            .chain(self.recycled_surfaces.drain(..));
+            .chain(self.recycled_surfaces.drain(..))
+            .chain(
+                self.shared_surfaces
+                    .drain(..)
+                    .flat_map(|shared_surface| shared_surface.copies.into_values())
+                    .filter_map(|copy| copy.surface),
+            )
+            .collect::<Vec<_>>();
         for mut surface in surfaces {
             device.destroy_surface(context, &mut surface)?;
         }
@@ -481,20 +1005,23 @@ impl<Device: DeviceAPI> Clone for SwapChain<Device> {
 
 impl<Device: DeviceAPI> SwapChain<Device> {
     // Guarantee unique access to the swap chain data
-    fn lock(&self) -> MutexGuard<SwapChainData<Device>> {
+    fn lock(&self) -> MutexGuard<'_, SwapChainData<Device>> {
         self.0.lock().unwrap_or_else(|err| err.into_inner())
     }
 
     /// Swap the back and front buffers.
+    /// If the swap chain belongs to a collection with a memory budget that is exceeded, the
+    /// idle recycled surfaces of the collection's swap chains for `context` are then destroyed.
     /// Called by the producer.
     /// Returns an error if `context` is not the producer context for this swap chain.
     pub fn swap_buffers(
         &self,
         device: &mut Device,
         context: &mut Device::Context,
-        preserve_buffer: PreserveBuffer<'_>,
+        preserve_buffer: PreserveBuffer,
     ) -> Result<(), Error> {
-        self.lock().swap_buffers(device, context, preserve_buffer)
+        self.lock().swap_buffers(device, context, preserve_buffer)?;
+        self.enforce_memory_budget(device, context)
     }
 
     /// Swap the attached swap chain.
@@ -513,7 +1040,9 @@ impl<Device: DeviceAPI> SwapChain<Device> {
 
     /// Resize the swap chain.
     /// This creates a new back buffer of the appropriate size,
-    /// and destroys the old one.
+    /// optionally copies the old contents into it, and destroys the old one.
+    /// If the swap chain belongs to a collection with a memory budget, the budget is then
+    /// enforced, as it is after swapping buffers.
     /// Called by the producer.
     /// Returns an error if `context` is not the producer context for this swap chain.
     pub fn resize(
@@ -521,8 +1050,27 @@ impl<Device: DeviceAPI> SwapChain<Device> {
         device: &mut Device,
         context: &mut Device::Context,
         size: Size2D<i32>,
+        preserve_buffer: PreserveBuffer,
+    ) -> Result<(), Error> {
+        self.lock().resize(device, context, size, preserve_buffer)?;
+        self.enforce_memory_budget(device, context)
+    }
+
+    // Trim the swap chains of the collection this swap chain belongs to, if it has a memory
+    // budget and exceeds it. This must be called without the swap chain locked, since trimming
+    // locks every swap chain in the collection.
+    fn enforce_memory_budget(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
     ) -> Result<(), Error> {
-        self.lock().resize(device, context, size)
+        let collection = self.lock().collection.clone();
+        if let Some(collection) = collection {
+            if !collection.trim_to_memory_budget(device, context)? {
+                debug!("Swap chains still exceed their memory budget after trimming");
+            }
+        }
+        Ok(())
     }
 
     /// Get the current size.
@@ -581,10 +1129,24 @@ impl<Device: DeviceAPI> SwapChain<Device> {
     /// Destroy the swap chain.
     /// Called by the producer.
     /// Returns an error if `context` is not the producer context for this swap chain.
+    /// Returns an error if broadcast consumers are still reading a front buffer, which they must
+    /// release first.
     pub fn destroy(&self, device: &mut Device, context: &mut Device::Context) -> Result<(), Error> {
         self.lock().destroy(device, context)
     }
 
+    /// Report the surfaces held by the swap chain, and an estimate of their memory usage.
+    pub fn memory_report(&self, device: &Device) -> SwapChainMemoryReport {
+        self.lock().memory_report(device)
+    }
+
+    /// Destroy the idle recycled surfaces, without otherwise affecting the swap chain.
+    /// Called by the producer.
+    /// Returns an error if `context` is not the producer context for this swap chain.
+    pub fn trim(&self, device: &mut Device, context: &mut Device::Context) -> Result<(), Error> {
+        self.lock().trim(device, context)
+    }
+
     /// Create a new attached swap chain
     pub fn create_attached(
         device: &mut Device,
@@ -592,6 +1154,7 @@ impl<Device: DeviceAPI> SwapChain<Device> {
         surface_access: SurfaceAccess,
     ) -> Result<SwapChain<Device>, Error> {
         let size = device.context_surface_info(context).unwrap().unwrap().size;
+        let bytes_per_pixel = bytes_per_pixel(device, context);
         Ok(SwapChain(Arc::new(Mutex::new(SwapChainData {
             size,
             context_id: device.context_id(context),
@@ -599,6 +1162,11 @@ impl<Device: DeviceAPI> SwapChain<Device> {
             back_buffer: BackBuffer::Attached,
             pending_surface: None,
             recycled_surfaces: Vec::new(),
+            consumers: FnvHashMap::default(),
+            next_consumer_id: 0,
+            shared_surfaces: Vec::new(),
+            bytes_per_pixel,
+            collection: None,
         }))))
     }
 
@@ -611,6 +1179,7 @@ impl<Device: DeviceAPI> SwapChain<Device> {
     ) -> Result<SwapChain<Device>, Error> {
         let surface_type = SurfaceType::Generic { size };
         let surface = device.create_surface(context, surface_access, surface_type)?;
+        let bytes_per_pixel = bytes_per_pixel(device, context);
         Ok(SwapChain(Arc::new(Mutex::new(SwapChainData {
             size,
             context_id: device.context_id(context),
@@ -618,10 +1187,31 @@ impl<Device: DeviceAPI> SwapChain<Device> {
             back_buffer: BackBuffer::Detached(surface),
             pending_surface: None,
             recycled_surfaces: Vec::new(),
+            consumers: FnvHashMap::default(),
+            next_consumer_id: 0,
+            shared_surfaces: Vec::new(),
+            bytes_per_pixel,
+            collection: None,
         }))))
     }
 }
 
+// Estimate the size of one pixel of the surfaces created for a context, in bytes.
+fn bytes_per_pixel<Device: DeviceAPI>(device: &Device, context: &Device::Context) -> usize {
+    let flags = device
+        .context_descriptor_attributes(&device.context_descriptor(context))
+        .flags;
+    // Color is always stored as RGBA8, and depth as DEPTH24 (packed with stencil if present).
+    let depth_stencil = if flags.contains(ContextAttributeFlags::DEPTH) {
+        4
+    } else if flags.contains(ContextAttributeFlags::STENCIL) {
+        1
+    } else {
+        0
+    };
+    4 + depth_stencil
+}
+
 impl<Device> SwapChainAPI for SwapChain<Device>
 where
     Device: 'static + DeviceAPI,
@@ -643,6 +1233,94 @@ where
     }
 }
 
+impl<Device> BroadcastSwapChainAPI for SwapChain<Device>
+where
+    Device: 'static + DeviceAPI,
+    Device::Surface: Send,
+{
+    type Device = Device;
+    type Context = Device::Context;
+
+    /// Register a new consumer that reads in `context`, switching the swap chain to broadcast
+    /// its front buffer.
+    /// The producer copies each front buffer for every consumer context when it swaps buffers,
+    /// so consumers in a context that didn't have any yet only see the next front buffer.
+    /// Called by a consumer.
+    fn register_consumer(&self, device: &Device, context: &Device::Context) -> ConsumerID {
+        self.lock().register_consumer(device.context_id(context))
+    }
+
+    /// Unregister a consumer.
+    /// A front buffer the consumer is still reading must still be released by it.
+    /// Called by a consumer.
+    fn unregister_consumer(&self, consumer: ConsumerID) {
+        self.lock().unregister_consumer(consumer)
+    }
+
+    /// Acquire read access to the current front buffer on behalf of a registered consumer,
+    /// releasing any older front buffer it is still reading.
+    /// Returns the texture to read from, or `None` if there is no front buffer for the
+    /// consumer's context yet.
+    /// Returns an error if `context` is not the context the consumer registered in.
+    /// Consumers in the same context share a surface texture, so they must all read on the same
+    /// thread.
+    /// Called by a consumer.
+    fn acquire_front_buffer(
+        &self,
+        consumer: ConsumerID,
+        device: &Device,
+        context: &mut Device::Context,
+    ) -> Result<Option<Texture>, Error> {
+        self.lock().acquire_front_buffer(consumer, device, context)
+    }
+
+    /// Release the front buffer acquired with `acquire_front_buffer`.
+    /// The context and thread must be the ones the front buffer was acquired in.
+    /// Called by a consumer.
+    fn release_front_buffer(
+        &self,
+        consumer: ConsumerID,
+        device: &Device,
+        context: &mut Device::Context,
+    ) -> Result<(), Error> {
+        self.lock().release_front_buffer(consumer, device, context)
+    }
+}
+
+// A weak reference to a collection of swap chains, held by the swap chains in it.
+struct WeakSwapChains<SwapChainID: Eq + Hash, Device: DeviceAPI> {
+    ids: Weak<Mutex<FnvHashMap<ContextID, FnvHashSet<SwapChainID>>>>,
+    table: Weak<RwLock<FnvHashMap<SwapChainID, SwapChain<Device>>>>,
+    memory_budget: Weak<Mutex<Option<usize>>>,
+}
+
+impl<SwapChainID, Device> SwapChainCollection<Device> for WeakSwapChains<SwapChainID, Device>
+where
+    SwapChainID: Clone + Eq + Hash + Debug + Send + Sync,
+    Device: DeviceAPI,
+    Device::Surface: Send,
+{
+    fn trim_to_memory_budget(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
+    ) -> Result<bool, Error> {
+        match (
+            self.ids.upgrade(),
+            self.table.upgrade(),
+            self.memory_budget.upgrade(),
+        ) {
+            (Some(ids), Some(table), Some(memory_budget)) => SwapChains {
+                ids,
+                table,
+                memory_budget,
+            }
+            .trim_to_memory_budget(device, context),
+            _ => Ok(true),
+        }
+    }
+}
+
 /// A thread-safe collection of swap chains.
 #[derive(Default)]
 pub struct SwapChains<SwapChainID: Eq + Hash, Device: DeviceAPI> {
@@ -650,6 +1328,8 @@ pub struct SwapChains<SwapChainID: Eq + Hash, Device: DeviceAPI> {
     ids: Arc<Mutex<FnvHashMap<ContextID, FnvHashSet<SwapChainID>>>>,
     // The swap chains, indexed by swap chain id
     table: Arc<RwLock<FnvHashMap<SwapChainID, SwapChain<Device>>>>,
+    // The memory budget for all the swap chains, in bytes
+    memory_budget: Arc<Mutex<Option<usize>>>,
 }
 
 // We can't derive Clone unfortunately
@@ -658,6 +1338,7 @@ impl<SwapChainID: Eq + Hash, Device: DeviceAPI> Clone for SwapChains<SwapChainID
         SwapChains {
             ids: self.ids.clone(),
             table: self.table.clone(),
+            memory_budget: self.memory_budget.clone(),
         }
     }
 }
@@ -672,24 +1353,40 @@ where
         SwapChains {
             ids: Arc::new(Mutex::new(FnvHashMap::default())),
             table: Arc::new(RwLock::new(FnvHashMap::default())),
+            memory_budget: Arc::new(Mutex::new(None)),
         }
     }
 
     // Lock the ids
-    fn ids(&self) -> MutexGuard<FnvHashMap<ContextID, FnvHashSet<SwapChainID>>> {
+    fn ids(&self) -> MutexGuard<'_, FnvHashMap<ContextID, FnvHashSet<SwapChainID>>> {
         self.ids.lock().unwrap_or_else(|err| err.into_inner())
     }
 
     // Lock the lookup table
-    fn table(&self) -> RwLockReadGuard<FnvHashMap<SwapChainID, SwapChain<Device>>> {
+    fn table(&self) -> RwLockReadGuard<'_, FnvHashMap<SwapChainID, SwapChain<Device>>> {
         self.table.read().unwrap_or_else(|err| err.into_inner())
     }
 
     // Lock the lookup table for writing
-    fn table_mut(&self) -> RwLockWriteGuard<FnvHashMap<SwapChainID, SwapChain<Device>>> {
+    fn table_mut(&self) -> RwLockWriteGuard<'_, FnvHashMap<SwapChainID, SwapChain<Device>>> {
         self.table.write().unwrap_or_else(|err| err.into_inner())
     }
 
+    // Make a swap chain part of this collection, so that it enforces the memory budget.
+    fn adopt(&self, swap_chain: SwapChain<Device>) -> SwapChain<Device>
+    where
+        SwapChainID: 'static + Send + Sync,
+        Device: 'static,
+        Device::Surface: Send,
+    {
+        swap_chain.lock().collection = Some(Arc::new(WeakSwapChains {
+            ids: Arc::downgrade(&self.ids),
+            table: Arc::downgrade(&self.table),
+            memory_budget: Arc::downgrade(&self.memory_budget),
+        }));
+        swap_chain
+    }
+
     /// Create a new attached swap chain and insert it in the table.
     /// Returns an error if the `id` is already in the table.
     pub fn create_attached_swap_chain(
@@ -698,12 +1395,19 @@ where
         device: &mut Device,
         context: &mut Device::Context,
         surface_access: SurfaceAccess,
-    ) -> Result<(), Error> {
+    ) -> Result<(), Error>
+    where
+        SwapChainID: 'static + Send + Sync,
+        Device: 'static,
+        Device::Surface: Send,
+    {
         match self.table_mut().entry(id.clone()) {
             Entry::Occupied(_) => Err(Error::Failed)?,
-            Entry::Vacant(entry) => {
-                entry.insert(SwapChain::create_attached(device, context, surface_access)?)
-            }
+            Entry::Vacant(entry) => entry.insert(self.adopt(SwapChain::create_attached(
+                device,
+                context,
+                surface_access,
+            )?)),
         };
         self.ids()
             .entry(device.context_id(context))
@@ -721,15 +1425,20 @@ where
         device: &mut Device,
         context: &mut Device::Context,
         surface_access: SurfaceAccess,
-    ) -> Result<(), Error> {
+    ) -> Result<(), Error>
+    where
+        SwapChainID: 'static + Send + Sync,
+        Device: 'static,
+        Device::Surface: Send,
+    {
         match self.table_mut().entry(id.clone()) {
             Entry::Occupied(_) => Err(Error::Failed)?,
-            Entry::Vacant(entry) => entry.insert(SwapChain::create_detached(
+            Entry::Vacant(entry) => entry.insert(self.adopt(SwapChain::create_detached(
                 device,
                 context,
                 surface_access,
                 size,
-            )?),
+            )?)),
         };
         self.ids()
             .entry(device.context_id(context))
@@ -773,6 +1482,72 @@ where
         Ok(())
     }
 
+    /// Set the memory budget for all the swap chains, in bytes.
+    /// The budget is enforced whenever one of the swap chains swaps buffers or is resized, by
+    /// trimming the swap chains for its producer context, and by `trim_to_memory_budget`.
+    pub fn set_memory_budget(&self, memory_budget: Option<usize>) {
+        *self
+            .memory_budget
+            .lock()
+            .unwrap_or_else(|err| err.into_inner()) = memory_budget;
+    }
+
+    /// Get the memory budget for all the swap chains, in bytes.
+    pub fn memory_budget(&self) -> Option<usize> {
+        *self
+            .memory_budget
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Report the surfaces held by each swap chain, and an estimate of their memory usage.
+    pub fn memory_reports(&self, device: &Device) -> Vec<(SwapChainID, SwapChainMemoryReport)> {
+        self.table()
+            .iter()
+            .map(|(id, swap_chain)| (id.clone(), swap_chain.memory_report(device)))
+            .collect()
+    }
+
+    /// Destroy the idle recycled surfaces of all the swap chains for a particular producer
+    /// context, without destroying the swap chains themselves.
+    /// Called by the producer.
+    pub fn trim(&self, device: &mut Device, context: &mut Device::Context) -> Result<(), Error> {
+        for (_, swap_chain) in self.iter(device, context) {
+            swap_chain.trim(device, context)?;
+        }
+        Ok(())
+    }
+
+    /// If all the swap chains together exceed the memory budget, trim the swap chains for
+    /// a particular producer context.
+    /// Returns whether the swap chains are within the memory budget afterwards.
+    /// Called by the producer.
+    pub fn trim_to_memory_budget(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
+    ) -> Result<bool, Error> {
+        let memory_budget = match self.memory_budget() {
+            Some(memory_budget) => memory_budget,
+            None => return Ok(true),
+        };
+        let total_bytes = |device: &Device| -> usize {
+            self.memory_reports(device)
+                .iter()
+                .map(|(_, report)| report.bytes)
+                .sum()
+        };
+        if total_bytes(device) <= memory_budget {
+            return Ok(true);
+        }
+        debug!(
+            "Swap chains exceed memory budget of {} bytes",
+            memory_budget
+        );
+        self.trim(device, context)?;
+        Ok(total_bytes(device) <= memory_budget)
+    }
+
     /// Iterate over all the swap chains for a particular producer context.
     /// Called by the producer.
     pub fn iter(
@@ -817,6 +1592,35 @@ pub trait SwapChainAPI: 'static + Clone + Send {
     fn recycle_surface(&self, surface: Self::Surface);
 }
 
+/// The consumers' view of a swap chain that broadcasts its front buffer
+pub trait BroadcastSwapChainAPI: SwapChainAPI {
+    type Device;
+    type Context;
+
+    /// Register a new consumer that reads in `context`, switching the swap chain to broadcast
+    /// its front buffer.
+    fn register_consumer(&self, device: &Self::Device, context: &Self::Context) -> ConsumerID;
+
+    /// Unregister a consumer.
+    fn unregister_consumer(&self, consumer: ConsumerID);
+
+    /// Acquire read access to the current front buffer on behalf of a registered consumer.
+    fn acquire_front_buffer(
+        &self,
+        consumer: ConsumerID,
+        device: &Self::Device,
+        context: &mut Self::Context,
+    ) -> Result<Option<Texture>, Error>;
+
+    /// Release the front buffer acquired with `acquire_front_buffer`.
+    fn release_front_buffer(
+        &self,
+        consumer: ConsumerID,
+        device: &Self::Device,
+        context: &mut Self::Context,
+    ) -> Result<(), Error>;
+}
+
 /// The consumer's view of a collection of swap chains
 pub trait SwapChainsAPI<SwapChainID>: 'static + Clone + Send {
     type Surface;
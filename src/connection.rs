@@ -85,3 +85,16 @@ pub trait Connection: Sized {
         size: Size2D<i32>,
     ) -> Result<Self::NativeWidget, Error>;
 }
+
+/// A connection whose devices can create either OpenGL or OpenGL ES contexts.
+///
+/// This is implemented by the EGL-based Unix backends, and by `multi::Connection` when both of its
+/// backends implement it.
+#[cfg(free_unix)]
+pub trait GLApiConnection: Connection {
+    /// Returns this connection with the OpenGL API flavor that its devices create contexts with
+    /// set to `gl_api`.
+    ///
+    /// Devices that have already been created from this connection are unaffected.
+    fn with_gl_api(self, gl_api: GLApi) -> Self;
+}
@@ -4,11 +4,13 @@
 
 #![allow(unused_imports)]
 
+use crate::device::Device as DeviceInterface;
 use crate::gl;
 use crate::info::GLVersion;
-use crate::Gl;
+use crate::{Error, Gl};
 
 use std::ffi::CStr;
+use std::ops::Deref;
 use std::os::raw::c_char;
 use std::sync::Mutex;
 
@@ -43,9 +45,42 @@ bitflags! {
         /// The OpenGL compatibility profile will be used. If this is not present, the core profile
         /// is used.
         const COMPATIBILITY_PROFILE = 0x08;
+        /// The context is created without a framebuffer configuration, so that it can render to
+        /// surfaces of differing formats (see `Device::create_surface_with_attributes()`). The
+        /// alpha, depth, and stencil flags then only provide the default format for surfaces.
+        ///
+        /// On EGL, this requires `EGL_KHR_no_config_context`; if it is unavailable, creating the
+        /// context descriptor fails with `RequiredExtensionUnavailable`. Other backends ignore
+        /// this flag.
+        const NO_CONFIG             = 0x10;
+        /// The context is created without error checking, so invalid OpenGL calls have undefined
+        /// behavior instead of generating errors. This reduces driver overhead in release builds.
+        ///
+        /// This maps to `EGL_KHR_create_context_no_error` on EGL and
+        /// `WGL_ARB_create_context_no_error` on WGL, which enable `GL_KHR_no_error`. Where these
+        /// are unsupported, the flag is ignored with a warning, and the attributes of the
+        /// resulting context descriptor don't include it. No-error contexts can't be debug or
+        /// robust contexts, which `surfman` never requests.
+        const NO_ERROR              = 0x20;
     }
 }
 
+/// The scheduling priority of a context relative to other contexts on the same GPU.
+///
+/// This maps to `EGL_IMG_context_priority`. Drivers are free to grant a lower priority than the
+/// one requested, so check `Device::context_descriptor()` to find out which priority a context
+/// actually has. Where the extension is unavailable, all contexts have medium priority.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ContextPriority {
+    /// The context yields to contexts of higher priority. Suitable for background work.
+    Low,
+    /// The default priority.
+    #[default]
+    Medium,
+    /// The context is preferred over contexts of lower priority. Suitable for compositors.
+    High,
+}
+
 /// Attributes that control aspects of a context and/or surfaces created from that context.
 ///
 /// Similar to: <https://www.khronos.org/registry/webgl/specs/latest/1.0/#WEBGLCONTEXTATTRIBUTES>
@@ -58,6 +93,8 @@ pub struct ContextAttributes {
     pub version: GLVersion,
     /// Various flags.
     pub flags: ContextAttributeFlags,
+    /// The scheduling priority requested for the context.
+    pub priority: ContextPriority,
 }
 
 impl ContextAttributes {
@@ -66,10 +103,139 @@ impl ContextAttributes {
         ContextAttributes {
             version: GLVersion::new(0, 0),
             flags: ContextAttributeFlags::empty(),
+            priority: ContextPriority::Medium,
+        }
+    }
+
+    /// Returns these attributes with the surface format flags (`ALPHA`, `DEPTH`, and `STENCIL`)
+    /// replaced by those in `surface_flags`.
+    #[allow(dead_code)]
+    pub(crate) fn with_surface_flags(
+        mut self,
+        surface_flags: ContextAttributeFlags,
+    ) -> ContextAttributes {
+        let surface_format_flags = ContextAttributeFlags::ALPHA
+            | ContextAttributeFlags::DEPTH
+            | ContextAttributeFlags::STENCIL;
+        self.flags.remove(surface_format_flags);
+        self.flags.insert(surface_flags & surface_format_flags);
+        self
+    }
+}
+
+/// The OpenGL function table of a context, returned by `Device::context_gl()`.
+///
+/// The context is current for as long as this value is alive. When it is dropped, whichever
+/// context was current before is made current again.
+pub struct ContextGL<'a, Guard> {
+    gl: &'a Gl,
+    _guard: Guard,
+}
+
+impl<'a, Guard> ContextGL<'a, Guard> {
+    // The context must have been made current with `guard`.
+    pub(crate) fn new(gl: &'a Gl, guard: Guard) -> ContextGL<'a, Guard> {
+        ContextGL { gl, _guard: guard }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn map_guard<F, NewGuard>(self, f: F) -> ContextGL<'a, NewGuard>
+    where
+        F: FnOnce(Guard) -> NewGuard,
+    {
+        ContextGL {
+            gl: self.gl,
+            _guard: f(self._guard),
         }
     }
 }
 
+impl<Guard> Deref for ContextGL<'_, Guard> {
+    type Target = Gl;
+
+    #[inline]
+    fn deref(&self) -> &Gl {
+        self.gl
+    }
+}
+
+// Creates a descriptor and a context with the given attributes.
+pub(crate) fn create_context_with_attributes<D>(
+    device: &mut D,
+    attributes: &ContextAttributes,
+) -> Result<(D::ContextDescriptor, D::Context), Error>
+where
+    D: DeviceInterface,
+{
+    let descriptor = device.create_context_descriptor(attributes)?;
+    let context = device.create_context(&descriptor, None)?;
+    Ok((descriptor, context))
+}
+
+// Returns true if the error means that the device doesn't support the requested attributes, as
+// opposed to something having gone wrong.
+pub(crate) fn attributes_unsupported(error: &Error) -> bool {
+    matches!(
+        *error,
+        Error::UnsupportedGLProfile
+            | Error::UnsupportedGLVersion
+            | Error::RequiredExtensionUnavailable
+            | Error::ContextCreationFailed(_)
+    )
+}
+
+// Returns a descriptor for the first of `candidates` that a context can actually be created with.
+//
+// Creating and destroying contexts changes the current context, so callers must save the current
+// context beforehand and restore it afterward.
+pub(crate) fn negotiate_context_descriptor<D>(
+    device: &mut D,
+    candidates: &[ContextAttributes],
+) -> Result<D::ContextDescriptor, Error>
+where
+    D: DeviceInterface,
+{
+    let mut last_error = Error::UnsupportedGLVersion;
+    for attributes in candidates {
+        match create_context_with_attributes(device, attributes) {
+            Ok((descriptor, mut context)) => {
+                device.destroy_context(&mut context)?;
+                return Ok(descriptor);
+            }
+            Err(error) if attributes_unsupported(&error) => last_error = error,
+            Err(error) => return Err(error),
+        }
+    }
+    Err(last_error)
+}
+
+// Returns true if the current context was created without error checking.
+//
+// This can only tell on newer versions, so backends record the flag for the contexts they create
+// and only fall back to this for foreign contexts.
+#[allow(dead_code)]
+pub(crate) fn current_context_has_no_error(gl: &Gl) -> bool {
+    use glow::HasContext;
+
+    // `GL_CONTEXT_FLAGS` is missing before OpenGL 3.0 and OpenGL ES 3.2. Querying it anyway would
+    // be undefined behavior in a context without error checking, and checking for the resulting
+    // error would swallow any error that the application hasn't read yet.
+    let version = GLVersion::current(gl);
+    let minimum_version = if gl.version().is_embedded {
+        (3, 2)
+    } else {
+        (3, 0)
+    };
+    if (version.major, version.minor) < minimum_version {
+        return false;
+    }
+
+    unsafe {
+        let context_flags = gl.get_parameter_i32(gl::CONTEXT_FLAGS);
+        (context_flags & gl::CONTEXT_FLAG_NO_ERROR_BIT as i32) != 0
+    }
+}
+
 #[cfg(any(target_os = "android", target_env = "ohos"))]
 pub(crate) fn current_context_uses_compatibility_profile(_gl: &Gl) -> bool {
     false
@@ -3,7 +3,13 @@
 //! The abstract interface that all devices conform to.
 
 use super::connection::Connection as ConnectionInterface;
-use crate::{ContextAttributes, ContextID, Error, GLApi, SurfaceAccess, SurfaceInfo, SurfaceType};
+#[cfg(free_unix)]
+use crate::platform::generic::egl::surface_handle::SurfaceHandle;
+use crate::{
+    ContextAttributeFlags, ContextAttributes, ContextGL, ContextID, DeviceCapabilities, Error,
+    GLApi,
+};
+use crate::{SurfaceAccess, SurfaceInfo, SurfaceType};
 use euclid::default::Size2D;
 use glow::Texture;
 
@@ -22,6 +28,8 @@ where
     type Context;
     /// The context descriptor type associated with this device.
     type ContextDescriptor;
+    /// The guard type returned by `make_context_current_scoped()`.
+    type CurrentContextGuard;
     /// The native context type associated with this device.
     type NativeContext;
     /// The surface type associated with this device.
@@ -43,6 +51,13 @@ where
     /// Returns the OpenGL API flavor that this device supports (OpenGL or OpenGL ES).
     fn gl_api(&self) -> GLApi;
 
+    /// Returns the OpenGL versions, limits, surface formats, and extensions that this device
+    /// supports.
+    ///
+    /// This creates and destroys temporary contexts, so it is relatively expensive; callers
+    /// should cache the result. Whatever context was current before the call remains current.
+    fn capabilities(&mut self) -> Result<DeviceCapabilities, Error>;
+
     // context.rs
 
     /// Creates a context descriptor with the given attributes.
@@ -53,6 +68,21 @@ where
         attributes: &ContextAttributes,
     ) -> Result<Self::ContextDescriptor, Error>;
 
+    /// Creates a context descriptor for the first of `candidates` that this device can actually
+    /// create a context with.
+    ///
+    /// Candidates are tried in order, so list them from most to least preferred, typically with
+    /// the highest version first. Call `context_descriptor_attributes()` on the result to find out
+    /// which candidate was chosen.
+    ///
+    /// This creates and destroys a temporary context for each candidate it tries. Whatever context
+    /// was current before the call remains current. If no candidate works, the error from the last
+    /// one is returned.
+    fn negotiate_context_descriptor(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<Self::ContextDescriptor, Error>;
+
     /// Creates a new OpenGL context and makes it current.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -82,6 +112,9 @@ where
     /// Makes the context the current OpenGL context for this thread.
     ///
     /// After calling this function, it is valid to use OpenGL rendering commands.
+    ///
+    /// This doesn't drain any `owned::DestructionQueue` holding the context's surfaces; use
+    /// `OwnedContext::make_current()`, or drain the queue explicitly.
     fn make_context_current(&self, context: &Self::Context) -> Result<(), Error>;
 
     /// Removes the current OpenGL context from this thread.
@@ -90,6 +123,18 @@ where
     /// made current.
     fn make_no_context_current(&self) -> Result<(), Error>;
 
+    /// Makes the context the current OpenGL context for this thread until the returned guard is
+    /// dropped.
+    ///
+    /// When the guard is dropped, whichever context and draw/read surfaces were current before
+    /// this call are made current again, even if they weren't created by `surfman`. This makes it
+    /// safe to issue OpenGL commands from inside a host application that has its own current
+    /// context.
+    fn make_context_current_scoped(
+        &self,
+        context: &Self::Context,
+    ) -> Result<Self::CurrentContextGuard, Error>;
+
     /// Returns the attributes that the context descriptor was created with.
     fn context_descriptor_attributes(
         &self,
@@ -105,6 +150,17 @@ where
     /// load OpenGL function pointers.
     fn get_proc_address(&self, context: &Self::Context, symbol_name: &str) -> *const c_void;
 
+    /// Makes the context current and returns the OpenGL function table that surfman loaded for
+    /// it.
+    ///
+    /// This lets applications share surfman's function pointers instead of loading their own with
+    /// `get_proc_address`. The context stays current until the returned value is dropped, at which
+    /// point whichever context was current before is made current again.
+    fn context_gl<'a>(
+        &self,
+        context: &'a Self::Context,
+    ) -> Result<ContextGL<'a, Self::CurrentContextGuard>, Error>;
+
     /// Attaches a surface to a context for rendering.
     ///
     /// This function takes ownership of the surface. The surface must have been created with this
@@ -130,6 +186,31 @@ where
         context: &mut Self::Context,
     ) -> Result<Option<Self::Surface>, Error>;
 
+    /// Attaches a surface to a context for reading, separately from the surface it renders to.
+    ///
+    /// While a read surface is bound, the context reads from it and draws to the surface bound
+    /// with `bind_surface_to_context`, so one surface can be copied into another with
+    /// `glBlitFramebuffer` or `glCopyTexImage2D`. For generic surfaces, the read and draw surfaces'
+    /// framebuffer objects are bound to `GL_READ_FRAMEBUFFER` and `GL_DRAW_FRAMEBUFFER` whenever
+    /// the context is made current or the read surface is bound or unbound.
+    ///
+    /// The surface must have been created with this context, or an `IncompatibleSurface` error is
+    /// returned. If a read surface is already bound, a `SurfaceAlreadyBound` error is returned.
+    /// Backends that can't read from a separate surface return `UnsupportedOnThisPlatform`.
+    ///
+    /// If an error is returned, the surface is returned alongside it.
+    fn bind_read_surface_to_context(
+        &self,
+        context: &mut Self::Context,
+        surface: Self::Surface,
+    ) -> Result<(), (Error, Self::Surface)>;
+
+    /// Removes and returns any read surface attached to this context.
+    fn unbind_read_surface_from_context(
+        &self,
+        context: &mut Self::Context,
+    ) -> Result<Option<Self::Surface>, Error>;
+
     /// Returns a unique ID representing a context.
     ///
     /// This ID is unique to all currently-allocated contexts. If you destroy a context and create
@@ -141,6 +222,14 @@ where
     /// This includes, most notably, the OpenGL framebuffer object needed to render to the surface.
     fn context_surface_info(&self, context: &Self::Context) -> Result<Option<SurfaceInfo>, Error>;
 
+    /// Returns various information about the read surface attached to a context, if any.
+    ///
+    /// This includes, most notably, the OpenGL framebuffer object needed to read from the surface.
+    fn context_read_surface_info(
+        &self,
+        context: &Self::Context,
+    ) -> Result<Option<SurfaceInfo>, Error>;
+
     /// Returns the native context associated with the given context.
     fn native_context(&self, context: &Self::Context) -> Self::NativeContext;
 
@@ -157,6 +246,39 @@ where
         surface_type: SurfaceType<<Self::Connection as ConnectionInterface>::NativeWidget>,
     ) -> Result<Self::Surface, Error>;
 
+    /// Like `create_surface()`, but the format of a generic surface is chosen by the `ALPHA`,
+    /// `DEPTH`, and `STENCIL` flags in `surface_flags` instead of by the context's descriptor.
+    /// Other flags are ignored.
+    ///
+    /// This allows one context to render to surfaces of differing formats. It is most useful with
+    /// contexts created with `ContextAttributeFlags::NO_CONFIG`. Widget surfaces always use the
+    /// format of the context's descriptor.
+    fn create_surface_with_attributes(
+        &mut self,
+        context: &Self::Context,
+        surface_access: SurfaceAccess,
+        surface_type: SurfaceType<<Self::Connection as ConnectionInterface>::NativeWidget>,
+        surface_flags: ContextAttributeFlags,
+    ) -> Result<Self::Surface, Error>;
+
+    /// Creates a generic surface that renders into an existing OpenGL texture.
+    ///
+    /// The texture must be a `TEXTURE_2D` of the given size with level 0 defined in a
+    /// color-renderable format, and it must belong to the given context (or to a context in its
+    /// share group). The resulting surface can be shared with other contexts through
+    /// `create_surface_texture()` and swap chains like any other generic surface.
+    ///
+    /// The texture remains owned by the caller: destroying the surface does not delete it, but the
+    /// texture must not be deleted or respecified while the surface is alive.
+    ///
+    /// Returns `UnsupportedOnThisPlatform` on backends that cannot wrap GL textures.
+    fn create_surface_from_gl_texture(
+        &mut self,
+        context: &Self::Context,
+        size: &Size2D<i32>,
+        texture: Texture,
+    ) -> Result<Self::Surface, Error>;
+
     /// Creates a surface texture from an existing generic surface for use with the given context.
     ///
     /// The surface texture is local to the supplied context and takes ownership of the surface.
@@ -238,3 +360,29 @@ where
     /// It is only legal to read from, not write to, this texture object.
     fn surface_texture_object(&self, surface_texture: &Self::SurfaceTexture) -> Option<Texture>;
 }
+
+/// A device whose generic surfaces can be shared with other processes through a `SurfaceHandle`.
+///
+/// This is implemented by the EGL-based Unix backends, and by `multi::Device` when both of its
+/// backends implement it.
+#[cfg(free_unix)]
+pub trait SurfaceHandleDevice: Device {
+    /// Exports the contents of a generic surface into a handle that can be sent to another
+    /// process, which can import it with `create_surface_from_handle()`.
+    ///
+    /// The supplied context must be the context the surface is associated with, or this returns
+    /// an `IncompatibleSurface` error. Exporting a widget surface returns a `WidgetAttached` error.
+    fn export_surface(
+        &self,
+        context: &Self::Context,
+        surface: &Self::Surface,
+    ) -> Result<SurfaceHandle, Error>;
+
+    /// Creates a generic surface from a handle produced by `export_surface()`, possibly in another
+    /// process.
+    fn create_surface_from_handle(
+        &mut self,
+        context: &Self::Context,
+        handle: &SurfaceHandle,
+    ) -> Result<Self::Surface, Error>;
+}
@@ -47,8 +47,10 @@ pub enum Error {
     DeviceOpenFailed,
     /// The system couldn't create a surface.
     SurfaceCreationFailed(WindowingApiError),
-    /// The system couldn't import a surface from another thread.
+    /// The system couldn't import a surface from another thread or process.
     SurfaceImportFailed(WindowingApiError),
+    /// The system couldn't export a surface for use by another process.
+    SurfaceExportFailed(WindowingApiError),
     /// The system couldn't create a surface texture from a surface.
     SurfaceTextureCreationFailed(WindowingApiError),
     /// The system couldn't present a widget surface.
@@ -1,8 +1,11 @@
 // surfman/surfman/src/gl_utils.rs
 //
 //! Various OpenGL utilities used by the different backends.
+//!
+//! The `GLStateSnapshot` type is public, so that applications can save and restore OpenGL state
+//! around their own rendering the same way `surfman` does internally.
 
-use glow::{HasContext, NativeFramebuffer};
+use glow::{HasContext, NativeBuffer, NativeFramebuffer, NativeRenderbuffer, NativeTexture};
 
 use crate::gl;
 use crate::Gl;
@@ -41,6 +44,17 @@ pub(crate) fn unbind_framebuffer_if_necessary(gl: &Gl, framebuffer_object: Nativ
     }
 }
 
+/// Returns true if the context has pixel buffer objects, which OpenGL 2.1 and OpenGL ES 3.0
+/// introduced. Without them, the pixel pack and unpack buffer bindings don't exist.
+pub(crate) fn supports_pixel_buffer_objects(gl: &Gl) -> bool {
+    let version = gl.version();
+    if version.is_embedded {
+        version.major >= 3
+    } else {
+        (version.major, version.minor) >= (2, 1)
+    }
+}
+
 #[allow(dead_code)]
 pub(crate) fn destroy_framebuffer(gl: &Gl, framebuffer_object: NativeFramebuffer) {
     unbind_framebuffer_if_necessary(gl, framebuffer_object);
@@ -48,3 +62,199 @@ pub(crate) fn destroy_framebuffer(gl: &Gl, framebuffer_object: NativeFramebuffer
         gl.delete_framebuffer(framebuffer_object);
     }
 }
+
+bitflags! {
+    /// Groups of OpenGL state that a `GLStateSnapshot` can capture and restore.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct GLStateGroups: u16 {
+        /// The draw and read framebuffer bindings.
+        const FRAMEBUFFER_BINDINGS        = 0x01;
+        /// The color, depth, and stencil clear values.
+        const CLEAR_VALUES                = 0x02;
+        /// The color, depth, and front and back stencil write masks.
+        const WRITE_MASKS                 = 0x04;
+        /// Whether the scissor test is enabled, and the scissor box.
+        const SCISSOR                     = 0x08;
+        /// Whether rasterizer discard is enabled.
+        const RASTERIZER_DISCARD          = 0x10;
+        /// The `GL_TEXTURE_2D` binding of the active texture unit.
+        const TEXTURE_2D_BINDING          = 0x20;
+        /// The pixel unpack buffer binding. Ignored on contexts without pixel buffer objects.
+        const PIXEL_UNPACK_BUFFER_BINDING = 0x40;
+        /// The renderbuffer binding.
+        const RENDERBUFFER_BINDING        = 0x80;
+        /// The pixel pack buffer binding. Ignored on contexts without pixel buffer objects.
+        const PIXEL_PACK_BUFFER_BINDING   = 0x100;
+    }
+}
+
+/// A saved copy of some groups of OpenGL state, which can later be restored.
+///
+/// Capture a snapshot before changing state on a context you don't own, and restore it afterward
+/// so that the changes don't leak out. The snapshot must be restored on the same context it was
+/// captured from.
+#[derive(Clone, Debug)]
+pub struct GLStateSnapshot {
+    groups: GLStateGroups,
+    draw_framebuffer: Option<NativeFramebuffer>,
+    read_framebuffer: Option<NativeFramebuffer>,
+    clear_color: [f32; 4],
+    clear_depth: f32,
+    clear_stencil: i32,
+    color_mask: [bool; 4],
+    depth_mask: bool,
+    stencil_mask: i32,
+    stencil_back_mask: i32,
+    scissor_enabled: bool,
+    scissor_box: [i32; 4],
+    rasterizer_discard_enabled: bool,
+    texture_2d: Option<NativeTexture>,
+    pixel_unpack_buffer: Option<NativeBuffer>,
+    pixel_pack_buffer: Option<NativeBuffer>,
+    renderbuffer: Option<NativeRenderbuffer>,
+}
+
+impl GLStateSnapshot {
+    /// Captures the given groups of state from the current OpenGL context.
+    pub fn capture(gl: &Gl, groups: GLStateGroups) -> GLStateSnapshot {
+        let mut snapshot = GLStateSnapshot {
+            groups,
+            draw_framebuffer: None,
+            read_framebuffer: None,
+            clear_color: [0.0; 4],
+            clear_depth: 1.0,
+            clear_stencil: 0,
+            color_mask: [true; 4],
+            depth_mask: true,
+            stencil_mask: !0,
+            stencil_back_mask: !0,
+            scissor_enabled: false,
+            scissor_box: [0; 4],
+            rasterizer_discard_enabled: false,
+            texture_2d: None,
+            pixel_unpack_buffer: None,
+            pixel_pack_buffer: None,
+            renderbuffer: None,
+        };
+
+        let groups = pixel_buffer_groups(gl, groups);
+        unsafe {
+            if groups.contains(GLStateGroups::FRAMEBUFFER_BINDINGS) {
+                snapshot.draw_framebuffer =
+                    gl.get_parameter_framebuffer(gl::DRAW_FRAMEBUFFER_BINDING);
+                snapshot.read_framebuffer =
+                    gl.get_parameter_framebuffer(gl::READ_FRAMEBUFFER_BINDING);
+            }
+            if groups.contains(GLStateGroups::CLEAR_VALUES) {
+                let mut clear_depth = [0.0];
+                let mut clear_stencil = [0];
+                gl.get_parameter_f32_slice(gl::COLOR_CLEAR_VALUE, &mut snapshot.clear_color);
+                gl.get_parameter_f32_slice(gl::DEPTH_CLEAR_VALUE, &mut clear_depth);
+                gl.get_parameter_i32_slice(gl::STENCIL_CLEAR_VALUE, &mut clear_stencil);
+                snapshot.clear_depth = clear_depth[0];
+                snapshot.clear_stencil = clear_stencil[0];
+            }
+            if groups.contains(GLStateGroups::WRITE_MASKS) {
+                let mut stencil_mask = [0];
+                let mut stencil_back_mask = [0];
+                snapshot.color_mask = gl.get_parameter_bool_array::<4>(gl::COLOR_WRITEMASK);
+                snapshot.depth_mask = gl.get_parameter_bool(gl::DEPTH_WRITEMASK);
+                gl.get_parameter_i32_slice(gl::STENCIL_WRITEMASK, &mut stencil_mask);
+                gl.get_parameter_i32_slice(gl::STENCIL_BACK_WRITEMASK, &mut stencil_back_mask);
+                snapshot.stencil_mask = stencil_mask[0];
+                snapshot.stencil_back_mask = stencil_back_mask[0];
+            }
+            if groups.contains(GLStateGroups::SCISSOR) {
+                snapshot.scissor_enabled = gl.is_enabled(gl::SCISSOR_TEST);
+                gl.get_parameter_i32_slice(gl::SCISSOR_BOX, &mut snapshot.scissor_box);
+            }
+            if groups.contains(GLStateGroups::RASTERIZER_DISCARD) {
+                snapshot.rasterizer_discard_enabled = gl.is_enabled(gl::RASTERIZER_DISCARD);
+            }
+            if groups.contains(GLStateGroups::TEXTURE_2D_BINDING) {
+                snapshot.texture_2d = gl.get_parameter_texture(gl::TEXTURE_BINDING_2D);
+            }
+            if groups.contains(GLStateGroups::PIXEL_UNPACK_BUFFER_BINDING) {
+                snapshot.pixel_unpack_buffer =
+                    gl.get_parameter_buffer(gl::PIXEL_UNPACK_BUFFER_BINDING);
+            }
+            if groups.contains(GLStateGroups::RENDERBUFFER_BINDING) {
+                snapshot.renderbuffer = gl.get_parameter_renderbuffer(gl::RENDERBUFFER_BINDING);
+            }
+            if groups.contains(GLStateGroups::PIXEL_PACK_BUFFER_BINDING) {
+                snapshot.pixel_pack_buffer = gl.get_parameter_buffer(gl::PIXEL_PACK_BUFFER_BINDING);
+            }
+        }
+
+        snapshot
+    }
+
+    /// Returns the groups of state that this snapshot holds.
+    #[inline]
+    pub fn groups(&self) -> GLStateGroups {
+        self.groups
+    }
+
+    /// Restores the captured state to the current OpenGL context.
+    pub fn restore(&self, gl: &Gl) {
+        let groups = pixel_buffer_groups(gl, self.groups);
+        unsafe {
+            if groups.contains(GLStateGroups::FRAMEBUFFER_BINDINGS) {
+                gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, self.draw_framebuffer);
+                gl.bind_framebuffer(gl::READ_FRAMEBUFFER, self.read_framebuffer);
+            }
+            if groups.contains(GLStateGroups::CLEAR_VALUES) {
+                let [r, g, b, a] = self.clear_color;
+                gl.clear_color(r, g, b, a);
+                gl.clear_depth(self.clear_depth as f64);
+                gl.clear_stencil(self.clear_stencil);
+            }
+            if groups.contains(GLStateGroups::WRITE_MASKS) {
+                let [r, g, b, a] = self.color_mask;
+                gl.color_mask(r, g, b, a);
+                gl.depth_mask(self.depth_mask);
+                gl.stencil_mask_separate(gl::FRONT, self.stencil_mask as u32);
+                gl.stencil_mask_separate(gl::BACK, self.stencil_back_mask as u32);
+            }
+            if groups.contains(GLStateGroups::SCISSOR) {
+                set_enabled(gl, gl::SCISSOR_TEST, self.scissor_enabled);
+                let [x, y, width, height] = self.scissor_box;
+                gl.scissor(x, y, width, height);
+            }
+            if groups.contains(GLStateGroups::RASTERIZER_DISCARD) {
+                set_enabled(gl, gl::RASTERIZER_DISCARD, self.rasterizer_discard_enabled);
+            }
+            if groups.contains(GLStateGroups::TEXTURE_2D_BINDING) {
+                gl.bind_texture(gl::TEXTURE_2D, self.texture_2d);
+            }
+            if groups.contains(GLStateGroups::PIXEL_UNPACK_BUFFER_BINDING) {
+                gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, self.pixel_unpack_buffer);
+            }
+            if groups.contains(GLStateGroups::RENDERBUFFER_BINDING) {
+                gl.bind_renderbuffer(gl::RENDERBUFFER, self.renderbuffer);
+            }
+            if groups.contains(GLStateGroups::PIXEL_PACK_BUFFER_BINDING) {
+                gl.bind_buffer(gl::PIXEL_PACK_BUFFER, self.pixel_pack_buffer);
+            }
+        }
+    }
+}
+
+// Leaves out the pixel buffer bindings on contexts that don't have them.
+fn pixel_buffer_groups(gl: &Gl, groups: GLStateGroups) -> GLStateGroups {
+    let pixel_buffer_groups =
+        GLStateGroups::PIXEL_UNPACK_BUFFER_BINDING | GLStateGroups::PIXEL_PACK_BUFFER_BINDING;
+    if groups.intersects(pixel_buffer_groups) && !supports_pixel_buffer_objects(gl) {
+        groups - pixel_buffer_groups
+    } else {
+        groups
+    }
+}
+
+unsafe fn set_enabled(gl: &Gl, capability: u32, enabled: bool) {
+    if enabled {
+        gl.enable(capability);
+    } else {
+        gl.disable(capability);
+    }
+}
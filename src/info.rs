@@ -2,8 +2,36 @@
 //
 //! OpenGL information.
 
-use crate::Gl;
+use crate::context;
+use crate::device::Device as DeviceInterface;
+use crate::{gl, ContextAttributeFlags, ContextAttributes, ContextPriority, Error, Gl};
 use glow::HasContext;
+use std::collections::HashSet;
+
+// The OpenGL versions that `Device::capabilities()` checks for.
+static GL_VERSIONS: [GLVersion; 13] = [
+    GLVersion { major: 2, minor: 0 },
+    GLVersion { major: 2, minor: 1 },
+    GLVersion { major: 3, minor: 0 },
+    GLVersion { major: 3, minor: 1 },
+    GLVersion { major: 3, minor: 2 },
+    GLVersion { major: 3, minor: 3 },
+    GLVersion { major: 4, minor: 0 },
+    GLVersion { major: 4, minor: 1 },
+    GLVersion { major: 4, minor: 2 },
+    GLVersion { major: 4, minor: 3 },
+    GLVersion { major: 4, minor: 4 },
+    GLVersion { major: 4, minor: 5 },
+    GLVersion { major: 4, minor: 6 },
+];
+
+// The OpenGL ES versions that `Device::capabilities()` checks for.
+static GL_ES_VERSIONS: [GLVersion; 4] = [
+    GLVersion { major: 2, minor: 0 },
+    GLVersion { major: 3, minor: 0 },
+    GLVersion { major: 3, minor: 1 },
+    GLVersion { major: 3, minor: 2 },
+];
 
 /// The API (OpenGL or OpenGL ES).
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -42,3 +70,129 @@ impl GLVersion {
         }
     }
 }
+
+/// Describes what a device supports, as returned by `Device::capabilities()`.
+#[derive(Clone, Debug)]
+pub struct DeviceCapabilities {
+    /// The API (OpenGL or OpenGL ES) that contexts on this device use.
+    pub gl_api: GLApi,
+    /// The versions of `gl_api` that contexts can be created with, lowest first.
+    pub gl_versions: Vec<GLVersion>,
+    /// The value of `GL_MAX_TEXTURE_SIZE`.
+    pub max_texture_size: i32,
+    /// The value of `GL_MAX_RENDERBUFFER_SIZE`.
+    pub max_renderbuffer_size: i32,
+    /// The value of `GL_MAX_SAMPLES`, or 0 if multisampled renderbuffers are unsupported.
+    pub max_samples: i32,
+    /// The combinations of the `ALPHA`, `DEPTH`, and `STENCIL` flags that generic surfaces can be
+    /// created with.
+    pub surface_formats: Vec<ContextAttributeFlags>,
+    /// Whether surfaces created with `SurfaceAccess::GPUCPU` can be locked for CPU access with
+    /// `lock_surface_data()`.
+    pub gpu_cpu_surfaces: bool,
+    /// Whether widget surfaces can be created.
+    pub widget_surfaces: bool,
+    /// The extensions of the windowing API (EGL or WGL). Empty on backends without an extension
+    /// mechanism.
+    pub platform_extensions: HashSet<String>,
+    /// The OpenGL extensions supported by contexts on this device.
+    pub gl_extensions: HashSet<String>,
+}
+
+impl DeviceCapabilities {
+    // Creates a temporary context for each candidate version to find out what the device
+    // supports. Limits and extensions come from the context with the highest version.
+    //
+    // Creating and destroying contexts changes the current context, so callers must save the
+    // current context beforehand and restore it afterward.
+    pub(crate) fn probe<D>(
+        device: &mut D,
+        platform_extensions: HashSet<String>,
+        widget_surfaces: bool,
+        gpu_cpu_surfaces: bool,
+    ) -> Result<DeviceCapabilities, Error>
+    where
+        D: DeviceInterface,
+    {
+        let gl_api = device.gl_api();
+        let candidate_versions = match gl_api {
+            GLApi::GL => &GL_VERSIONS[..],
+            GLApi::GLES => &GL_ES_VERSIONS[..],
+        };
+
+        let mut limits = None;
+        let mut gl_versions = vec![];
+        for &version in candidate_versions {
+            let attributes = ContextAttributes {
+                version,
+                flags: ContextAttributeFlags::empty(),
+                priority: ContextPriority::Medium,
+            };
+            match context::create_context_with_attributes(device, &attributes) {
+                Ok((_, mut context)) => {
+                    let result = device
+                        .context_gl(&context)
+                        .map(|gl| unsafe { query_limits(&gl) });
+                    device.destroy_context(&mut context)?;
+                    limits = Some(result?);
+                    gl_versions.push(version);
+                }
+                Err(error) if context::attributes_unsupported(&error) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        let version = match gl_versions.last() {
+            Some(&version) => version,
+            None => return Err(Error::UnsupportedGLVersion),
+        };
+
+        let mut surface_formats = vec![];
+        let format_flags = ContextAttributeFlags::ALPHA
+            | ContextAttributeFlags::DEPTH
+            | ContextAttributeFlags::STENCIL;
+        for flag_bits in 0..(format_flags.bits() + 1) {
+            let flags = ContextAttributeFlags::from_bits_truncate(flag_bits);
+            let attributes = ContextAttributes {
+                version,
+                flags,
+                priority: ContextPriority::Medium,
+            };
+            if device.create_context_descriptor(&attributes).is_ok() {
+                surface_formats.push(flags);
+            }
+        }
+
+        let (max_texture_size, max_renderbuffer_size, max_samples, gl_extensions) =
+            limits.ok_or(Error::UnsupportedGLVersion)?;
+        Ok(DeviceCapabilities {
+            gl_api,
+            gl_versions,
+            max_texture_size,
+            max_renderbuffer_size,
+            max_samples,
+            surface_formats,
+            gpu_cpu_surfaces,
+            widget_surfaces,
+            platform_extensions,
+            gl_extensions,
+        })
+    }
+}
+
+// Returns `GL_MAX_TEXTURE_SIZE`, `GL_MAX_RENDERBUFFER_SIZE`, `GL_MAX_SAMPLES`, and the extensions of
+// the current context.
+unsafe fn query_limits(gl: &Gl) -> (i32, i32, i32, HashSet<String>) {
+    // `GL_MAX_SAMPLES` is new in OpenGL 3.0 and OpenGL ES 3.0.
+    let max_samples = if GLVersion::current(gl).major >= 3 {
+        gl.get_parameter_i32(gl::MAX_SAMPLES)
+    } else {
+        0
+    };
+    (
+        gl.get_parameter_i32(gl::MAX_TEXTURE_SIZE),
+        gl.get_parameter_i32(gl::MAX_RENDERBUFFER_SIZE),
+        max_samples,
+        gl.supported_extensions().clone(),
+    )
+}
@@ -22,7 +22,9 @@ extern crate objc;
 
 pub mod platform;
 pub use platform::default::connection::{Connection, NativeConnection};
-pub use platform::default::context::{Context, ContextDescriptor, NativeContext};
+pub use platform::default::context::{
+    Context, ContextDescriptor, CurrentContextGuard, NativeContext,
+};
 pub use platform::default::device::{Adapter, Device, NativeDevice};
 pub use platform::default::surface::{NativeWidget, Surface, SurfaceTexture};
 
@@ -34,19 +36,29 @@ pub use platform::system::device::{Adapter as SystemAdapter, Device as SystemDev
 #[cfg(target_os = "macos")]
 pub use platform::system::surface::Surface as SystemSurface;
 
+#[cfg(any(android_platform, angle, free_unix, ohos_platform))]
+pub use platform::generic::egl::device::set_egl_library_path;
+#[cfg(any(android_platform, angle, free_unix, ohos_platform))]
+pub use platform::generic::egl::shader_cache::{
+    set_shader_cache, DirectoryShaderCache, ShaderCache,
+};
+
 #[cfg(feature = "chains")]
 pub mod chains;
 pub mod connection;
 pub mod device;
 
 pub mod error;
+pub mod owned;
 pub use crate::error::{Error, WindowingApiError};
 
 mod context;
-pub use crate::context::{ContextAttributeFlags, ContextAttributes, ContextID};
+pub use crate::context::{
+    ContextAttributeFlags, ContextAttributes, ContextGL, ContextID, ContextPriority,
+};
 
 mod info;
-pub use crate::info::{GLApi, GLVersion};
+pub use crate::info::{DeviceCapabilities, GLApi, GLVersion};
 
 mod surface;
 pub use crate::surface::{SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType, SystemSurfaceInfo};
@@ -56,7 +68,7 @@ pub(crate) use macros::implement_interfaces;
 
 pub(crate) use glow::{self as gl, Context as Gl};
 
-mod gl_utils;
+pub mod gl_utils;
 mod renderbuffers;
 
 #[cfg(any(
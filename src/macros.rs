@@ -37,7 +37,7 @@ macro_rules! implement_interfaces {
     () => {
         mod implementation {
             use super::connection::{Connection, NativeConnection};
-            use super::context::{Context, ContextDescriptor, NativeContext};
+            use super::context::{Context, ContextDescriptor, CurrentContextGuard, NativeContext};
             use super::device::{Adapter, Device, NativeDevice};
             use super::surface::{NativeWidget, Surface, SurfaceTexture};
             use euclid::default::Size2D;
@@ -45,9 +45,10 @@ macro_rules! implement_interfaces {
             use std::os::raw::c_void;
             use $crate::connection::Connection as ConnectionInterface;
             use $crate::device::Device as DeviceInterface;
-            use $crate::info::GLApi;
+            use $crate::info::{DeviceCapabilities, GLApi};
             use $crate::Error;
-            use $crate::{ContextAttributes, ContextID, SurfaceAccess, SurfaceInfo, SurfaceType};
+            use $crate::{ContextAttributeFlags, ContextAttributes, ContextID};
+            use $crate::{SurfaceAccess, SurfaceInfo, SurfaceType};
 
             impl ConnectionInterface for Connection {
                 type Adapter = Adapter;
@@ -152,6 +153,7 @@ macro_rules! implement_interfaces {
                 type Connection = Connection;
                 type Context = Context;
                 type ContextDescriptor = ContextDescriptor;
+                type CurrentContextGuard = CurrentContextGuard;
                 type NativeContext = NativeContext;
                 type Surface = Surface;
                 type SurfaceTexture = SurfaceTexture;
@@ -179,6 +181,11 @@ macro_rules! implement_interfaces {
                     Device::gl_api(self)
                 }
 
+                #[inline]
+                fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+                    Device::capabilities(self)
+                }
+
                 // context.rs
 
                 #[inline]
@@ -189,6 +196,14 @@ macro_rules! implement_interfaces {
                     Device::create_context_descriptor(self, attributes)
                 }
 
+                #[inline]
+                fn negotiate_context_descriptor(
+                    &mut self,
+                    candidates: &[ContextAttributes],
+                ) -> Result<Self::ContextDescriptor, Error> {
+                    Device::negotiate_context_descriptor(self, candidates)
+                }
+
                 #[inline]
                 fn create_context(
                     &mut self,
@@ -226,6 +241,14 @@ macro_rules! implement_interfaces {
                     Device::make_no_context_current(self)
                 }
 
+                #[inline]
+                fn make_context_current_scoped(
+                    &self,
+                    context: &Context,
+                ) -> Result<CurrentContextGuard, Error> {
+                    Device::make_context_current_scoped(self, context)
+                }
+
                 #[inline]
                 fn context_descriptor_attributes(
                     &self,
@@ -243,6 +266,14 @@ macro_rules! implement_interfaces {
                     Device::get_proc_address(self, context, symbol_name)
                 }
 
+                #[inline]
+                fn context_gl<'a>(
+                    &self,
+                    context: &'a Self::Context,
+                ) -> Result<$crate::ContextGL<'a, CurrentContextGuard>, Error> {
+                    Device::context_gl(self, context)
+                }
+
                 #[inline]
                 fn bind_surface_to_context(
                     &self,
@@ -260,6 +291,23 @@ macro_rules! implement_interfaces {
                     Device::unbind_surface_from_context(self, context)
                 }
 
+                #[inline]
+                fn bind_read_surface_to_context(
+                    &self,
+                    context: &mut Self::Context,
+                    surface: Self::Surface,
+                ) -> Result<(), (Error, Self::Surface)> {
+                    Device::bind_read_surface_to_context(self, context, surface)
+                }
+
+                #[inline]
+                fn unbind_read_surface_from_context(
+                    &self,
+                    context: &mut Self::Context,
+                ) -> Result<Option<Self::Surface>, Error> {
+                    Device::unbind_read_surface_from_context(self, context)
+                }
+
                 #[inline]
                 fn context_id(&self, context: &Self::Context) -> ContextID {
                     Device::context_id(self, context)
@@ -273,6 +321,14 @@ macro_rules! implement_interfaces {
                     Device::context_surface_info(self, context)
                 }
 
+                #[inline]
+                fn context_read_surface_info(
+                    &self,
+                    context: &Self::Context,
+                ) -> Result<Option<SurfaceInfo>, Error> {
+                    Device::context_read_surface_info(self, context)
+                }
+
                 #[inline]
                 fn native_context(&self, context: &Self::Context) -> Self::NativeContext {
                     Device::native_context(self, context)
@@ -290,6 +346,33 @@ macro_rules! implement_interfaces {
                     Device::create_surface(self, context, surface_access, surface_type)
                 }
 
+                #[inline]
+                fn create_surface_with_attributes(
+                    &mut self,
+                    context: &Self::Context,
+                    surface_access: SurfaceAccess,
+                    surface_type: SurfaceType<NativeWidget>,
+                    surface_flags: ContextAttributeFlags,
+                ) -> Result<Self::Surface, Error> {
+                    Device::create_surface_with_attributes(
+                        self,
+                        context,
+                        surface_access,
+                        surface_type,
+                        surface_flags,
+                    )
+                }
+
+                #[inline]
+                fn create_surface_from_gl_texture(
+                    &mut self,
+                    context: &Self::Context,
+                    size: &Size2D<i32>,
+                    texture: Texture,
+                ) -> Result<Self::Surface, Error> {
+                    Device::create_surface_from_gl_texture(self, context, size, texture)
+                }
+
                 #[inline]
                 fn create_surface_texture(
                     &self,
@@ -354,6 +437,35 @@ macro_rules! implement_interfaces {
                     Device::surface_texture_object(self, surface_texture)
                 }
             }
+
+            #[cfg(free_unix)]
+            impl $crate::connection::GLApiConnection for Connection {
+                #[inline]
+                fn with_gl_api(self, gl_api: GLApi) -> Connection {
+                    Connection::with_gl_api(self, gl_api)
+                }
+            }
+
+            #[cfg(free_unix)]
+            impl $crate::device::SurfaceHandleDevice for Device {
+                #[inline]
+                fn export_surface(
+                    &self,
+                    context: &Context,
+                    surface: &Surface,
+                ) -> Result<super::surface::SurfaceHandle, Error> {
+                    Device::export_surface(self, context, surface)
+                }
+
+                #[inline]
+                fn create_surface_from_handle(
+                    &mut self,
+                    context: &Context,
+                    handle: &super::surface::SurfaceHandle,
+                ) -> Result<Surface, Error> {
+                    Device::create_surface_from_handle(self, context, handle)
+                }
+            }
         }
     };
 }
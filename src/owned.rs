@@ -0,0 +1,451 @@
+// surfman/surfman/src/owned.rs
+//
+//! Owned contexts and surfaces that clean up after themselves, and deferred destruction of
+//! surfaces.
+//!
+//! Raw contexts and surfaces must be destroyed explicitly with the device that created them, and
+//! panic if they are dropped instead. This module provides an optional safe layer on top: a
+//! `SharedDevice` hands out `OwnedContext` and `OwnedSurface` handles that remember their device
+//! and destroy themselves when dropped, including while unwinding from a panic.
+//!
+//! A context stays alive until its last handle and its last surface have been dropped. Surfaces
+//! are destroyed with their owning context made current temporarily, after which whichever
+//! context was current before is restored.
+//!
+//! Handles lock the device, and then their context, for the duration of each call, so they must
+//! not be dropped while the device is locked with `SharedDevice::lock()` or
+//! `OwnedContext::with()`.
+//!
+//! Surfaces and surface textures can also be released from any thread, without access to their
+//! context, by pushing them onto a `DestructionQueue`. Each owned context has one, which is
+//! drained whenever the context is made current with `OwnedContext::make_current()`, but not when
+//! the raw context is made current with `Device::make_context_current()`. Queues can be used with
+//! raw contexts too, in which case they must be drained explicitly.
+
+use crate::connection::Connection as ConnectionAPI;
+use crate::device::Device as DeviceAPI;
+use crate::{Error, SurfaceAccess, SurfaceType};
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem::{self, ManuallyDrop};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// A device shared by the owned contexts and surfaces created from it.
+pub struct SharedDevice<Device: DeviceAPI + 'static>(Arc<Mutex<Device>>);
+
+/// A context that is destroyed once it and all of its surfaces have been dropped.
+pub struct OwnedContext<Device: DeviceAPI + 'static>(Arc<ContextData<Device>>);
+
+/// A surface that is destroyed, with its owning context, when dropped.
+pub struct OwnedSurface<Device: DeviceAPI + 'static> {
+    context: OwnedContext<Device>,
+    // This is only `None` once ownership has been given up with `into_inner()`.
+    surface: Option<Device::Surface>,
+}
+
+/// A thread-safe queue of surfaces and surface textures waiting to be destroyed by the context
+/// that owns them.
+///
+/// Any thread holding a surface can push it onto the queue, and the thread that owns the context
+/// then destroys everything in the queue with `drain()`. Surface textures can't leave the thread
+/// they were created on, so they are only destroyed by draining the queue on the thread that
+/// pushed them. Anything still queued when the last handle to the queue is dropped is leaked, with
+/// a warning.
+pub struct DestructionQueue<Device: DeviceAPI + 'static>(Arc<Mutex<PendingDestruction<Device>>>);
+
+struct PendingDestruction<Device: DeviceAPI + 'static> {
+    // Identifies this queue's surface textures in `QUEUED_SURFACE_TEXTURES`.
+    id: usize,
+    surfaces: Vec<Device::Surface>,
+    // The number of surface textures queued on all threads.
+    surface_texture_count: usize,
+}
+
+static NEXT_QUEUE_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // The surface textures queued on this thread, indexed by the id of their queue. Surface
+    // textures can't leave the thread they were created on, so they are kept here rather than in
+    // the queue itself. They are never dropped: any that are never drained are leaked.
+    static QUEUED_SURFACE_TEXTURES: RefCell<HashMap<usize, Box<dyn Any>>> =
+        RefCell::new(HashMap::new());
+}
+
+fn queue_surface_textures<SurfaceTexture: 'static>(
+    queue_id: usize,
+    surface_textures: impl IntoIterator<Item = ManuallyDrop<SurfaceTexture>>,
+) {
+    QUEUED_SURFACE_TEXTURES.with(|queued| {
+        queued
+            .borrow_mut()
+            .entry(queue_id)
+            .or_insert_with(|| Box::new(Vec::<ManuallyDrop<SurfaceTexture>>::new()))
+            .downcast_mut::<Vec<ManuallyDrop<SurfaceTexture>>>()
+            .expect("A destruction queue only holds one type of surface texture")
+            .extend(surface_textures)
+    });
+}
+
+fn take_queued_surface_textures<SurfaceTexture: 'static>(
+    queue_id: usize,
+) -> Vec<ManuallyDrop<SurfaceTexture>> {
+    QUEUED_SURFACE_TEXTURES.with(|queued| match queued.borrow_mut().remove(&queue_id) {
+        None => vec![],
+        Some(surface_textures) => *surface_textures
+            .downcast::<Vec<ManuallyDrop<SurfaceTexture>>>()
+            .expect("A destruction queue only holds one type of surface texture"),
+    })
+}
+
+struct ContextData<Device: DeviceAPI + 'static> {
+    device: SharedDevice<Device>,
+    destruction_queue: DestructionQueue<Device>,
+    // This is only dropped once it has been destroyed.
+    context: Mutex<ManuallyDrop<Device::Context>>,
+}
+
+impl<Device: DeviceAPI + 'static> Clone for SharedDevice<Device> {
+    fn clone(&self) -> Self {
+        SharedDevice(self.0.clone())
+    }
+}
+
+impl<Device: DeviceAPI + 'static> Clone for OwnedContext<Device> {
+    fn clone(&self) -> Self {
+        OwnedContext(self.0.clone())
+    }
+}
+
+impl<Device: DeviceAPI + 'static> Clone for DestructionQueue<Device> {
+    fn clone(&self) -> Self {
+        DestructionQueue(self.0.clone())
+    }
+}
+
+impl<Device: DeviceAPI + 'static> Default for DestructionQueue<Device> {
+    fn default() -> Self {
+        DestructionQueue::new()
+    }
+}
+
+impl<Device: DeviceAPI + 'static> SharedDevice<Device> {
+    /// Takes ownership of a device so that it can be shared by owned contexts and surfaces.
+    pub fn new(device: Device) -> SharedDevice<Device> {
+        SharedDevice(Arc::new(Mutex::new(device)))
+    }
+
+    /// Locks the device for direct use.
+    ///
+    /// Owned handles can't be dropped while the device is locked.
+    pub fn lock(&self) -> MutexGuard<'_, Device> {
+        // A panic while the device was locked leaves nothing half-done that we'd need to avoid,
+        // and cleanup during unwinding must still be able to get at the device.
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Creates a new owned context from a descriptor, optionally sharing textures with another
+    /// context.
+    pub fn create_context(
+        &self,
+        descriptor: &Device::ContextDescriptor,
+        share_with: Option<&OwnedContext<Device>>,
+    ) -> Result<OwnedContext<Device>, Error> {
+        let mut device = self.lock();
+        let context = match share_with {
+            None => device.create_context(descriptor, None)?,
+            Some(share_with) => {
+                if !Arc::ptr_eq(&self.0, &share_with.0.device.0) {
+                    return Err(Error::IncompatibleSharedContext);
+                }
+                let share_with = share_with.0.lock_context();
+                device.create_context(descriptor, Some(&**share_with))?
+            }
+        };
+        Ok(OwnedContext(Arc::new(ContextData {
+            device: self.clone(),
+            destruction_queue: DestructionQueue::new(),
+            context: Mutex::new(ManuallyDrop::new(context)),
+        })))
+    }
+
+    /// Takes ownership of a raw context that was created on this device.
+    pub fn adopt_context(&self, context: Device::Context) -> OwnedContext<Device> {
+        OwnedContext(Arc::new(ContextData {
+            device: self.clone(),
+            destruction_queue: DestructionQueue::new(),
+            context: Mutex::new(ManuallyDrop::new(context)),
+        }))
+    }
+}
+
+impl<Device: DeviceAPI + 'static> OwnedContext<Device> {
+    /// Returns the device that this context was created on.
+    #[inline]
+    pub fn device(&self) -> &SharedDevice<Device> {
+        &self.0.device
+    }
+
+    /// Calls `f` with the device and the raw context, both locked.
+    ///
+    /// Don't destroy the context inside `f`; it is destroyed when the last handle is dropped.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Device, &mut Device::Context) -> R,
+    {
+        let mut device = self.0.device.lock();
+        let mut context = self.0.lock_context();
+        f(&mut device, &mut context)
+    }
+
+    /// Returns the queue of surfaces and surface textures waiting to be destroyed by this context.
+    #[inline]
+    pub fn destruction_queue(&self) -> &DestructionQueue<Device> {
+        &self.0.destruction_queue
+    }
+
+    /// Makes this context the current OpenGL context for this thread, then destroys everything in
+    /// its destruction queue.
+    pub fn make_current(&self) -> Result<(), Error> {
+        self.with(|device, context| {
+            device.make_context_current(context)?;
+            self.0.destruction_queue.drain(device, context)
+        })
+    }
+
+    /// Creates a new owned surface for this context.
+    pub fn create_surface(
+        &self,
+        surface_access: SurfaceAccess,
+        surface_type: SurfaceType<<Device::Connection as ConnectionAPI>::NativeWidget>,
+    ) -> Result<OwnedSurface<Device>, Error> {
+        let surface = self
+            .with(|device, context| device.create_surface(context, surface_access, surface_type))?;
+        Ok(self.adopt_surface(surface))
+    }
+
+    /// Takes ownership of a raw surface that was created for this context.
+    pub fn adopt_surface(&self, surface: Device::Surface) -> OwnedSurface<Device> {
+        OwnedSurface {
+            context: self.clone(),
+            surface: Some(surface),
+        }
+    }
+
+    /// Attaches a surface to this context for rendering.
+    ///
+    /// The surface must have been created for this context. While it is bound, it is owned by the
+    /// context, and it is destroyed along with the context unless it is unbound first.
+    pub fn bind_surface(
+        &self,
+        surface: OwnedSurface<Device>,
+    ) -> Result<(), (Error, OwnedSurface<Device>)> {
+        if !Arc::ptr_eq(&self.0, &surface.context.0) {
+            return Err((Error::IncompatibleSurface, surface));
+        }
+        let surface = surface.into_inner();
+        self.with(|device, context| device.bind_surface_to_context(context, surface))
+            .map_err(|(err, surface)| (err, self.adopt_surface(surface)))
+    }
+
+    /// Removes and returns the surface attached to this context, if any.
+    pub fn unbind_surface(&self) -> Result<Option<OwnedSurface<Device>>, Error> {
+        let surface = self.with(|device, context| device.unbind_surface_from_context(context))?;
+        Ok(surface.map(|surface| self.adopt_surface(surface)))
+    }
+}
+
+impl<Device: DeviceAPI + 'static> OwnedSurface<Device> {
+    /// Returns the context that owns this surface.
+    #[inline]
+    pub fn context(&self) -> &OwnedContext<Device> {
+        &self.context
+    }
+
+    /// Returns the raw surface.
+    #[inline]
+    pub fn get(&self) -> &Device::Surface {
+        self.surface.as_ref().unwrap()
+    }
+
+    /// Returns the raw surface mutably.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut Device::Surface {
+        self.surface.as_mut().unwrap()
+    }
+
+    /// Gives up ownership of the raw surface, which must then be destroyed explicitly.
+    pub fn into_inner(mut self) -> Device::Surface {
+        self.surface.take().unwrap()
+    }
+}
+
+impl<Device: DeviceAPI + 'static> DestructionQueue<Device> {
+    /// Creates an empty queue.
+    pub fn new() -> DestructionQueue<Device> {
+        DestructionQueue(Arc::new(Mutex::new(PendingDestruction {
+            id: NEXT_QUEUE_ID.fetch_add(1, Ordering::Relaxed),
+            surfaces: vec![],
+            surface_texture_count: 0,
+        })))
+    }
+
+    /// Queues a surface for destruction.
+    pub fn push_surface(&self, surface: Device::Surface) {
+        self.lock().surfaces.push(surface);
+    }
+
+    /// Queues a surface texture, and the surface it wraps, for destruction on this thread.
+    pub fn push_surface_texture(&self, surface_texture: Device::SurfaceTexture) {
+        let mut pending = self.lock();
+        queue_surface_textures(pending.id, [ManuallyDrop::new(surface_texture)]);
+        pending.surface_texture_count += 1;
+    }
+
+    /// Returns true if nothing is waiting to be destroyed.
+    pub fn is_empty(&self) -> bool {
+        let pending = self.lock();
+        pending.surfaces.is_empty() && pending.surface_texture_count == 0
+    }
+
+    /// Destroys everything in the queue with the context that owns it.
+    ///
+    /// The context is made current while this happens, after which whichever context was current
+    /// before is restored. If the context can't be made current, the queue is left untouched.
+    /// Surfaces created for other contexts, and surface textures queued on other threads, are left
+    /// in the queue. A surface texture is destroyed by whichever context drains its thread's
+    /// queue, but the surface it wraps is only destroyed by its own context. Objects that fail to
+    /// be destroyed are leaked, and the first error is returned.
+    pub fn drain(&self, device: &Device, context: &mut Device::Context) -> Result<(), Error> {
+        let context_id = device.context_id(context);
+        let (mut surfaces, surface_textures) = {
+            let mut pending = self.lock();
+            let surface_textures = take_queued_surface_textures(pending.id);
+            pending.surface_texture_count -= surface_textures.len();
+            let (surfaces, other_surfaces): (Vec<_>, Vec<_>) = mem::take(&mut pending.surfaces)
+                .into_iter()
+                .partition(|surface| device.surface_info(surface).context_id == context_id);
+            pending.surfaces = other_surfaces;
+            (surfaces, surface_textures)
+        };
+        if surfaces.is_empty() && surface_textures.is_empty() {
+            return Ok(());
+        }
+
+        let _guard = match device.make_context_current_scoped(context) {
+            Ok(guard) => guard,
+            Err(err) => {
+                let mut pending = self.lock();
+                pending.surfaces.extend(surfaces);
+                pending.surface_texture_count += surface_textures.len();
+                queue_surface_textures(pending.id, surface_textures);
+                return Err(err);
+            }
+        };
+
+        let mut first_error = None;
+        for surface_texture in surface_textures {
+            let surface_texture = ManuallyDrop::into_inner(surface_texture);
+            match device.destroy_surface_texture(context, surface_texture) {
+                Ok(surface) if device.surface_info(&surface).context_id == context_id => {
+                    surfaces.push(surface)
+                }
+                Ok(surface) => self.push_surface(surface),
+                Err((err, surface_texture)) => {
+                    warn!("Failed to destroy a queued surface texture: {:?}", err);
+                    mem::forget(surface_texture);
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        for mut surface in surfaces {
+            if let Err(err) = device.destroy_surface(context, &mut surface) {
+                warn!("Failed to destroy a queued surface: {:?}", err);
+                mem::forget(surface);
+                first_error.get_or_insert(err);
+            }
+        }
+
+        match first_error {
+            None => Ok(()),
+            Some(err) => Err(err),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PendingDestruction<Device>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<Device: DeviceAPI + 'static> ContextData<Device> {
+    fn lock_context(&self) -> MutexGuard<'_, ManuallyDrop<Device::Context>> {
+        self.context.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<Device: DeviceAPI + 'static> Drop for OwnedSurface<Device> {
+    fn drop(&mut self) {
+        let Some(mut surface) = self.surface.take() else {
+            return;
+        };
+        let result = self.context.with(|device, context| {
+            let _guard = device.make_context_current_scoped(context).ok()?;
+            Some(device.destroy_surface(context, &mut surface))
+        });
+        match result {
+            Some(Ok(())) => {}
+            Some(Err(err)) => {
+                warn!("Failed to destroy an owned surface: {:?}", err);
+                // There's nothing more we can do; leak the surface rather than panicking.
+                mem::forget(surface);
+            }
+            // The context is probably current on another thread, so leave the surface for that
+            // thread to destroy.
+            None => self.context.destruction_queue().push_surface(surface),
+        }
+    }
+}
+
+impl<Device: DeviceAPI + 'static> Drop for ContextData<Device> {
+    fn drop(&mut self) {
+        let device = self.device.lock();
+        let context = self
+            .context
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Err(err) = self.destruction_queue.drain(&device, context) {
+            warn!(
+                "Failed to drain the destruction queue of an owned context: {:?}",
+                err
+            );
+        }
+        match device.destroy_context(context) {
+            Ok(()) => unsafe { ManuallyDrop::drop(context) },
+            Err(err) => {
+                // Leak the context rather than panicking.
+                warn!("Failed to destroy an owned context: {:?}", err);
+            }
+        }
+    }
+}
+
+impl<Device: DeviceAPI + 'static> Drop for PendingDestruction<Device> {
+    fn drop(&mut self) {
+        // Queued surface textures are never dropped, so forgetting this thread's is enough. Any
+        // queued on other threads stay there until those threads exit.
+        let _ = QUEUED_SURFACE_TEXTURES.try_with(|queued| queued.borrow_mut().remove(&self.id));
+        if self.surfaces.is_empty() && self.surface_texture_count == 0 {
+            return;
+        }
+        warn!(
+            "Leaking {} surfaces and {} surface textures that were never destroyed",
+            self.surfaces.len(),
+            self.surface_texture_count
+        );
+        for surface in self.surfaces.drain(..) {
+            mem::forget(surface);
+        }
+    }
+}
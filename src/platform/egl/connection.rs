@@ -6,6 +6,8 @@
 
 use super::device::{Adapter, Device, NativeDevice};
 use super::surface::NativeWidget;
+use crate::platform::generic::egl::device::load_egl_library;
+use crate::platform::generic::egl::ffi::EGLDisplayExtensionFunctions;
 use crate::Error;
 use crate::GLApi;
 
@@ -30,6 +32,7 @@ impl Connection {
     /// Connects to the default display.
     #[inline]
     pub fn new() -> Result<Connection, Error> {
+        load_egl_library()?;
         Ok(Connection)
     }
 
@@ -95,6 +98,10 @@ impl Connection {
         &self,
         native_device: NativeDevice,
     ) -> Result<Device, Error> {
+        if !EGLDisplayExtensionFunctions::get(native_device.0).supports_egl_images() {
+            return Err(Error::RequiredExtensionUnavailable);
+        }
+
         Ok(Device {
             egl_display: native_device.0,
             display_is_owned: false,
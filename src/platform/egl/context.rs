@@ -6,10 +6,10 @@ use glow::HasContext;
 
 use super::device::Device;
 use super::surface::{Surface, SurfaceObjects};
-use crate::context::{ContextID, CREATE_CONTEXT_MUTEX};
+use crate::context::{ContextGL, ContextID, CREATE_CONTEXT_MUTEX};
 use crate::egl;
 use crate::egl::types::{EGLConfig, EGLContext, EGLSurface, EGLint};
-use crate::platform::generic::egl::context::{self, CurrentContextGuard};
+use crate::platform::generic::egl::context;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::error::ToWindowingApiError;
 use crate::platform::generic::egl::surface::ExternalEGLSurfaces;
@@ -20,7 +20,9 @@ use std::mem;
 use std::os::raw::c_void;
 use std::thread;
 
-pub use crate::platform::generic::egl::context::{ContextDescriptor, NativeContext};
+pub use crate::platform::generic::egl::context::{
+    ContextDescriptor, CurrentContextGuard, NativeContext,
+};
 
 /// Represents an OpenGL rendering context.
 ///
@@ -81,6 +83,19 @@ impl Device {
         }
     }
 
+    /// Creates a context descriptor for the first of `candidates` that this device can actually
+    /// create a context with.
+    ///
+    /// Candidates are tried in order, so list them from most to least preferred. Whatever context
+    /// was current before the call remains current.
+    pub fn negotiate_context_descriptor(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor, Error> {
+        let _guard = CurrentContextGuard::with_release_display(self.egl_display);
+        crate::context::negotiate_context_descriptor(self, candidates)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -104,8 +119,9 @@ impl Device {
                 gl_api,
             )?;
 
-            // Create a dummy pbuffer.
-            let pbuffer = context::create_dummy_pbuffer(egl_display, egl_context).unwrap();
+            // Create a dummy pbuffer, if we need one.
+            let pbuffer = context::create_default_surface(egl_display, egl_context)
+                .unwrap_or(egl::NO_SURFACE);
 
             EGL_FUNCTIONS.with(|egl| {
                 if egl.MakeCurrent(egl_display, pbuffer, pbuffer, egl_context) == egl::FALSE {
@@ -140,9 +156,9 @@ impl Device {
     ) -> Result<Context, Error> {
         let mut next_context_id = CREATE_CONTEXT_MUTEX.lock().unwrap();
 
-        // Create a dummy pbuffer.
-        let pbuffer =
-            context::create_dummy_pbuffer(self.egl_display, native_context.egl_context).unwrap();
+        // Create a dummy pbuffer, if we need one.
+        let pbuffer = context::create_default_surface(self.egl_display, native_context.egl_context)
+            .unwrap_or(egl::NO_SURFACE);
 
         // Create the context.
         let context = Context {
@@ -177,9 +193,11 @@ impl Device {
             }
 
             EGL_FUNCTIONS.with(|egl| {
-                let result = egl.DestroySurface(self.egl_display, context.pbuffer);
-                assert_ne!(result, egl::FALSE);
-                context.pbuffer = egl::NO_SURFACE;
+                if context.pbuffer != egl::NO_SURFACE {
+                    let result = egl.DestroySurface(self.egl_display, context.pbuffer);
+                    assert_ne!(result, egl::FALSE);
+                    context.pbuffer = egl::NO_SURFACE;
+                }
 
                 egl.MakeCurrent(
                     self.egl_display,
@@ -189,6 +207,7 @@ impl Device {
                 );
 
                 if context.context_is_owned {
+                    context::forget_created_context(self.egl_display, context.egl_context);
                     let result = egl.DestroyContext(self.egl_display, context.egl_context);
                     assert_ne!(result, egl::FALSE);
                 }
@@ -305,6 +324,30 @@ impl Device {
         }
     }
 
+    /// Attaches a surface to a context for reading, separately from the surface it renders to.
+    ///
+    /// This backend can't read from a separate surface, so this always returns an
+    /// `UnsupportedOnThisPlatform` error, alongside the surface.
+    #[inline]
+    pub fn bind_read_surface_to_context(
+        &self,
+        _: &mut Context,
+        surface: Surface,
+    ) -> Result<(), (Error, Surface)> {
+        Err((Error::UnsupportedOnThisPlatform, surface))
+    }
+
+    /// Removes and returns any read surface attached to this context.
+    ///
+    /// Read surfaces can't be bound on this backend, so this always returns `None`.
+    #[inline]
+    pub fn unbind_read_surface_from_context(
+        &self,
+        _: &mut Context,
+    ) -> Result<Option<Surface>, Error> {
+        Ok(None)
+    }
+
     /// Returns the attributes that the context descriptor was created with.
     pub fn context_descriptor_attributes(
         &self,
@@ -325,6 +368,20 @@ impl Device {
         context::get_proc_address(symbol_name)
     }
 
+    /// Makes the context current and returns the OpenGL function table that surfman loaded for
+    /// it.
+    ///
+    /// This lets applications share surfman's function pointers instead of loading their own with
+    /// `get_proc_address`. The context stays current until the returned value is dropped, at which
+    /// point whichever context was current before is made current again.
+    pub fn context_gl<'a>(
+        &self,
+        context: &'a Context,
+    ) -> Result<ContextGL<'a, CurrentContextGuard>, Error> {
+        let guard = self.make_context_current_scoped(context)?;
+        Ok(ContextGL::new(&context.gl, guard))
+    }
+
     pub(crate) fn context_to_egl_config(&self, context: &Context) -> EGLConfig {
         unsafe {
             context::egl_config_from_id(
@@ -338,6 +395,21 @@ impl Device {
         }
     }
 
+    /// Makes the context the current OpenGL context for this thread until the returned guard is
+    /// dropped.
+    ///
+    /// When the guard is dropped, whichever context and draw/read surfaces were current before
+    /// this call are made current again, even if they weren't created by `surfman`. If no context
+    /// was current, the context is released.
+    pub fn make_context_current_scoped(
+        &self,
+        context: &Context,
+    ) -> Result<CurrentContextGuard, Error> {
+        let guard = CurrentContextGuard::with_release_display(self.egl_display);
+        self.make_context_current(context)?;
+        Ok(guard)
+    }
+
     pub(crate) fn temporarily_make_context_current(
         &self,
         context: &Context,
@@ -367,6 +439,14 @@ impl Device {
         }
     }
 
+    /// Returns various information about the read surface attached to a context, if any.
+    ///
+    /// Read surfaces can't be bound on this backend, so this always returns `None`.
+    #[inline]
+    pub fn context_read_surface_info(&self, _: &Context) -> Result<Option<SurfaceInfo>, Error> {
+        Ok(None)
+    }
+
     /// Given a context, returns its underlying EGL context and attached surfaces.
     pub fn native_context(&self, context: &Context) -> NativeContext {
         let (egl_draw_surface, egl_read_surface) = match context.framebuffer {
@@ -3,10 +3,14 @@
 //! A thread-local handle to the device.
 
 use super::connection::Connection;
+use super::context::CurrentContextGuard;
 use crate::egl;
 use crate::egl::types::EGLDisplay;
+use crate::platform::generic::egl::device::terminate_owned_egl_display;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
-use crate::{Error, GLApi};
+use crate::platform::generic::egl::device::{egl_extensions, initialize_owned_egl_display};
+use crate::platform::generic::egl::ffi::EGLDisplayExtensionFunctions;
+use crate::{DeviceCapabilities, Error, GLApi};
 
 /// Represents a hardware display adapter that can be used for rendering (including the CPU).
 ///
@@ -28,14 +32,13 @@ pub struct NativeDevice(pub EGLDisplay);
 
 impl Drop for Device {
     fn drop(&mut self) {
-        EGL_FUNCTIONS.with(|egl| unsafe {
+        unsafe {
             if !self.display_is_owned {
                 return;
             }
-            let result = egl.Terminate(self.egl_display);
-            assert_ne!(result, egl::FALSE);
+            terminate_owned_egl_display(self.egl_display);
             self.egl_display = egl::NO_DISPLAY;
-        })
+        }
     }
 }
 
@@ -57,9 +60,15 @@ impl Device {
                 assert_ne!(egl_display, egl::NO_DISPLAY);
 
                 // I don't think this should ever fail.
-                let (mut major_version, mut minor_version) = (0, 0);
-                let result = egl.Initialize(egl_display, &mut major_version, &mut minor_version);
-                assert_ne!(result, egl::FALSE);
+                let result = initialize_owned_egl_display(egl_display);
+                assert!(result);
+
+                // Surfaces on this platform are backed by EGL images, so we can't do anything
+                // without them.
+                if !EGLDisplayExtensionFunctions::get(egl_display).supports_egl_images() {
+                    terminate_owned_egl_display(egl_display);
+                    return Err(Error::RequiredExtensionUnavailable);
+                }
 
                 Ok(Device {
                     egl_display,
@@ -92,4 +101,14 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GLES
     }
+
+    /// Returns the OpenGL versions, limits, surface formats, and extensions that this device
+    /// supports.
+    ///
+    /// This creates and destroys temporary contexts, so callers should cache the result.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        let _guard = CurrentContextGuard::with_release_display(self.egl_display);
+        let extensions = unsafe { egl_extensions(self.egl_display) };
+        DeviceCapabilities::probe(self, extensions, true, false)
+    }
 }
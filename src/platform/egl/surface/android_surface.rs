@@ -22,13 +22,14 @@ use crate::gl;
 use crate::gl_utils;
 use crate::platform::generic;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::ffi::EGLDisplayExtensionFunctions;
 use crate::platform::generic::egl::ffi::EGLImageKHR;
-use crate::platform::generic::egl::ffi::EGL_EXTENSION_FUNCTIONS;
 use crate::platform::generic::egl::ffi::EGL_IMAGE_PRESERVED_KHR;
 use crate::platform::generic::egl::ffi::EGL_NATIVE_BUFFER_ANDROID;
 use crate::platform::generic::egl::ffi::EGL_NO_IMAGE_KHR;
 use crate::renderbuffers::Renderbuffers;
-use crate::{Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType, WindowingApiError};
+use crate::WindowingApiError;
+use crate::{ContextAttributeFlags, Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType};
 
 use euclid::default::Size2D;
 use glow::{HasContext, Texture};
@@ -68,17 +69,50 @@ impl Device {
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
         match surface_type {
-            SurfaceType::Generic { size } => self.create_generic_surface(context, &size),
+            SurfaceType::Generic { size } => self.create_generic_surface(context, &size, None),
             SurfaceType::Widget { native_widget } => unsafe {
                 self.create_window_surface(context, native_widget.native_window)
             },
         }
     }
 
+    /// Like `create_surface()`, but the format of a generic surface is chosen by the `ALPHA`,
+    /// `DEPTH`, and `STENCIL` flags in `surface_flags` instead of by the context's descriptor.
+    ///
+    /// Widget surfaces always use the format of the context's descriptor.
+    pub fn create_surface_with_attributes(
+        &mut self,
+        context: &Context,
+        surface_access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget>,
+        surface_flags: ContextAttributeFlags,
+    ) -> Result<Surface, Error> {
+        match surface_type {
+            SurfaceType::Generic { size } => {
+                self.create_generic_surface(context, &size, Some(surface_flags))
+            }
+            surface_type => self.create_surface(context, surface_access, surface_type),
+        }
+    }
+
+    /// Creates a generic surface that renders into an existing OpenGL texture.
+    ///
+    /// This is not yet supported on Android, where generic surfaces are backed by hardware
+    /// buffers; it always returns `UnsupportedOnThisPlatform`.
+    pub fn create_surface_from_gl_texture(
+        &mut self,
+        _: &Context,
+        _: &Size2D<i32>,
+        _: Texture,
+    ) -> Result<Surface, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
     fn create_generic_surface(
         &mut self,
         context: &Context,
         size: &Size2D<i32>,
+        surface_flags: Option<ContextAttributeFlags>,
     ) -> Result<Surface, Error> {
         let _guard = self.temporarily_make_context_current(context)?;
         let gl = &context.gl;
@@ -107,7 +141,18 @@ impl Device {
             let egl_image = self.create_egl_image(context, hardware_buffer);
 
             // Initialize and bind the image to the texture.
-            let texture_object = generic::egl::surface::bind_egl_image_to_gl_texture(gl, egl_image);
+            let texture_object = match generic::egl::surface::bind_egl_image_to_gl_texture(
+                gl,
+                self.egl_display,
+                egl_image,
+            ) {
+                Ok(texture_object) => texture_object,
+                Err(err) => {
+                    self.destroy_egl_image(egl_image);
+                    AHardwareBuffer_release(hardware_buffer);
+                    return Err(err);
+                }
+            };
 
             // Create the framebuffer, and bind the texture to it.
             let framebuffer_object = gl_utils::create_and_bind_framebuffer(
@@ -118,7 +163,10 @@ impl Device {
 
             // Bind renderbuffers as appropriate.
             let context_descriptor = self.context_descriptor(context);
-            let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+            let mut context_attributes = self.context_descriptor_attributes(&context_descriptor);
+            if let Some(surface_flags) = surface_flags {
+                context_attributes = context_attributes.with_surface_flags(surface_flags);
+            }
             let renderbuffers = Renderbuffers::new(gl, size, &context_attributes);
             renderbuffers.bind_to_current_framebuffer(gl);
 
@@ -196,8 +244,17 @@ impl Device {
                     let gl = &context.gl;
 
                     let local_egl_image = self.create_egl_image(context, hardware_buffer);
-                    let texture_object =
-                        generic::egl::surface::bind_egl_image_to_gl_texture(gl, local_egl_image);
+                    let texture_object = match generic::egl::surface::bind_egl_image_to_gl_texture(
+                        gl,
+                        self.egl_display,
+                        local_egl_image,
+                    ) {
+                        Ok(texture_object) => texture_object,
+                        Err(err) => {
+                            self.destroy_egl_image(local_egl_image);
+                            return Err((err, surface));
+                        }
+                    };
                     Ok(SurfaceTexture {
                         surface,
                         local_egl_image,
@@ -243,6 +300,18 @@ impl Device {
         Ok(())
     }
 
+    unsafe fn destroy_egl_image(&self, egl_image: EGLImageKHR) {
+        // Without `EGL_KHR_image_base`, no image could have been created in the first place.
+        let Some(destroy_image) =
+            EGLDisplayExtensionFunctions::get(self.egl_display).DestroyImageKHR
+        else {
+            return;
+        };
+        if destroy_image(self.egl_display, egl_image) == egl::FALSE {
+            warn!("Failed to destroy an EGL image");
+        }
+    }
+
     #[allow(non_snake_case)]
     unsafe fn create_egl_image(
         &self,
@@ -250,8 +319,9 @@ impl Device {
         hardware_buffer: *mut AHardwareBuffer,
     ) -> EGLImageKHR {
         // Get the native client buffer.
-        let eglGetNativeClientBufferANDROID =
-            EGL_EXTENSION_FUNCTIONS.GetNativeClientBufferANDROID.expect(
+        let eglGetNativeClientBufferANDROID = EGLDisplayExtensionFunctions::get(self.egl_display)
+            .GetNativeClientBufferANDROID
+            .expect(
                 "Where's the `EGL_ANDROID_get_native_client_buffer` \
                                             extension?",
             );
@@ -266,7 +336,10 @@ impl Device {
             egl::NONE as EGLint,
             0,
         ];
-        let egl_image = (EGL_EXTENSION_FUNCTIONS.CreateImageKHR)(
+        let create_image = EGLDisplayExtensionFunctions::get(self.egl_display)
+            .CreateImageKHR
+            .unwrap();
+        let egl_image = create_image(
             self.egl_display,
             egl::NO_CONTEXT,
             EGL_NATIVE_BUFFER_ANDROID,
@@ -314,9 +387,7 @@ impl Device {
                         gl.delete_texture(texture);
                     }
 
-                    let egl_display = self.egl_display;
-                    let result = (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, *egl_image);
-                    assert_ne!(result, egl::FALSE);
+                    self.destroy_egl_image(*egl_image);
                     *egl_image = EGL_NO_IMAGE_KHR;
 
                     AHardwareBuffer_release(*hardware_buffer);
@@ -354,12 +425,7 @@ impl Device {
                 gl.delete_texture(texture);
             }
 
-            let egl_display = self.egl_display;
-            let result = (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(
-                egl_display,
-                surface_texture.local_egl_image,
-            );
-            assert_ne!(result, egl::FALSE);
+            self.destroy_egl_image(surface_texture.local_egl_image);
             surface_texture.local_egl_image = EGL_NO_IMAGE_KHR;
         }
 
@@ -4,32 +4,55 @@
 
 use super::device::EGL_FUNCTIONS;
 use super::error::ToWindowingApiError;
-use super::ffi::EGL_CONTEXT_OPENGL_PROFILE_MASK;
+use super::ffi::EGLDisplayExtensionFunctions;
+use super::ffi::EGL_NO_CONFIG_KHR;
 use super::ffi::{EGL_CONTEXT_MINOR_VERSION_KHR, EGL_CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT};
+use super::ffi::{EGL_CONTEXT_OPENGL_NO_ERROR_KHR, EGL_CONTEXT_OPENGL_PROFILE_MASK};
+use super::ffi::{EGL_CONTEXT_PRIORITY_HIGH_IMG, EGL_CONTEXT_PRIORITY_LEVEL_IMG};
+use super::ffi::{EGL_CONTEXT_PRIORITY_LOW_IMG, EGL_CONTEXT_PRIORITY_MEDIUM_IMG};
 use super::surface::{EGLBackedSurface, ExternalEGLSurfaces};
 use crate::context::{self, CREATE_CONTEXT_MUTEX};
 use crate::egl;
-use crate::egl::types::{EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLint};
+use crate::egl::types::{EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLenum, EGLint};
 use crate::surface::Framebuffer;
-use crate::{ContextAttributeFlags, ContextAttributes, ContextID, Error, GLApi, GLVersion};
-use crate::{Gl, SurfaceInfo};
+use crate::{gl, gl_utils, Gl, SurfaceInfo};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextID, ContextPriority};
+use crate::{Error, GLApi, GLVersion};
 use glow::HasContext;
 
+use std::collections::HashMap;
 use std::ffi::CString;
 use std::mem;
 use std::os::raw::c_void;
 use std::ptr;
+use std::sync::{LazyLock, Mutex};
 use std::thread;
 
 #[allow(dead_code)]
 const DUMMY_PBUFFER_SIZE: EGLint = 16;
 const RGB_CHANNEL_BIT_DEPTH: EGLint = 8;
 
+// What `create_context()` knows about each context it created that EGL can't tell us later, keyed
+// by display and context, since context handles are only unique within a display.
+static CREATED_CONTEXTS: LazyLock<Mutex<HashMap<(usize, usize), CreatedContextInfo>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone, Copy)]
+struct CreatedContextInfo {
+    // The config that surfaces should use by default. Contexts created without a config report a
+    // config ID of zero, so this is the only record of it.
+    surface_config_id: EGLint,
+    // Whether error checking was disabled. There's no EGL query for this, and `GL_CONTEXT_FLAGS`
+    // is missing on older versions.
+    no_error: bool,
+}
+
 pub(crate) struct EGLBackedContext {
     pub(crate) egl_context: EGLContext,
     pub(crate) id: ContextID,
     pbuffer: EGLSurface,
     framebuffer: Framebuffer<EGLBackedSurface, ExternalEGLSurfaces>,
+    read_surface: Option<EGLBackedSurface>,
     context_is_owned: bool,
 }
 
@@ -52,14 +75,29 @@ pub struct ContextDescriptor {
     pub(crate) egl_config_id: EGLint,
     pub(crate) gl_version: GLVersion,
     pub(crate) compatibility_profile: bool,
+    // If true, contexts are created without a config, and `egl_config_id` only provides the
+    // default format for surfaces. It may be zero if that format is unknown.
+    pub(crate) no_config: bool,
+    // If true, contexts are created without error checking. This is false if `NO_ERROR` was
+    // requested but the display doesn't support it.
+    pub(crate) no_error: bool,
+    // The requested scheduling priority, or, for descriptors of existing contexts, the granted
+    // one. This is `Medium` if the display doesn't support priorities.
+    pub(crate) priority: ContextPriority,
 }
 
+/// Restores the previously-current EGL context and draw/read surfaces when dropped.
+///
+/// This is returned by `Device::make_context_current_scoped()`. The previous state is captured
+/// straight from EGL, so contexts that were not created by `surfman` are restored as well. If no
+/// context was current beforehand, the device's display is released on drop instead.
 #[must_use]
-pub(crate) struct CurrentContextGuard {
+pub struct CurrentContextGuard {
     egl_display: EGLDisplay,
     old_egl_draw_surface: EGLSurface,
     old_egl_read_surface: EGLSurface,
     old_egl_context: EGLContext,
+    release_egl_display: EGLDisplay,
 }
 
 impl Drop for EGLBackedContext {
@@ -81,6 +119,13 @@ impl Drop for CurrentContextGuard {
                     self.old_egl_read_surface,
                     self.old_egl_context,
                 );
+            } else if self.release_egl_display != egl::NO_DISPLAY {
+                egl.MakeCurrent(
+                    self.release_egl_display,
+                    egl::NO_SURFACE,
+                    egl::NO_SURFACE,
+                    egl::NO_CONTEXT,
+                );
             }
         })
     }
@@ -103,14 +148,15 @@ impl EGLBackedContext {
             gl_api,
         )?;
 
-        // Create a dummy pbuffer.
-        let pbuffer = create_dummy_pbuffer(egl_display, egl_context).unwrap_or(egl::NO_SURFACE);
+        // Create a dummy pbuffer, if we need one.
+        let pbuffer = create_default_surface(egl_display, egl_context).unwrap_or(egl::NO_SURFACE);
 
         // Wrap and return it.
         let context = EGLBackedContext {
             egl_context,
             id: *next_context_id,
             framebuffer: Framebuffer::None,
+            read_surface: None,
             context_is_owned: true,
             pbuffer,
         };
@@ -127,6 +173,7 @@ impl EGLBackedContext {
                 draw: native_context.egl_draw_surface,
                 read: native_context.egl_read_surface,
             }),
+            read_surface: None,
             context_is_owned: false,
             pbuffer: egl::NO_SURFACE,
         };
@@ -150,6 +197,7 @@ impl EGLBackedContext {
             );
 
             if self.context_is_owned {
+                forget_created_context(egl_display, self.egl_context);
                 let result = egl.DestroyContext(egl_display, self.egl_context);
                 assert_ne!(result, egl::FALSE);
             }
@@ -159,11 +207,7 @@ impl EGLBackedContext {
     }
 
     pub(crate) fn native_context(&self) -> NativeContext {
-        let egl_surfaces = match self.framebuffer {
-            Framebuffer::Surface(ref surface) => surface.egl_surfaces(),
-            Framebuffer::External(ref surfaces) => (*surfaces).clone(),
-            Framebuffer::None => ExternalEGLSurfaces::default(),
-        };
+        let egl_surfaces = self.egl_surfaces(egl::NO_SURFACE);
 
         NativeContext {
             egl_context: self.egl_context,
@@ -172,16 +216,37 @@ impl EGLBackedContext {
         }
     }
 
-    pub(crate) unsafe fn make_current(&self, egl_display: EGLDisplay) -> Result<(), Error> {
-        let egl_surfaces = match self.framebuffer {
+    // Returns the EGL surfaces to draw to and read from, using `default_egl_surface` if no surface
+    // is bound.
+    fn egl_surfaces(&self, default_egl_surface: EGLSurface) -> ExternalEGLSurfaces {
+        let mut egl_surfaces = match self.framebuffer {
             Framebuffer::Surface(ref surface) => surface.egl_surfaces(),
             Framebuffer::External(ref surfaces) => (*surfaces).clone(),
             Framebuffer::None => ExternalEGLSurfaces {
-                draw: self.pbuffer,
-                read: self.pbuffer,
+                draw: default_egl_surface,
+                read: default_egl_surface,
             },
         };
 
+        // Generic surfaces are read through their framebuffer objects, so only window surfaces
+        // change what EGL reads from. EGL requires either both or neither of the draw and read
+        // surfaces to be present, so reuse the read surface for drawing if necessary.
+        if let Some(ref read_surface) = self.read_surface {
+            let read_egl_surface = read_surface.egl_surfaces().read;
+            if read_egl_surface != egl::NO_SURFACE {
+                egl_surfaces.read = read_egl_surface;
+                if egl_surfaces.draw == egl::NO_SURFACE {
+                    egl_surfaces.draw = read_egl_surface;
+                }
+            }
+        }
+
+        egl_surfaces
+    }
+
+    pub(crate) unsafe fn make_current(&self, egl_display: EGLDisplay) -> Result<(), Error> {
+        let egl_surfaces = self.egl_surfaces(self.pbuffer);
+
         EGL_FUNCTIONS.with(|egl| {
             let result = egl.MakeCurrent(
                 egl_display,
@@ -204,6 +269,7 @@ impl EGLBackedContext {
 
     pub(crate) unsafe fn bind_surface(
         &mut self,
+        gl: &Gl,
         egl_display: EGLDisplay,
         surface: EGLBackedSurface,
     ) -> Result<(), (Error, EGLBackedSurface)> {
@@ -219,7 +285,8 @@ impl EGLBackedContext {
 
         // If we're current, call `make_context_current()` again to switch to the new framebuffer.
         if self.is_current() {
-            drop(self.make_current(egl_display))
+            drop(self.make_current(egl_display));
+            self.bind_read_and_draw_framebuffers(gl);
         }
 
         Ok(())
@@ -239,17 +306,106 @@ impl EGLBackedContext {
             Framebuffer::External(_) => return Err(Error::ExternalRenderTarget),
         }
 
-        let surface = match mem::replace(&mut self.framebuffer, Framebuffer::None) {
+        let mut surface = match mem::replace(&mut self.framebuffer, Framebuffer::None) {
             Framebuffer::Surface(surface) => surface,
             Framebuffer::None | Framebuffer::External(_) => unreachable!(),
         };
 
+        // Without EGL images, surface textures are made from a copy of the surface's pixels, so
+        // read them back while we still can.
+        if surface.needs_read_back() {
+            let _guard = CurrentContextGuard::new();
+            if self.make_current(egl_display).is_ok() {
+                surface.read_back_pixels(gl);
+            }
+        }
+
         // If we're current, we stay current, but with no surface attached.
         surface.unbind(gl, egl_display, self.egl_context);
 
+        // That detached the read surface too, so reattach it.
+        if self.read_surface.is_some() && self.is_current() {
+            drop(self.make_current(egl_display));
+            self.bind_read_and_draw_framebuffers(gl);
+        }
+
+        Ok(Some(surface))
+    }
+
+    pub(crate) unsafe fn bind_read_surface(
+        &mut self,
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        surface: EGLBackedSurface,
+    ) -> Result<(), (Error, EGLBackedSurface)> {
+        if self.id != surface.context_id {
+            return Err((Error::IncompatibleSurface, surface));
+        }
+        if let Framebuffer::External(_) = self.framebuffer {
+            return Err((Error::ExternalRenderTarget, surface));
+        }
+        if self.read_surface.is_some() {
+            return Err((Error::SurfaceAlreadyBound, surface));
+        }
+
+        self.read_surface = Some(surface);
+
+        // If we're current, call `make_context_current()` again to switch to the new read surface.
+        if self.is_current() {
+            drop(self.make_current(egl_display));
+            self.bind_read_and_draw_framebuffers(gl);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) unsafe fn unbind_read_surface(
+        &mut self,
+        gl: &Gl,
+        egl_display: EGLDisplay,
+    ) -> Result<Option<EGLBackedSurface>, Error> {
+        let surface = match self.read_surface.take() {
+            None => return Ok(None),
+            Some(surface) => surface,
+        };
+
+        // If we're current, we stay current, but with only the draw surface attached, which is
+        // now read from as well.
+        if self.is_current() {
+            if let Some(framebuffer_object) = surface.info().framebuffer_object {
+                gl_utils::unbind_framebuffer_if_necessary(gl, framebuffer_object);
+            }
+            drop(self.make_current(egl_display));
+            let draw_framebuffer_object = self
+                .bound_surface()
+                .and_then(|surface| surface.info().framebuffer_object);
+            gl.bind_framebuffer(gl::FRAMEBUFFER, draw_framebuffer_object);
+        }
+
         Ok(Some(surface))
     }
 
+    // If a separate read surface is bound, binds its framebuffer object to `GL_READ_FRAMEBUFFER`
+    // and that of the draw surface to `GL_DRAW_FRAMEBUFFER`, so that the context reads from one
+    // and draws to the other without the caller binding anything. Window surfaces have no
+    // framebuffer object, so EGL's default framebuffer is bound for them instead. The context
+    // must be current.
+    pub(crate) unsafe fn bind_read_and_draw_framebuffers(&self, gl: &Gl) {
+        let read_surface = match self.read_surface {
+            Some(ref read_surface) => read_surface,
+            None => return,
+        };
+        // Separate read and draw framebuffers need OpenGL (ES) 3.0.
+        if gl.version().major < 3 {
+            return;
+        }
+        let draw_framebuffer_object = self
+            .bound_surface()
+            .and_then(|surface| surface.info().framebuffer_object);
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, read_surface.info().framebuffer_object);
+        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, draw_framebuffer_object);
+    }
+
     pub(crate) fn surface_info(&self) -> Result<Option<SurfaceInfo>, Error> {
         match self.framebuffer {
             Framebuffer::None => Ok(None),
@@ -257,6 +413,17 @@ impl EGLBackedContext {
             Framebuffer::Surface(ref surface) => Ok(Some(surface.info())),
         }
     }
+
+    pub(crate) fn bound_surface(&self) -> Option<&EGLBackedSurface> {
+        match self.framebuffer {
+            Framebuffer::Surface(ref surface) => Some(surface),
+            Framebuffer::None | Framebuffer::External(_) => None,
+        }
+    }
+
+    pub(crate) fn read_surface_info(&self) -> Result<Option<SurfaceInfo>, Error> {
+        Ok(self.read_surface.as_ref().map(EGLBackedSurface::info))
+    }
 }
 
 impl NativeContext {
@@ -305,6 +472,25 @@ impl ContextDescriptor {
 
         let compatibility_profile = flags.contains(ContextAttributeFlags::COMPATIBILITY_PROFILE);
 
+        let no_config = flags.contains(ContextAttributeFlags::NO_CONFIG);
+        if no_config && !EGLDisplayExtensionFunctions::get(egl_display).no_config_context {
+            return Err(Error::RequiredExtensionUnavailable);
+        }
+
+        let mut no_error = flags.contains(ContextAttributeFlags::NO_ERROR);
+        if no_error && !EGLDisplayExtensionFunctions::get(egl_display).create_context_no_error {
+            warn!("`EGL_KHR_create_context_no_error` is unavailable; ignoring `NO_ERROR`");
+            no_error = false;
+        }
+
+        let mut priority = attributes.priority;
+        if priority != ContextPriority::Medium
+            && !EGLDisplayExtensionFunctions::get(egl_display).context_priority
+        {
+            warn!("`EGL_IMG_context_priority` is unavailable; ignoring context priority");
+            priority = ContextPriority::Medium;
+        }
+
         // Mesa doesn't support the OpenGL compatibility profile post version 3.0. Take that into
         // account.
         if compatibility_profile
@@ -397,6 +583,9 @@ impl ContextDescriptor {
                 egl_config_id,
                 gl_version,
                 compatibility_profile,
+                no_config,
+                no_error,
+                priority,
             })
         })
     }
@@ -406,14 +595,30 @@ impl ContextDescriptor {
         egl_display: EGLDisplay,
         egl_context: EGLContext,
     ) -> ContextDescriptor {
-        let egl_config_id = get_context_attr(egl_display, egl_context, egl::CONFIG_ID as EGLint);
+        let no_config = get_context_attr(egl_display, egl_context, egl::CONFIG_ID as EGLint) == 0;
+        let egl_config_id = surface_config_id(egl_display, egl_context);
         let gl_version = GLVersion::current(&gl);
         let compatibility_profile = context::current_context_uses_compatibility_profile(&gl);
+        let no_error = created_context_has_no_error(egl_display, egl_context)
+            .unwrap_or_else(|| context::current_context_has_no_error(gl));
+        let priority = if EGLDisplayExtensionFunctions::get(egl_display).context_priority {
+            let level = get_context_attr(
+                egl_display,
+                egl_context,
+                EGL_CONTEXT_PRIORITY_LEVEL_IMG as EGLint,
+            );
+            priority_from_level(level)
+        } else {
+            ContextPriority::Medium
+        };
 
         ContextDescriptor {
             egl_config_id,
             gl_version,
             compatibility_profile,
+            no_config,
+            no_error,
+            priority,
         }
     }
 
@@ -444,17 +649,22 @@ impl ContextDescriptor {
     }
 
     pub(crate) unsafe fn attributes(&self, egl_display: EGLDisplay) -> ContextAttributes {
-        let egl_config = egl_config_from_id(egl_display, self.egl_config_id);
-
-        let alpha_size = get_config_attr(egl_display, egl_config, egl::ALPHA_SIZE as EGLint);
-        let depth_size = get_config_attr(egl_display, egl_config, egl::DEPTH_SIZE as EGLint);
-        let stencil_size = get_config_attr(egl_display, egl_config, egl::STENCIL_SIZE as EGLint);
-
         // Convert to `surfman` context attribute flags.
         let mut attribute_flags = ContextAttributeFlags::empty();
-        attribute_flags.set(ContextAttributeFlags::ALPHA, alpha_size != 0);
-        attribute_flags.set(ContextAttributeFlags::DEPTH, depth_size != 0);
-        attribute_flags.set(ContextAttributeFlags::STENCIL, stencil_size != 0);
+        if self.egl_config_id != 0 {
+            let egl_config = egl_config_from_id(egl_display, self.egl_config_id);
+
+            let alpha_size = get_config_attr(egl_display, egl_config, egl::ALPHA_SIZE as EGLint);
+            let depth_size = get_config_attr(egl_display, egl_config, egl::DEPTH_SIZE as EGLint);
+            let stencil_size =
+                get_config_attr(egl_display, egl_config, egl::STENCIL_SIZE as EGLint);
+
+            attribute_flags.set(ContextAttributeFlags::ALPHA, alpha_size != 0);
+            attribute_flags.set(ContextAttributeFlags::DEPTH, depth_size != 0);
+            attribute_flags.set(ContextAttributeFlags::STENCIL, stencil_size != 0);
+        }
+        attribute_flags.set(ContextAttributeFlags::NO_CONFIG, self.no_config);
+        attribute_flags.set(ContextAttributeFlags::NO_ERROR, self.no_error);
 
         attribute_flags.set(
             ContextAttributeFlags::COMPATIBILITY_PROFILE,
@@ -465,23 +675,52 @@ impl ContextDescriptor {
         ContextAttributes {
             flags: attribute_flags,
             version: self.gl_version,
+            priority: self.priority,
         }
     }
 }
 
 impl CurrentContextGuard {
     pub(crate) fn new() -> CurrentContextGuard {
+        CurrentContextGuard::with_release_display(egl::NO_DISPLAY)
+    }
+
+    /// Like `new()`, but if no context is current, `release_egl_display` is made to have no
+    /// current context again when the guard is dropped.
+    pub(crate) fn with_release_display(release_egl_display: EGLDisplay) -> CurrentContextGuard {
         EGL_FUNCTIONS.with(|egl| unsafe {
             CurrentContextGuard {
                 egl_display: egl.GetCurrentDisplay(),
                 old_egl_draw_surface: egl.GetCurrentSurface(egl::DRAW as EGLint),
                 old_egl_read_surface: egl.GetCurrentSurface(egl::READ as EGLint),
                 old_egl_context: egl.GetCurrentContext(),
+                release_egl_display,
             }
         })
     }
 }
 
+// Returns the `EGL_RENDERABLE_TYPE` bit that configs for contexts of the given API must have.
+//
+// OpenGL ES has no compatibility profile, so requesting one fails with `UnsupportedGLProfile`.
+#[cfg(free_unix)]
+pub(crate) fn renderable_type(
+    gl_api: GLApi,
+    attributes: &ContextAttributes,
+) -> Result<EGLint, Error> {
+    match gl_api {
+        GLApi::GL => Ok(egl::OPENGL_BIT as EGLint),
+        GLApi::GLES
+            if attributes
+                .flags
+                .contains(ContextAttributeFlags::COMPATIBILITY_PROFILE) =>
+        {
+            Err(Error::UnsupportedGLProfile)
+        }
+        GLApi::GLES => Ok(egl::OPENGL_ES2_BIT as EGLint),
+    }
+}
+
 pub(crate) unsafe fn create_context(
     egl_display: EGLDisplay,
     descriptor: &ContextDescriptor,
@@ -496,7 +735,11 @@ pub(crate) unsafe fn create_context(
         assert_ne!(ok, egl::FALSE);
     });
 
-    let egl_config = egl_config_from_id(egl_display, descriptor.egl_config_id);
+    let egl_config = if descriptor.no_config {
+        EGL_NO_CONFIG_KHR
+    } else {
+        egl_config_from_id(egl_display, descriptor.egl_config_id)
+    };
 
     let mut egl_context_attributes = vec![
         egl::CONTEXT_CLIENT_VERSION as EGLint,
@@ -514,6 +757,20 @@ pub(crate) unsafe fn create_context(
         ]);
     }
 
+    if descriptor.no_error {
+        egl_context_attributes.extend(&[
+            EGL_CONTEXT_OPENGL_NO_ERROR_KHR as EGLint,
+            egl::TRUE as EGLint,
+        ]);
+    }
+
+    if descriptor.priority != ContextPriority::Medium {
+        egl_context_attributes.extend(&[
+            EGL_CONTEXT_PRIORITY_LEVEL_IMG as EGLint,
+            priority_level(descriptor.priority),
+        ]);
+    }
+
     // Include some extra zeroes to work around broken implementations.
     //
     // FIXME(pcwalton): Which implementations are those? (This is copied from Gecko.)
@@ -532,10 +789,49 @@ pub(crate) unsafe fn create_context(
             return Err(Error::ContextCreationFailed(err));
         }
 
+        let mut created_contexts = CREATED_CONTEXTS.lock().unwrap();
+        created_contexts.insert(
+            (egl_display as usize, egl_context as usize),
+            CreatedContextInfo {
+                surface_config_id: descriptor.egl_config_id,
+                no_error: descriptor.no_error,
+            },
+        );
+
         Ok(egl_context)
     })
 }
 
+// Returns the ID of the config that surfaces for the given context should use by default.
+//
+// Contexts created without a config report a config ID of zero. If we created the context, we know
+// which config its descriptor picked for surfaces; otherwise, this returns zero too.
+pub(crate) unsafe fn surface_config_id(egl_display: EGLDisplay, egl_context: EGLContext) -> EGLint {
+    let egl_config_id = get_context_attr(egl_display, egl_context, egl::CONFIG_ID as EGLint);
+    if egl_config_id != 0 {
+        return egl_config_id;
+    }
+    let created_contexts = CREATED_CONTEXTS.lock().unwrap();
+    created_contexts
+        .get(&(egl_display as usize, egl_context as usize))
+        .map_or(0, |info| info.surface_config_id)
+}
+
+// Returns whether the given context was created without error checking, if `create_context()`
+// created it. Foreign contexts return `None` and have to be queried instead.
+fn created_context_has_no_error(egl_display: EGLDisplay, egl_context: EGLContext) -> Option<bool> {
+    let created_contexts = CREATED_CONTEXTS.lock().unwrap();
+    created_contexts
+        .get(&(egl_display as usize, egl_context as usize))
+        .map(|info| info.no_error)
+}
+
+// Forgets what `create_context()` recorded about a context. Call this when destroying contexts.
+pub(crate) fn forget_created_context(egl_display: EGLDisplay, egl_context: EGLContext) {
+    let mut created_contexts = CREATED_CONTEXTS.lock().unwrap();
+    created_contexts.remove(&(egl_display as usize, egl_context as usize));
+}
+
 pub(crate) unsafe fn make_no_context_current(egl_display: EGLDisplay) -> Result<(), Error> {
     EGL_FUNCTIONS.with(|egl| {
         let result = egl.MakeCurrent(
@@ -565,6 +861,23 @@ pub(crate) unsafe fn get_config_attr(
     })
 }
 
+fn priority_level(priority: ContextPriority) -> EGLint {
+    let level = match priority {
+        ContextPriority::Low => EGL_CONTEXT_PRIORITY_LOW_IMG,
+        ContextPriority::Medium => EGL_CONTEXT_PRIORITY_MEDIUM_IMG,
+        ContextPriority::High => EGL_CONTEXT_PRIORITY_HIGH_IMG,
+    };
+    level as EGLint
+}
+
+fn priority_from_level(level: EGLint) -> ContextPriority {
+    match level as EGLenum {
+        EGL_CONTEXT_PRIORITY_LOW_IMG => ContextPriority::Low,
+        EGL_CONTEXT_PRIORITY_HIGH_IMG => ContextPriority::High,
+        _ => ContextPriority::Medium,
+    }
+}
+
 pub(crate) unsafe fn get_context_attr(
     egl_display: EGLDisplay,
     egl_context: EGLContext,
@@ -613,14 +926,31 @@ pub(crate) fn get_proc_address(symbol_name: &str) -> *const c_void {
     })
 }
 
+// Returns the surface that the given context should be made current with while no surface is bound
+// to it. If the display supports `EGL_KHR_surfaceless_context`, no surface is needed and this
+// returns `egl::NO_SURFACE`; otherwise, this falls back to creating a dummy pbuffer.
+pub(crate) unsafe fn create_default_surface(
+    egl_display: EGLDisplay,
+    egl_context: EGLContext,
+) -> Option<EGLSurface> {
+    if EGLDisplayExtensionFunctions::get(egl_display).surfaceless_context {
+        return Some(egl::NO_SURFACE);
+    }
+    create_dummy_pbuffer(egl_display, egl_context)
+}
+
 // Creates and returns a dummy pbuffer surface for the given context. This is used as the default
-// framebuffer on some backends.
-#[allow(dead_code)]
-pub(crate) unsafe fn create_dummy_pbuffer(
+// framebuffer on backends without surfaceless contexts.
+unsafe fn create_dummy_pbuffer(
     egl_display: EGLDisplay,
     egl_context: EGLContext,
 ) -> Option<EGLSurface> {
-    let egl_config_id = get_context_attr(egl_display, egl_context, egl::CONFIG_ID as EGLint);
+    // Contexts without a config can't tell us what kind of pbuffer they are compatible with, so
+    // fall back to the config their descriptor picked, if we created them.
+    let egl_config_id = surface_config_id(egl_display, egl_context);
+    if egl_config_id == 0 {
+        return None;
+    }
     let egl_config = egl_config_from_id(egl_display, egl_config_id);
 
     let pbuffer_attributes = [
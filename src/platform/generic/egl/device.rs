@@ -2,47 +2,158 @@
 //
 //! Functionality common to backends using EGL displays.
 
+use crate::egl;
+use crate::egl::types::{EGLDisplay, EGLint};
 use crate::egl::Egl;
+use crate::platform::generic::egl::ffi::EGLDisplayExtensionFunctions;
+use crate::platform::generic::egl::shader_cache;
+use crate::Error;
 
 #[cfg(not(target_os = "windows"))]
 use libc::{dlopen, dlsym, RTLD_LAZY};
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::ffi::{CStr, CString};
 use std::mem;
 use std::os::raw::c_void;
-use std::sync::LazyLock;
+#[cfg(not(target_os = "windows"))]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(target_os = "windows")]
+use std::os::windows::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::{LazyLock, Mutex, OnceLock};
 #[cfg(target_os = "windows")]
 use winapi::shared::minwindef::HMODULE;
 #[cfg(target_os = "windows")]
 use winapi::um::libloaderapi;
 
-thread_local! {
-    pub static EGL_FUNCTIONS: Egl = Egl::load_with(get_proc_address);
+/// The EGL function table, shared by all threads.
+pub(crate) static EGL_FUNCTIONS: LazyLock<EGLFunctions> =
+    LazyLock::new(|| EGLFunctions(Egl::load_with(get_proc_address)));
+
+pub(crate) struct EGLFunctions(Egl);
+
+// The table only holds function pointers, which are valid on every thread.
+unsafe impl Send for EGLFunctions {}
+unsafe impl Sync for EGLFunctions {}
+
+impl EGLFunctions {
+    /// Calls `f` with the EGL function table.
+    #[inline]
+    pub(crate) fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Egl) -> R,
+    {
+        f(&self.0)
+    }
+}
+
+// The number of live references to each EGL display that `surfman` initialized itself.
+//
+// `eglGetPlatformDisplay()` hands out the same display for the same native display, and a single
+// `eglTerminate()` tears it down for everyone, so displays are only terminated once the last
+// reference to them goes away.
+static OWNED_EGL_DISPLAYS: LazyLock<Mutex<HashMap<usize, usize>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Initializes an EGL display on behalf of `surfman` and takes a reference to it.
+///
+/// The first time a display is initialized, the shader cache is installed on it, if one has been
+/// set.
+///
+/// Every successful call must be balanced by a call to `terminate_owned_egl_display()`. Returns
+/// false if `eglInitialize()` failed, in which case no reference is taken.
+pub(crate) unsafe fn initialize_owned_egl_display(egl_display: EGLDisplay) -> bool {
+    // Hold the lock across initialization so that a concurrent termination can't race with us.
+    let mut owned_egl_displays = OWNED_EGL_DISPLAYS.lock().unwrap();
+    let ok = EGL_FUNCTIONS.with(|egl| {
+        let (mut egl_major_version, mut egl_minor_version) = (0, 0);
+        egl.Initialize(egl_display, &mut egl_major_version, &mut egl_minor_version)
+    });
+    if ok == egl::FALSE {
+        return false;
+    }
+    let reference_count = owned_egl_displays.entry(egl_display as usize).or_insert(0);
+    if *reference_count == 0 {
+        shader_cache::install_shader_cache(egl_display);
+    }
+    *reference_count += 1;
+    true
+}
+
+/// Releases a reference taken with `initialize_owned_egl_display()`, terminating the display if
+/// this was the last one.
+///
+/// This is called when connections are dropped, possibly while unwinding, so failures are logged
+/// rather than panicking.
+pub(crate) unsafe fn terminate_owned_egl_display(egl_display: EGLDisplay) {
+    let mut owned_egl_displays = OWNED_EGL_DISPLAYS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    let reference_count = match owned_egl_displays.get_mut(&(egl_display as usize)) {
+        Some(reference_count) => reference_count,
+        None => {
+            warn!(
+                "Terminating EGL display {:?}, which surfman doesn't own",
+                egl_display
+            );
+            return;
+        }
+    };
+    *reference_count -= 1;
+    if *reference_count > 0 {
+        return;
+    }
+    owned_egl_displays.remove(&(egl_display as usize));
+
+    EGLDisplayExtensionFunctions::forget(egl_display);
+    let result = EGL_FUNCTIONS.with(|egl| egl.Terminate(egl_display));
+    if result == egl::FALSE {
+        warn!("Failed to terminate EGL display {:?}", egl_display);
+    }
+}
+
+/// Returns true if `surfman` still holds a reference to the given EGL display.
+#[cfg(any(test, feature = "sm-test"))]
+pub(crate) fn is_owned_egl_display(egl_display: EGLDisplay) -> bool {
+    OWNED_EGL_DISPLAYS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .contains_key(&(egl_display as usize))
 }
 
+/// Returns the extensions advertised by an EGL display, including client extensions.
+pub(crate) unsafe fn egl_extensions(egl_display: EGLDisplay) -> HashSet<String> {
+    EGL_FUNCTIONS.with(|egl| {
+        let mut extensions = HashSet::new();
+        for display in [egl::NO_DISPLAY, egl_display] {
+            let string = egl.QueryString(display, egl::EXTENSIONS as EGLint);
+            if !string.is_null() {
+                let string = CStr::from_ptr(string).to_string_lossy();
+                extensions.extend(string.split_whitespace().map(String::from));
+            }
+        }
+        extensions
+    })
+}
+
+/// The environment variable that overrides the path of the EGL library to load.
+const EGL_LIBRARY_PATH_ENV_VAR: &str = "SURFMAN_EGL_LIBRARY";
+
+// The path set with `set_egl_library_path()`, if any. This is locked while the library is loading.
+static EGL_LIBRARY_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+static EGL_LIBRARY: OnceLock<Option<EGLLibraryWrapper>> = OnceLock::new();
+
 #[cfg(target_os = "windows")]
-static EGL_LIBRARY: LazyLock<EGLLibraryWrapper> = LazyLock::new(|| unsafe {
-    let module = libloaderapi::LoadLibraryA(c"libEGL.dll".as_ptr());
-    EGLLibraryWrapper(module)
-});
+static EGL_POTENTIAL_LIBRARY_NAMES: [&str; 1] = ["libEGL.dll"];
 
 #[cfg(target_env = "ohos")]
-static EGL_POTENTIAL_SO_NAMES: [&CStr; 1] = [c"libEGL.so"];
+static EGL_POTENTIAL_LIBRARY_NAMES: [&str; 1] = ["libEGL.so"];
 
 #[cfg(not(any(target_os = "windows", target_os = "macos", target_env = "ohos")))]
-static EGL_POTENTIAL_SO_NAMES: [&CStr; 2] = [c"libEGL.so.1", c"libEGL.so"];
-
-#[cfg(not(any(target_os = "windows", target_os = "macos")))]
-static EGL_LIBRARY: LazyLock<EGLLibraryWrapper> = LazyLock::new(|| {
-    for soname in EGL_POTENTIAL_SO_NAMES {
-        unsafe {
-            let handle = dlopen(soname.as_ptr(), RTLD_LAZY);
-            if !handle.is_null() {
-                return EGLLibraryWrapper(handle);
-            }
-        }
-    }
-    panic!("Unable to load the libEGL shared object");
-});
+static EGL_POTENTIAL_LIBRARY_NAMES: [&str; 2] = ["libEGL.so.1", "libEGL.so"];
 
 #[cfg(target_os = "windows")]
 struct EGLLibraryWrapper(HMODULE);
@@ -52,21 +163,102 @@ struct EGLLibraryWrapper(*mut c_void);
 unsafe impl Send for EGLLibraryWrapper {}
 unsafe impl Sync for EGLLibraryWrapper {}
 
+/// Sets the path of the EGL library that `surfman` loads, for example a bundled copy of Mesa's
+/// `libEGL`.
+///
+/// This must be called before the first connection is created, as the library is only loaded
+/// once per process. If the library has already been loaded, this returns `Error::Failed`.
+///
+/// The `SURFMAN_EGL_LIBRARY` environment variable, if set, takes precedence over this path. If
+/// neither is set, the system EGL library is loaded.
+pub fn set_egl_library_path<P>(path: P) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+{
+    let mut library_path = EGL_LIBRARY_PATH.lock().unwrap();
+    if EGL_LIBRARY.get().is_some() {
+        return Err(Error::Failed);
+    }
+    *library_path = Some(path.as_ref().to_owned());
+    Ok(())
+}
+
+/// Loads the EGL library if it hasn't been loaded yet.
+///
+/// Returns `Error::NoGLLibraryFound` if the library couldn't be loaded.
+pub(crate) fn load_egl_library() -> Result<(), Error> {
+    match egl_library() {
+        Some(_) => Ok(()),
+        None => Err(Error::NoGLLibraryFound),
+    }
+}
+
+fn egl_library() -> Option<&'static EGLLibraryWrapper> {
+    EGL_LIBRARY
+        .get_or_init(|| {
+            let library_path = EGL_LIBRARY_PATH.lock().unwrap();
+            let library_path = env::var_os(EGL_LIBRARY_PATH_ENV_VAR)
+                .map(PathBuf::from)
+                .or_else(|| library_path.clone());
+
+            // Don't fall back to the system library if a specific one was requested.
+            match library_path {
+                Some(library_path) => open_library(&library_path),
+                None => EGL_POTENTIAL_LIBRARY_NAMES
+                    .iter()
+                    .find_map(|name| open_library(Path::new(name))),
+            }
+        })
+        .as_ref()
+}
+
+#[cfg(target_os = "windows")]
+fn open_library(path: &Path) -> Option<EGLLibraryWrapper> {
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    unsafe {
+        let module = libloaderapi::LoadLibraryW(path.as_ptr());
+        if module.is_null() {
+            None
+        } else {
+            Some(EGLLibraryWrapper(module))
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn open_library(path: &Path) -> Option<EGLLibraryWrapper> {
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    unsafe {
+        let handle = dlopen(path.as_ptr(), RTLD_LAZY);
+        if handle.is_null() {
+            None
+        } else {
+            Some(EGLLibraryWrapper(handle))
+        }
+    }
+}
+
 #[cfg(target_os = "windows")]
 fn get_proc_address(symbol_name: &str) -> *const c_void {
     unsafe {
+        let Some(library) = egl_library() else {
+            return ptr::null();
+        };
         let symbol_name: CString = CString::new(symbol_name).unwrap();
         let symbol_ptr = symbol_name.as_ptr();
-        libloaderapi::GetProcAddress(EGL_LIBRARY.0, symbol_ptr).cast()
+        libloaderapi::GetProcAddress(library.0, symbol_ptr).cast()
     }
 }
 
 #[cfg(not(target_os = "windows"))]
 fn get_proc_address(symbol_name: &str) -> *const c_void {
     unsafe {
+        let Some(library) = egl_library() else {
+            return ptr::null();
+        };
         let symbol_name: CString = CString::new(symbol_name).unwrap();
         let symbol_ptr = symbol_name.as_ptr();
-        dlsym(EGL_LIBRARY.0, symbol_ptr).cast_const()
+        dlsym(library.0, symbol_ptr).cast_const()
     }
 }
 
@@ -0,0 +1,469 @@
+// surfman/surfman/src/platform/generic/egl/external_objects.rs
+//
+//! Interoperation with other APIs, such as Vulkan, via `GL_EXT_memory_object_fd` and
+//! `GL_EXT_semaphore_fd`.
+
+use super::device::lookup_egl_extension;
+use super::surface::EGLBackedSurface;
+use crate::egl::types::{EGLContext, EGLDisplay};
+use crate::gl;
+use crate::gl_utils::{GLStateGroups, GLStateSnapshot};
+use crate::{ContextAttributes, ContextID, Error, Gl, WindowingApiError};
+
+use euclid::default::Size2D;
+use glow::HasContext;
+use std::ffi::CStr;
+use std::mem;
+use std::os::fd::{FromRawFd, IntoRawFd, OwnedFd};
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use std::sync::LazyLock;
+use std::thread;
+
+const GL_TEXTURE_TILING_EXT: u32 = 0x9580;
+const GL_DEDICATED_MEMORY_OBJECT_EXT: u32 = 0x9581;
+const GL_OPTIMAL_TILING_EXT: u32 = 0x9584;
+const GL_HANDLE_TYPE_OPAQUE_FD_EXT: u32 = 0x9586;
+const GL_LAYOUT_GENERAL_EXT: u32 = 0x958d;
+
+#[allow(non_snake_case)]
+struct GLMemoryObjectFunctions {
+    CreateMemoryObjectsEXT: extern "C" fn(n: i32, memory_objects: *mut u32),
+    DeleteMemoryObjectsEXT: extern "C" fn(n: i32, memory_objects: *const u32),
+    MemoryObjectParameterivEXT: extern "C" fn(memory_object: u32, pname: u32, params: *const i32),
+    ImportMemoryFdEXT: extern "C" fn(memory: u32, size: u64, handle_type: u32, fd: c_int),
+    TexStorageMem2DEXT: extern "C" fn(
+        target: u32,
+        levels: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        memory: u32,
+        offset: u64,
+    ),
+}
+
+#[allow(non_snake_case)]
+struct GLSemaphoreFunctions {
+    GenSemaphoresEXT: extern "C" fn(n: i32, semaphores: *mut u32),
+    DeleteSemaphoresEXT: extern "C" fn(n: i32, semaphores: *const u32),
+    ImportSemaphoreFdEXT: extern "C" fn(semaphore: u32, handle_type: u32, fd: c_int),
+    WaitSemaphoreEXT: extern "C" fn(
+        semaphore: u32,
+        num_buffer_barriers: u32,
+        buffers: *const u32,
+        num_texture_barriers: u32,
+        textures: *const u32,
+        src_layouts: *const u32,
+    ),
+    SignalSemaphoreEXT: extern "C" fn(
+        semaphore: u32,
+        num_buffer_barriers: u32,
+        buffers: *const u32,
+        num_texture_barriers: u32,
+        textures: *const u32,
+        dst_layouts: *const u32,
+    ),
+}
+
+// These are OpenGL extensions, so whether they're usable depends on the context. The entry points
+// themselves are the same for every context, so they're only looked up once.
+static GL_MEMORY_OBJECT_FUNCTIONS: LazyLock<Option<GLMemoryObjectFunctions>> =
+    LazyLock::new(|| unsafe {
+        Some(GLMemoryObjectFunctions {
+            CreateMemoryObjectsEXT: lookup(c"glCreateMemoryObjectsEXT")?,
+            DeleteMemoryObjectsEXT: lookup(c"glDeleteMemoryObjectsEXT")?,
+            MemoryObjectParameterivEXT: lookup(c"glMemoryObjectParameterivEXT")?,
+            ImportMemoryFdEXT: lookup(c"glImportMemoryFdEXT")?,
+            TexStorageMem2DEXT: lookup(c"glTexStorageMem2DEXT")?,
+        })
+    });
+static GL_SEMAPHORE_FUNCTIONS: LazyLock<Option<GLSemaphoreFunctions>> = LazyLock::new(|| unsafe {
+    Some(GLSemaphoreFunctions {
+        GenSemaphoresEXT: lookup(c"glGenSemaphoresEXT")?,
+        DeleteSemaphoresEXT: lookup(c"glDeleteSemaphoresEXT")?,
+        ImportSemaphoreFdEXT: lookup(c"glImportSemaphoreFdEXT")?,
+        WaitSemaphoreEXT: lookup(c"glWaitSemaphoreEXT")?,
+        SignalSemaphoreEXT: lookup(c"glSignalSemaphoreEXT")?,
+    })
+});
+
+unsafe fn lookup<F: Copy>(name: &CStr) -> Option<F> {
+    let function = lookup_egl_extension(name);
+    if function.is_null() {
+        None
+    } else {
+        Some(mem::transmute_copy::<*mut c_void, F>(&function))
+    }
+}
+
+// Returns the memory object entry points if the current context supports them.
+fn memory_object_functions(gl: &Gl) -> Result<&'static GLMemoryObjectFunctions, Error> {
+    if !gl
+        .supported_extensions()
+        .contains("GL_EXT_memory_object_fd")
+    {
+        return Err(Error::RequiredExtensionUnavailable);
+    }
+    GL_MEMORY_OBJECT_FUNCTIONS
+        .as_ref()
+        .ok_or(Error::RequiredExtensionUnavailable)
+}
+
+// Returns the semaphore entry points if the current context supports them.
+fn semaphore_functions(gl: &Gl) -> Result<&'static GLSemaphoreFunctions, Error> {
+    if !gl.supported_extensions().contains("GL_EXT_semaphore_fd") {
+        return Err(Error::RequiredExtensionUnavailable);
+    }
+    GL_SEMAPHORE_FUNCTIONS
+        .as_ref()
+        .ok_or(Error::RequiredExtensionUnavailable)
+}
+
+/// Memory allocated by another API and exported as an opaque file descriptor, such as with
+/// `vkGetMemoryFdKHR()` and `VK_EXTERNAL_MEMORY_HANDLE_TYPE_OPAQUE_FD_BIT`.
+///
+/// The memory must hold an image of the surface's size in the `R8G8B8A8_UNORM` format with
+/// optimal tiling, and must come from the same physical device as the context.
+#[derive(Debug)]
+pub struct ExternalMemory {
+    /// The file descriptor. Ownership passes to OpenGL on import.
+    pub fd: OwnedFd,
+    /// The size of the whole allocation in bytes.
+    pub size: u64,
+    /// The offset of the image within the allocation in bytes.
+    pub offset: u64,
+    /// Whether the allocation is dedicated to this image, as with `VkMemoryDedicatedAllocateInfo`.
+    pub dedicated: bool,
+}
+
+/// A semaphore imported from another API, such as Vulkan, for synchronizing access to surfaces.
+///
+/// Semaphores are local to the context they were imported into, and must be destroyed with
+/// `Device::destroy_semaphore()`, or a panic will occur.
+#[derive(Debug)]
+pub struct ExternalSemaphore {
+    pub(crate) semaphore: u32,
+    pub(crate) context_id: ContextID,
+}
+
+impl Drop for ExternalSemaphore {
+    fn drop(&mut self) {
+        if self.semaphore != 0 && !thread::panicking() {
+            panic!("Semaphores must be destroyed explicitly with `destroy_semaphore`!")
+        }
+    }
+}
+
+impl EGLBackedSurface {
+    // Creates a generic surface whose texture is backed by imported memory. The context must be
+    // current.
+    pub(crate) unsafe fn from_external_memory(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        egl_context: EGLContext,
+        context_id: ContextID,
+        context_attributes: &ContextAttributes,
+        size: &Size2D<i32>,
+        memory: ExternalMemory,
+    ) -> Result<EGLBackedSurface, Error> {
+        let functions = memory_object_functions(gl)?;
+
+        // Import the memory.
+        let mut memory_object = 0;
+        (functions.CreateMemoryObjectsEXT)(1, &mut memory_object);
+        if memory.dedicated {
+            let dedicated = gl::TRUE as i32;
+            (functions.MemoryObjectParameterivEXT)(
+                memory_object,
+                GL_DEDICATED_MEMORY_OBJECT_EXT,
+                &dedicated,
+            );
+        }
+        let fd = memory.fd.into_raw_fd();
+        (functions.ImportMemoryFdEXT)(memory_object, memory.size, GL_HANDLE_TYPE_OPAQUE_FD_EXT, fd);
+        if gl.get_error() != gl::NO_ERROR {
+            // Ownership of the file descriptor only passes to OpenGL if the import succeeds.
+            drop(OwnedFd::from_raw_fd(fd));
+            (functions.DeleteMemoryObjectsEXT)(1, &memory_object);
+            return Err(Error::SurfaceImportFailed(WindowingApiError::BadParameter));
+        }
+
+        // Create a texture stored in that memory.
+        let state = GLStateSnapshot::capture(gl, GLStateGroups::TEXTURE_2D_BINDING);
+        let texture_object = gl.create_texture().ok();
+        gl.bind_texture(gl::TEXTURE_2D, texture_object);
+        gl.tex_parameter_i32(
+            gl::TEXTURE_2D,
+            GL_TEXTURE_TILING_EXT,
+            GL_OPTIMAL_TILING_EXT as i32,
+        );
+        (functions.TexStorageMem2DEXT)(
+            gl::TEXTURE_2D,
+            1,
+            gl::RGBA8,
+            size.width,
+            size.height,
+            memory_object,
+            memory.offset,
+        );
+        let error = gl.get_error();
+        state.restore(gl);
+        if error != gl::NO_ERROR {
+            if let Some(texture_object) = texture_object {
+                gl.delete_texture(texture_object);
+            }
+            (functions.DeleteMemoryObjectsEXT)(1, &memory_object);
+            return Err(Error::SurfaceImportFailed(WindowingApiError::BadMatch));
+        }
+
+        let (mut surface, framebuffer_status) = EGLBackedSurface::new_with_texture(
+            gl,
+            egl_display,
+            egl_context,
+            context_id,
+            context_attributes,
+            size,
+            texture_object,
+            true,
+        );
+        surface.memory_object = Some(memory_object);
+
+        if framebuffer_status != gl::FRAMEBUFFER_COMPLETE {
+            surface.destroy(gl, egl_display, context_id)?;
+            return Err(Error::SurfaceImportFailed(WindowingApiError::BadMatch));
+        }
+
+        Ok(surface)
+    }
+}
+
+impl ExternalSemaphore {
+    // Imports a semaphore into the current context.
+    pub(crate) unsafe fn import(
+        gl: &Gl,
+        context_id: ContextID,
+        fd: OwnedFd,
+    ) -> Result<ExternalSemaphore, Error> {
+        let functions = semaphore_functions(gl)?;
+
+        let mut semaphore = 0;
+        (functions.GenSemaphoresEXT)(1, &mut semaphore);
+        let fd = fd.into_raw_fd();
+        (functions.ImportSemaphoreFdEXT)(semaphore, GL_HANDLE_TYPE_OPAQUE_FD_EXT, fd);
+        if gl.get_error() != gl::NO_ERROR {
+            // As with memory, ownership of the file descriptor only passes on success.
+            drop(OwnedFd::from_raw_fd(fd));
+            (functions.DeleteSemaphoresEXT)(1, &semaphore);
+            return Err(Error::SurfaceImportFailed(WindowingApiError::BadParameter));
+        }
+
+        Ok(ExternalSemaphore {
+            semaphore,
+            context_id,
+        })
+    }
+
+    // Makes the current context wait for the semaphore before accessing the given surface.
+    pub(crate) unsafe fn wait(
+        &self,
+        gl: &Gl,
+        context_id: ContextID,
+        surface: Option<&EGLBackedSurface>,
+    ) -> Result<(), Error> {
+        if context_id != self.context_id {
+            return Err(Error::IncompatibleContext);
+        }
+
+        let functions = semaphore_functions(gl)?;
+        let textures: Vec<u32> = surface
+            .and_then(EGLBackedSurface::texture_object)
+            .map(|texture| texture.0.get())
+            .into_iter()
+            .collect();
+        let layouts = vec![GL_LAYOUT_GENERAL_EXT; textures.len()];
+        (functions.WaitSemaphoreEXT)(
+            self.semaphore,
+            0,
+            ptr::null(),
+            textures.len() as u32,
+            textures.as_ptr(),
+            layouts.as_ptr(),
+        );
+        Ok(())
+    }
+
+    // Signals the semaphore once the current context is done with the given surface.
+    pub(crate) unsafe fn signal(
+        &self,
+        gl: &Gl,
+        context_id: ContextID,
+        surface: Option<&EGLBackedSurface>,
+    ) -> Result<(), Error> {
+        if context_id != self.context_id {
+            return Err(Error::IncompatibleContext);
+        }
+
+        let functions = semaphore_functions(gl)?;
+        let textures: Vec<u32> = surface
+            .and_then(EGLBackedSurface::texture_object)
+            .map(|texture| texture.0.get())
+            .into_iter()
+            .collect();
+        let layouts = vec![GL_LAYOUT_GENERAL_EXT; textures.len()];
+        (functions.SignalSemaphoreEXT)(
+            self.semaphore,
+            0,
+            ptr::null(),
+            textures.len() as u32,
+            textures.as_ptr(),
+            layouts.as_ptr(),
+        );
+
+        // The other API can't see the signal until it has been submitted.
+        gl.flush();
+        Ok(())
+    }
+
+    pub(crate) unsafe fn destroy(&mut self, gl: &Gl, context_id: ContextID) -> Result<(), Error> {
+        if context_id != self.context_id {
+            return Err(Error::IncompatibleContext);
+        }
+
+        let functions = semaphore_functions(gl)?;
+        (functions.DeleteSemaphoresEXT)(1, &self.semaphore);
+        self.semaphore = 0;
+        Ok(())
+    }
+}
+
+// Deletes the memory object backing a surface that was created from external memory.
+pub(crate) unsafe fn delete_memory_object(memory_object: u32) {
+    if let Some(ref functions) = *GL_MEMORY_OBJECT_FUNCTIONS {
+        (functions.DeleteMemoryObjectsEXT)(1, &memory_object);
+    }
+}
+
+// Implements the external memory and semaphore methods of the EGL-based Unix devices, which only
+// differ in their `Device`, `Context`, and `Surface` types. Invoke this in the backend's `surface`
+// module.
+macro_rules! implement_external_objects {
+    () => {
+        impl Device {
+            /// Creates a generic surface backed by memory imported from another API, such as Vulkan, via
+            /// `GL_EXT_memory_object_fd`.
+            ///
+            /// This allows a surface to be rendered with OpenGL and consumed by Vulkan, or vice versa.
+            /// Depth and stencil buffers are allocated according to the context's descriptor. Access to
+            /// the memory must be synchronized with semaphores; see `import_semaphore()`.
+            ///
+            /// Returns `RequiredExtensionUnavailable` if the context doesn't support
+            /// `GL_EXT_memory_object_fd`.
+            pub fn create_surface_from_external_memory(
+                &mut self,
+                context: &Context,
+                size: &euclid::default::Size2D<i32>,
+                memory: $crate::platform::generic::egl::external_objects::ExternalMemory,
+            ) -> Result<Surface, $crate::Error> {
+                let _guard = self.temporarily_make_context_current(context)?;
+                let context_descriptor = self.context_descriptor(context);
+                let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+
+                unsafe {
+                    $crate::platform::generic::egl::surface::EGLBackedSurface::from_external_memory(
+                        &context.1,
+                        self.native_connection.egl_display,
+                        context.0.egl_context,
+                        context.0.id,
+                        &context_attributes,
+                        size,
+                        memory,
+                    )
+                    .map(Surface)
+                }
+            }
+
+            /// Imports a semaphore exported from another API as an opaque file descriptor, such as with
+            /// `vkGetSemaphoreFdKHR()`, via `GL_EXT_semaphore_fd`.
+            ///
+            /// OpenGL can't export semaphores, so semaphores signaled by OpenGL must also be created by
+            /// the other API. The file descriptor is consumed. Returns `RequiredExtensionUnavailable` if
+            /// the context doesn't support `GL_EXT_semaphore_fd`.
+            pub fn import_semaphore(
+                &self,
+                context: &Context,
+                fd: std::os::fd::OwnedFd,
+            ) -> Result<
+                $crate::platform::generic::egl::external_objects::ExternalSemaphore,
+                $crate::Error,
+            > {
+                let _guard = self.temporarily_make_context_current(context)?;
+                unsafe {
+                    $crate::platform::generic::egl::external_objects::ExternalSemaphore::import(
+                        &context.1,
+                        context.0.id,
+                        fd,
+                    )
+                }
+            }
+
+            /// Makes the context wait for a semaphore before it next accesses a surface.
+            ///
+            /// If `surface` is `None`, the surface currently bound to the context is used. The other API
+            /// must leave the surface's image in the `GENERAL` layout before signaling the semaphore.
+            pub fn wait_for_semaphore(
+                &self,
+                context: &Context,
+                semaphore: &$crate::platform::generic::egl::external_objects::ExternalSemaphore,
+                surface: Option<&Surface>,
+            ) -> Result<(), $crate::Error> {
+                let _guard = self.temporarily_make_context_current(context)?;
+                let surface = surface.map(|surface| &surface.0);
+                unsafe {
+                    semaphore.wait(
+                        &context.1,
+                        context.0.id,
+                        surface.or(context.0.bound_surface()),
+                    )
+                }
+            }
+
+            /// Signals a semaphore once the context has finished the commands issued so far, and flushes
+            /// them.
+            ///
+            /// If `surface` is `None`, the surface currently bound to the context is used. The surface's
+            /// image is left in the `GENERAL` layout for the other API.
+            pub fn signal_semaphore(
+                &self,
+                context: &Context,
+                semaphore: &$crate::platform::generic::egl::external_objects::ExternalSemaphore,
+                surface: Option<&Surface>,
+            ) -> Result<(), $crate::Error> {
+                let _guard = self.temporarily_make_context_current(context)?;
+                let surface = surface.map(|surface| &surface.0);
+                unsafe {
+                    semaphore.signal(
+                        &context.1,
+                        context.0.id,
+                        surface.or(context.0.bound_surface()),
+                    )
+                }
+            }
+
+            /// Destroys a semaphore imported with `import_semaphore()`.
+            ///
+            /// The supplied context must be the context the semaphore was imported into, or this returns
+            /// an `IncompatibleContext` error.
+            pub fn destroy_semaphore(
+                &self,
+                context: &Context,
+                semaphore: &mut $crate::platform::generic::egl::external_objects::ExternalSemaphore,
+            ) -> Result<(), $crate::Error> {
+                let _guard = self.temporarily_make_context_current(context)?;
+                unsafe { semaphore.destroy(&context.1, context.0.id) }
+            }
+        }
+    };
+}
+
+pub(crate) use implement_external_objects;
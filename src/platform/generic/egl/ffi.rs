@@ -5,10 +5,16 @@
 #![allow(dead_code)]
 
 use crate::egl::types::{EGLAttrib, EGLBoolean, EGLContext, EGLDeviceEXT, EGLDisplay, EGLSurface};
-use crate::egl::types::{EGLenum, EGLint};
+use crate::egl::types::{EGLConfig, EGLenum, EGLint};
 
-use std::os::raw::c_void;
-use std::sync::LazyLock;
+use crate::egl;
+use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use std::sync::{Arc, LazyLock, Mutex};
 
 pub enum EGLClientBufferOpaque {}
 pub type EGLClientBuffer = *mut EGLClientBufferOpaque;
@@ -16,12 +22,31 @@ pub type EGLClientBuffer = *mut EGLClientBufferOpaque;
 pub enum EGLImageKHROpaque {}
 pub type EGLImageKHR = *mut EGLImageKHROpaque;
 
+pub type EGLsizeiANDROID = isize;
+pub type EGLSetBlobFuncANDROID = extern "C" fn(
+    key: *const c_void,
+    key_size: EGLsizeiANDROID,
+    value: *const c_void,
+    value_size: EGLsizeiANDROID,
+);
+pub type EGLGetBlobFuncANDROID = extern "C" fn(
+    key: *const c_void,
+    key_size: EGLsizeiANDROID,
+    value: *mut c_void,
+    value_size: EGLsizeiANDROID,
+) -> EGLsizeiANDROID;
+
 pub const EGL_GL_TEXTURE_2D_KHR: EGLenum = 0x30b1;
 pub const EGL_IMAGE_PRESERVED_KHR: EGLenum = 0x30d2;
 pub const EGL_CONTEXT_MINOR_VERSION_KHR: EGLenum = 0x30fb;
 pub const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLenum = 0x30fd;
+pub const EGL_CONTEXT_PRIORITY_LEVEL_IMG: EGLenum = 0x3100;
+pub const EGL_CONTEXT_PRIORITY_HIGH_IMG: EGLenum = 0x3101;
+pub const EGL_CONTEXT_PRIORITY_MEDIUM_IMG: EGLenum = 0x3102;
+pub const EGL_CONTEXT_PRIORITY_LOW_IMG: EGLenum = 0x3103;
 pub const EGL_PLATFORM_DEVICE_EXT: EGLenum = 0x313f;
 pub const EGL_NATIVE_BUFFER_ANDROID: EGLenum = 0x3140;
+pub const EGL_CONTEXT_OPENGL_NO_ERROR_KHR: EGLenum = 0x31b3;
 pub const EGL_PLATFORM_X11_KHR: EGLenum = 0x31d5;
 pub const EGL_PLATFORM_WAYLAND_KHR: EGLenum = 0x31d8;
 pub const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31dd;
@@ -31,27 +56,42 @@ pub const EGL_DEVICE_EXT: EGLenum = 0x322c;
 pub const EGL_D3D11_DEVICE_ANGLE: EGLenum = 0x33a1;
 pub const EGL_DXGI_KEYED_MUTEX_ANGLE: EGLenum = 0x33a2;
 pub const EGL_D3D_TEXTURE_ANGLE: EGLenum = 0x33a3;
+pub const EGL_LINUX_DMA_BUF_EXT: EGLenum = 0x3270;
+pub const EGL_LINUX_DRM_FOURCC_EXT: EGLenum = 0x3271;
+pub const EGL_DMA_BUF_PLANE0_FD_EXT: EGLenum = 0x3272;
+pub const EGL_DMA_BUF_PLANE0_OFFSET_EXT: EGLenum = 0x3273;
+pub const EGL_DMA_BUF_PLANE0_PITCH_EXT: EGLenum = 0x3274;
+pub const EGL_DMA_BUF_PLANE1_FD_EXT: EGLenum = 0x3275;
+pub const EGL_DMA_BUF_PLANE1_OFFSET_EXT: EGLenum = 0x3276;
+pub const EGL_DMA_BUF_PLANE1_PITCH_EXT: EGLenum = 0x3277;
+pub const EGL_DMA_BUF_PLANE2_FD_EXT: EGLenum = 0x3278;
+pub const EGL_DMA_BUF_PLANE2_OFFSET_EXT: EGLenum = 0x3279;
+pub const EGL_DMA_BUF_PLANE2_PITCH_EXT: EGLenum = 0x327a;
+pub const EGL_DMA_BUF_PLANE3_FD_EXT: EGLenum = 0x3440;
+pub const EGL_DMA_BUF_PLANE3_OFFSET_EXT: EGLenum = 0x3441;
+pub const EGL_DMA_BUF_PLANE3_PITCH_EXT: EGLenum = 0x3442;
+pub const EGL_DMA_BUF_PLANE0_MODIFIER_LO_EXT: EGLenum = 0x3443;
+pub const EGL_DMA_BUF_PLANE0_MODIFIER_HI_EXT: EGLenum = 0x3444;
+pub const EGL_DMA_BUF_PLANE1_MODIFIER_LO_EXT: EGLenum = 0x3445;
+pub const EGL_DMA_BUF_PLANE1_MODIFIER_HI_EXT: EGLenum = 0x3446;
+pub const EGL_DMA_BUF_PLANE2_MODIFIER_LO_EXT: EGLenum = 0x3447;
+pub const EGL_DMA_BUF_PLANE2_MODIFIER_HI_EXT: EGLenum = 0x3448;
+pub const EGL_DMA_BUF_PLANE3_MODIFIER_LO_EXT: EGLenum = 0x3449;
+pub const EGL_DMA_BUF_PLANE3_MODIFIER_HI_EXT: EGLenum = 0x344a;
 
 pub const EGL_NO_DEVICE_EXT: EGLDeviceEXT = 0 as EGLDeviceEXT;
 pub const EGL_NO_IMAGE_KHR: EGLImageKHR = 0 as EGLImageKHR;
+pub const EGL_NO_CONFIG_KHR: EGLConfig = 0 as EGLConfig;
+
+// The DRM format modifier that means "no explicit modifier", from `drm_fourcc.h`.
+pub const DRM_FORMAT_MOD_INVALID: u64 = 0x00ff_ffff_ffff_ffff;
 
 pub const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 1;
 pub const EGL_CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT: EGLint = 2;
 
+// Client extensions, which don't depend on the display.
 #[allow(non_snake_case)]
 pub(crate) struct EGLExtensionFunctions {
-    // Ubiquitous extensions assumed to be present
-    pub(crate) CreateImageKHR: extern "C" fn(
-        dpy: EGLDisplay,
-        ctx: EGLContext,
-        target: EGLenum,
-        buffer: EGLClientBuffer,
-        attrib_list: *const EGLint,
-    ) -> EGLImageKHR,
-    pub(crate) DestroyImageKHR: extern "C" fn(dpy: EGLDisplay, image: EGLImageKHR) -> EGLBoolean,
-    pub(crate) ImageTargetTexture2DOES: extern "C" fn(target: EGLenum, image: EGLImageKHR),
-
-    // Optional extensions
     pub(crate) CreateDeviceANGLE: Option<
         extern "C" fn(
             device_type: EGLint,
@@ -59,14 +99,63 @@ pub(crate) struct EGLExtensionFunctions {
             attrib_list: *const EGLAttrib,
         ) -> EGLDeviceEXT,
     >,
-    pub(crate) GetNativeClientBufferANDROID:
-        Option<extern "C" fn(buffer: *const c_void) -> EGLClientBuffer>,
     pub(crate) QueryDeviceAttribEXT: Option<
         extern "C" fn(device: EGLDeviceEXT, attribute: EGLint, value: *mut EGLAttrib) -> EGLBoolean,
     >,
     pub(crate) QueryDisplayAttribEXT: Option<
         extern "C" fn(dpy: EGLDisplay, attribute: EGLint, value: *mut EGLAttrib) -> EGLBoolean,
     >,
+}
+
+type ExportDMABUFImageQueryMESAFn = extern "C" fn(
+    dpy: EGLDisplay,
+    image: EGLImageKHR,
+    fourcc: *mut c_int,
+    num_planes: *mut c_int,
+    modifiers: *mut u64,
+) -> EGLBoolean;
+type ExportDMABUFImageMESAFn = extern "C" fn(
+    dpy: EGLDisplay,
+    image: EGLImageKHR,
+    fds: *mut c_int,
+    strides: *mut EGLint,
+    offsets: *mut EGLint,
+) -> EGLBoolean;
+type SetBlobCacheFuncsANDROIDFn =
+    extern "C" fn(dpy: EGLDisplay, set: EGLSetBlobFuncANDROID, get: EGLGetBlobFuncANDROID);
+
+// Display extensions. Look these up with `EGLDisplayExtensionFunctions::get()`.
+#[allow(non_snake_case)]
+pub(crate) struct EGLDisplayExtensionFunctions {
+    // Nearly ubiquitous extensions, but minimal EGL implementations may lack them. See
+    // `supports_egl_images()`.
+    pub(crate) CreateImageKHR: Option<
+        extern "C" fn(
+            dpy: EGLDisplay,
+            ctx: EGLContext,
+            target: EGLenum,
+            buffer: EGLClientBuffer,
+            attrib_list: *const EGLint,
+        ) -> EGLImageKHR,
+    >,
+    pub(crate) DestroyImageKHR:
+        Option<extern "C" fn(dpy: EGLDisplay, image: EGLImageKHR) -> EGLBoolean>,
+    pub(crate) ImageTargetTexture2DOES: Option<extern "C" fn(target: EGLenum, image: EGLImageKHR)>,
+    // Whether images can be created from OpenGL textures (`EGL_KHR_gl_texture_2D_image`).
+    pub(crate) gl_texture_2d_images: bool,
+    // Whether contexts can be made current without a surface (`EGL_KHR_surfaceless_context`).
+    pub(crate) surfaceless_context: bool,
+    // Whether contexts can be created without a config (`EGL_KHR_no_config_context`).
+    pub(crate) no_config_context: bool,
+    // Whether contexts can be created without error checking
+    // (`EGL_KHR_create_context_no_error`).
+    pub(crate) create_context_no_error: bool,
+    // Whether contexts can be given a scheduling priority (`EGL_IMG_context_priority`).
+    pub(crate) context_priority: bool,
+
+    // Optional extensions
+    pub(crate) GetNativeClientBufferANDROID:
+        Option<extern "C" fn(buffer: *const c_void) -> EGLClientBuffer>,
     pub(crate) QuerySurfacePointerANGLE: Option<
         extern "C" fn(
             dpy: EGLDisplay,
@@ -75,6 +164,14 @@ pub(crate) struct EGLExtensionFunctions {
             value: *mut *mut c_void,
         ) -> EGLBoolean,
     >,
+    pub(crate) ExportDMABUFImageQueryMESA: Option<ExportDMABUFImageQueryMESAFn>,
+    pub(crate) ExportDMABUFImageMESA: Option<ExportDMABUFImageMESAFn>,
+    pub(crate) SetBlobCacheFuncsANDROID: Option<SetBlobCacheFuncsANDROIDFn>,
+    // Whether images can be created from dma-bufs (`EGL_EXT_image_dma_buf_import`).
+    pub(crate) dma_buf_import: bool,
+    // Whether dma-buf images can have explicit format modifiers
+    // (`EGL_EXT_image_dma_buf_import_modifiers`).
+    pub(crate) dma_buf_import_modifiers: bool,
 }
 
 pub(crate) static EGL_EXTENSION_FUNCTIONS: LazyLock<EGLExtensionFunctions> = LazyLock::new(|| {
@@ -82,15 +179,134 @@ pub(crate) static EGL_EXTENSION_FUNCTIONS: LazyLock<EGLExtensionFunctions> = Laz
     use std::mem::transmute as cast;
     unsafe {
         EGLExtensionFunctions {
-            CreateImageKHR: cast(get(c"eglCreateImageKHR")),
-            DestroyImageKHR: cast(get(c"eglDestroyImageKHR")),
-            ImageTargetTexture2DOES: cast(get(c"glEGLImageTargetTexture2DOES")),
-
             CreateDeviceANGLE: cast(get(c"eglCreateDeviceANGLE")),
-            GetNativeClientBufferANDROID: cast(get(c"eglGetNativeClientBufferANDROID")),
             QueryDeviceAttribEXT: cast(get(c"eglQueryDeviceAttribEXT")),
             QueryDisplayAttribEXT: cast(get(c"eglQueryDisplayAttribEXT")),
-            QuerySurfacePointerANGLE: cast(get(c"eglQuerySurfacePointerANGLE")),
         }
     }
 });
+
+// Display extension functions, keyed by display. These are looked up once per display, since
+// every lookup has to parse the display's extension string.
+static EGL_DISPLAY_EXTENSION_FUNCTIONS: LazyLock<
+    Mutex<HashMap<usize, Arc<EGLDisplayExtensionFunctions>>>,
+> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+impl EGLDisplayExtensionFunctions {
+    /// Returns the extension functions supported by the given display, looking them up on first
+    /// use.
+    pub(crate) fn get(egl_display: EGLDisplay) -> Arc<EGLDisplayExtensionFunctions> {
+        let mut cache = EGL_DISPLAY_EXTENSION_FUNCTIONS.lock().unwrap();
+        cache
+            .entry(egl_display as usize)
+            .or_insert_with(|| Arc::new(EGLDisplayExtensionFunctions::load(egl_display)))
+            .clone()
+    }
+
+    /// Drops the cached extension functions for a display that is being terminated.
+    pub(crate) fn forget(egl_display: EGLDisplay) {
+        EGL_DISPLAY_EXTENSION_FUNCTIONS
+            .lock()
+            .unwrap()
+            .remove(&(egl_display as usize));
+    }
+
+    /// Makes the given display report no EGL image support until it is forgotten, so that tests
+    /// can exercise the paths that copy pixels instead.
+    #[cfg(any(test, feature = "sm-test"))]
+    pub(crate) fn disable_egl_images(egl_display: EGLDisplay) {
+        let mut functions = EGLDisplayExtensionFunctions::load(egl_display);
+        functions.CreateImageKHR = None;
+        functions.DestroyImageKHR = None;
+        functions.ImageTargetTexture2DOES = None;
+        EGL_DISPLAY_EXTENSION_FUNCTIONS
+            .lock()
+            .unwrap()
+            .insert(egl_display as usize, Arc::new(functions));
+    }
+
+    fn load(egl_display: EGLDisplay) -> EGLDisplayExtensionFunctions {
+        use crate::platform::generic::egl::device::lookup_egl_extension as get;
+        use std::mem::transmute as cast;
+
+        // If the extension string is unavailable, trust whatever `eglGetProcAddress` returns.
+        let extensions = unsafe {
+            EGL_FUNCTIONS.with(|egl| {
+                let extensions = egl.QueryString(egl_display, egl::EXTENSIONS as EGLint);
+                if extensions.is_null() {
+                    None
+                } else {
+                    Some(CStr::from_ptr(extensions).to_string_lossy().into_owned())
+                }
+            })
+        };
+        let supported = |names: &[&str]| match extensions {
+            None => true,
+            Some(ref extensions) => extensions.split(' ').any(|name| names.contains(&name)),
+        };
+        // Extensions that only change behavior have no function pointer to fall back on, so only
+        // trust an extension string that actually advertises them.
+        let advertised = |name: &str| {
+            extensions
+                .as_ref()
+                .is_some_and(|extensions| extensions.split(' ').any(|other| other == name))
+        };
+        let get_if_supported = |names: &[&str], symbol: &CStr| {
+            if supported(names) {
+                unsafe { get(symbol) }
+            } else {
+                ptr::null_mut()
+            }
+        };
+
+        let image_extensions = ["EGL_KHR_image_base", "EGL_KHR_image"];
+        unsafe {
+            EGLDisplayExtensionFunctions {
+                CreateImageKHR: cast(get_if_supported(&image_extensions, c"eglCreateImageKHR")),
+                DestroyImageKHR: cast(get_if_supported(&image_extensions, c"eglDestroyImageKHR")),
+                // This is an OpenGL extension, so it doesn't appear in the EGL extension string.
+                ImageTargetTexture2DOES: cast(get(c"glEGLImageTargetTexture2DOES")),
+                gl_texture_2d_images: supported(&["EGL_KHR_gl_texture_2D_image"]),
+                surfaceless_context: advertised("EGL_KHR_surfaceless_context"),
+                no_config_context: advertised("EGL_KHR_no_config_context"),
+                create_context_no_error: advertised("EGL_KHR_create_context_no_error"),
+                context_priority: advertised("EGL_IMG_context_priority"),
+
+                GetNativeClientBufferANDROID: cast(get_if_supported(
+                    &["EGL_ANDROID_get_native_client_buffer"],
+                    c"eglGetNativeClientBufferANDROID",
+                )),
+                QuerySurfacePointerANGLE: cast(get_if_supported(
+                    &["EGL_ANGLE_query_surface_pointer"],
+                    c"eglQuerySurfacePointerANGLE",
+                )),
+                ExportDMABUFImageQueryMESA: cast::<*mut c_void, Option<ExportDMABUFImageQueryMESAFn>>(
+                    get_if_supported(
+                        &["EGL_MESA_image_dma_buf_export"],
+                        c"eglExportDMABUFImageQueryMESA",
+                    ),
+                ),
+                ExportDMABUFImageMESA: cast::<*mut c_void, Option<ExportDMABUFImageMESAFn>>(
+                    get_if_supported(
+                        &["EGL_MESA_image_dma_buf_export"],
+                        c"eglExportDMABUFImageMESA",
+                    ),
+                ),
+                SetBlobCacheFuncsANDROID: cast::<*mut c_void, Option<SetBlobCacheFuncsANDROIDFn>>(
+                    get_if_supported(&["EGL_ANDROID_blob_cache"], c"eglSetBlobCacheFuncsANDROID"),
+                ),
+                dma_buf_import: advertised("EGL_EXT_image_dma_buf_import"),
+                dma_buf_import_modifiers: advertised("EGL_EXT_image_dma_buf_import_modifiers"),
+            }
+        }
+    }
+
+    /// Returns true if EGL images can be created, destroyed, and bound to OpenGL textures.
+    ///
+    /// If this returns false, surface textures have to fall back to copying pixels.
+    pub(crate) fn supports_egl_images(&self) -> bool {
+        self.CreateImageKHR.is_some()
+            && self.DestroyImageKHR.is_some()
+            && self.ImageTargetTexture2DOES.is_some()
+    }
+}
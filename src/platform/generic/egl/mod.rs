@@ -7,5 +7,10 @@
 pub(crate) mod context;
 pub(crate) mod device;
 pub(crate) mod error;
+#[cfg(free_unix)]
+pub(crate) mod external_objects;
 pub(crate) mod ffi;
+pub(crate) mod shader_cache;
 pub(crate) mod surface;
+#[cfg(free_unix)]
+pub(crate) mod surface_handle;
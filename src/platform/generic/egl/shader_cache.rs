@@ -0,0 +1,193 @@
+// surfman/surfman/src/platform/generic/egl/shader_cache.rs
+//
+//! Caching of compiled shaders through `EGL_ANDROID_blob_cache`.
+
+use super::device::EGL_FUNCTIONS;
+use super::ffi::{EGLDisplayExtensionFunctions, EGLsizeiANDROID};
+use crate::egl::types::EGLDisplay;
+
+use std::fs;
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// A cache of compiled shaders and programs that the OpenGL driver consults instead of compiling
+/// them again.
+///
+/// Keys and values are opaque blobs generated by the driver. Drivers may call into the cache from
+/// any thread, and from several threads at once.
+pub trait ShaderCache: Send + Sync {
+    /// Returns the value most recently stored under `key`, or `None` if there isn't one.
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+    /// Stores `value` under `key`. The cache is free to drop entries, so failing to store one is
+    /// not an error.
+    fn set(&self, key: &[u8], value: &[u8]);
+}
+
+/// A shader cache that stores each entry as a file in a directory.
+///
+/// Entries are written atomically, so several processes can share the same directory.
+#[derive(Clone, Debug)]
+pub struct DirectoryShaderCache {
+    directory: PathBuf,
+}
+
+// The cache set with `set_shader_cache()`, if any.
+static SHADER_CACHE: RwLock<Option<Arc<dyn ShaderCache>>> = RwLock::new(None);
+
+// Distinguishes the temporary files of concurrent writes from this process.
+static NEXT_TEMPORARY_FILE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the cache that OpenGL drivers use to store compiled shaders, via
+/// `EGL_ANDROID_blob_cache`.
+///
+/// The cache is installed on every EGL display that `surfman` initializes from then on, so this
+/// should be called before the first connection is created. The extension's callbacks don't
+/// identify the display they are called for, so there is a single cache per process, shared by all
+/// connections. Displays that lack the extension, and displays that the application initialized
+/// itself, don't use the cache.
+pub fn set_shader_cache(cache: Arc<dyn ShaderCache>) {
+    *SHADER_CACHE.write().unwrap() = Some(cache);
+}
+
+/// Installs the shader cache on a display that `surfman` has just initialized, if a cache has been
+/// set and the display supports `EGL_ANDROID_blob_cache`.
+pub(crate) unsafe fn install_shader_cache(egl_display: EGLDisplay) {
+    if SHADER_CACHE.read().unwrap().is_none() {
+        return;
+    }
+    let extension_functions = EGLDisplayExtensionFunctions::get(egl_display);
+    let Some(set_blob_cache_funcs) = extension_functions.SetBlobCacheFuncsANDROID else {
+        return;
+    };
+    set_blob_cache_funcs(egl_display, set_blob, get_blob);
+
+    // Drivers refuse to replace callbacks that are already installed, which happens if the
+    // display was terminated and initialized again. They are the same callbacks, so clear the
+    // error and carry on.
+    EGL_FUNCTIONS.with(|egl| egl.GetError());
+}
+
+impl DirectoryShaderCache {
+    /// Returns a cache that stores its entries in `directory`, which is created when the first
+    /// entry is stored.
+    pub fn new<P>(directory: P) -> DirectoryShaderCache
+    where
+        P: AsRef<Path>,
+    {
+        DirectoryShaderCache {
+            directory: directory.as_ref().to_owned(),
+        }
+    }
+
+    // Entries are named after a hash of their key. They start with the key itself, so that hash
+    // collisions read as misses.
+    fn entry_path(&self, key: &[u8]) -> PathBuf {
+        // 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust versions.
+        let hash = key.iter().fold(0xcbf29ce484222325_u64, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        self.directory.join(format!("{:016x}", hash))
+    }
+}
+
+impl ShaderCache for DirectoryShaderCache {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let entry = fs::read(self.entry_path(key)).ok()?;
+        let (key_size, rest) = entry.split_first_chunk::<8>()?;
+        let key_size = u64::from_le_bytes(*key_size) as usize;
+        if rest.len() < key_size || &rest[..key_size] != key {
+            return None;
+        }
+        Some(rest[key_size..].to_vec())
+    }
+
+    fn set(&self, key: &[u8], value: &[u8]) {
+        let mut entry = Vec::with_capacity(8 + key.len() + value.len());
+        entry.extend_from_slice(&(key.len() as u64).to_le_bytes());
+        entry.extend_from_slice(key);
+        entry.extend_from_slice(value);
+
+        // Write to a temporary file first so that readers never see a partial entry.
+        let path = self.entry_path(key);
+        let temporary_path = path.with_extension(format!(
+            "{}-{}.tmp",
+            process::id(),
+            NEXT_TEMPORARY_FILE_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let result = fs::create_dir_all(&self.directory)
+            .and_then(|()| fs::write(&temporary_path, &entry))
+            .and_then(|()| fs::rename(&temporary_path, &path));
+        if let Err(err) = result {
+            warn!(
+                "Failed to store shader cache entry {}: {}",
+                path.display(),
+                err
+            );
+            let _ = fs::remove_file(&temporary_path);
+        }
+    }
+}
+
+unsafe fn blob<'a>(data: *const c_void, size: EGLsizeiANDROID) -> &'a [u8] {
+    if data.is_null() || size <= 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data as *const u8, size as usize)
+    }
+}
+
+extern "C" fn set_blob(
+    key: *const c_void,
+    key_size: EGLsizeiANDROID,
+    value: *const c_void,
+    value_size: EGLsizeiANDROID,
+) {
+    // Panicking here would unwind into the driver, so ignore poisoning, which can't leave the
+    // cache half-written anyway.
+    let Some(cache) = SHADER_CACHE
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+    else {
+        return;
+    };
+    unsafe {
+        let (key, value) = (blob(key, key_size), blob(value, value_size));
+        // Unwinding into the driver would abort, so treat a panic as a failure to store.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| cache.set(key, value)));
+    }
+}
+
+extern "C" fn get_blob(
+    key: *const c_void,
+    key_size: EGLsizeiANDROID,
+    value: *mut c_void,
+    value_size: EGLsizeiANDROID,
+) -> EGLsizeiANDROID {
+    let Some(cache) = SHADER_CACHE
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+    else {
+        return 0;
+    };
+    unsafe {
+        let key = blob(key, key_size);
+        let Ok(Some(cached_value)) = panic::catch_unwind(AssertUnwindSafe(|| cache.get(key)))
+        else {
+            return 0;
+        };
+
+        // If the buffer is too small, the driver asks again with one of the returned size.
+        if !value.is_null() && cached_value.len() <= value_size.max(0) as usize {
+            ptr::copy_nonoverlapping(cached_value.as_ptr(), value as *mut u8, cached_value.len());
+        }
+        cached_value.len() as EGLsizeiANDROID
+    }
+}
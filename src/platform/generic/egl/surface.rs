@@ -7,25 +7,26 @@ use super::device::EGL_FUNCTIONS;
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLint};
 use crate::gl;
-use crate::gl_utils;
+use crate::gl_utils::{self, GLStateGroups, GLStateSnapshot};
 use crate::platform::generic::egl::error::ToWindowingApiError;
 use crate::platform::generic::egl::ffi::EGLClientBuffer;
+use crate::platform::generic::egl::ffi::EGLDisplayExtensionFunctions;
 use crate::platform::generic::egl::ffi::EGLImageKHR;
-use crate::platform::generic::egl::ffi::EGL_EXTENSION_FUNCTIONS;
 use crate::platform::generic::egl::ffi::EGL_GL_TEXTURE_2D_KHR;
 use crate::platform::generic::egl::ffi::EGL_IMAGE_PRESERVED_KHR;
 use crate::platform::generic::egl::ffi::EGL_NO_IMAGE_KHR;
 use crate::renderbuffers::Renderbuffers;
 use crate::Gl;
-use crate::{ContextAttributes, ContextID, Error, SurfaceID, SurfaceInfo};
+use crate::{ContextAttributes, ContextID, Error, SurfaceID, SurfaceInfo, WindowingApiError};
 
 use euclid::default::Size2D;
-use glow::{Framebuffer, HasContext, PixelUnpackData, Texture};
+use glow::{Framebuffer, HasContext, PixelPackData, PixelUnpackData, Texture};
 use std::fmt::{self, Debug, Formatter};
 use std::marker::PhantomData;
 use std::mem;
 use std::os::raw::c_void;
 use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[allow(dead_code)]
 #[derive(Clone)]
@@ -38,6 +39,9 @@ pub struct EGLBackedSurface {
     pub(crate) context_id: ContextID,
     pub(crate) size: Size2D<i32>,
     pub(crate) objects: EGLSurfaceObjects,
+    // The memory object backing the texture of surfaces created from external memory, which is
+    // deleted along with the surface.
+    pub(crate) memory_object: Option<u32>,
     pub(crate) destroyed: bool,
 }
 
@@ -49,13 +53,40 @@ impl Debug for EGLBackedSurface {
 
 unsafe impl Send for EGLBackedSurface {}
 
+/// Wrapper for the `EGLImageKHR` that backs a generic surface.
+///
+/// Any EGL client on the same `EGLDisplay` can use this image.
+#[derive(Clone, Copy)]
+pub struct NativeSurface {
+    /// The EGL image.
+    pub egl_image: egl::types::EGLImageKHR,
+    /// The size of the image in pixels.
+    pub size: Size2D<i32>,
+}
+
 #[allow(dead_code)]
 pub(crate) enum EGLSurfaceObjects {
     TextureImage {
         egl_image: EGLImageKHR,
+        // False if the image was supplied by the application, which remains responsible for
+        // destroying it.
+        owns_egl_image: bool,
+        framebuffer_object: Option<Framebuffer>,
+        texture_object: Option<Texture>,
+        // False if the texture was supplied by the application, which remains responsible for
+        // deleting it.
+        owns_texture: bool,
+        renderbuffers: Renderbuffers,
+    },
+    // Used when EGL images are unavailable. Surface textures are created by copying `pixels`,
+    // which are read back from the framebuffer when the surface is unbound from its context.
+    CopiedTexture {
+        id: usize,
         framebuffer_object: Option<Framebuffer>,
         texture_object: Option<Texture>,
+        owns_texture: bool,
         renderbuffers: Renderbuffers,
+        pixels: Vec<u8>,
     },
     Window {
         native_window: *const c_void,
@@ -63,6 +94,9 @@ pub(crate) enum EGLSurfaceObjects {
     },
 }
 
+// IDs for `CopiedTexture` surfaces, which have no EGL image to identify them by.
+static NEXT_COPIED_TEXTURE_ID: AtomicUsize = AtomicUsize::new(1);
+
 pub(crate) struct EGLSurfaceTexture {
     pub(crate) surface: EGLBackedSurface,
     pub(crate) texture_object: Option<Texture>,
@@ -84,24 +118,20 @@ impl EGLBackedSurface {
         context_attributes: &ContextAttributes,
         size: &Size2D<i32>,
     ) -> EGLBackedSurface {
-        let egl_image_attribs = [
-            EGL_IMAGE_PRESERVED_KHR as EGLint,
-            egl::FALSE as EGLint,
-            egl::NONE as EGLint,
-            0,
-        ];
-
         unsafe {
+            // Save the bindings that we're about to change, so that they don't leak into the
+            // context.
+            let state = GLStateSnapshot::capture(
+                gl,
+                GLStateGroups::TEXTURE_2D_BINDING | GLStateGroups::PIXEL_UNPACK_BUFFER_BINDING,
+            );
+
             // Create our texture.
             let texture_object = gl.create_texture().ok();
-            // Save the current texture binding
-            let old_texture_object = gl.get_parameter_texture(gl::TEXTURE_BINDING_2D);
             gl.bind_texture(gl::TEXTURE_2D, texture_object);
             // Unbind PIXEL_UNPACK_BUFFER, because if it is bound,
             // it can cause errors in glTexImage2D.
-            // TODO: should this be inside a check for GL 2.0?
-            let unpack_buffer = gl.get_parameter_buffer(gl::PIXEL_UNPACK_BUFFER_BINDING);
-            if unpack_buffer.is_some() {
+            if gl_utils::supports_pixel_buffer_objects(gl) {
                 gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, None);
             }
             gl.tex_image_2d(
@@ -115,48 +145,216 @@ impl EGLBackedSurface {
                 gl::UNSIGNED_BYTE,
                 PixelUnpackData::Slice(None),
             );
-            // Restore the old bindings
-            gl.bind_texture(gl::TEXTURE_2D, old_texture_object);
-            if unpack_buffer.is_some() {
-                gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, unpack_buffer);
-            }
 
-            // Create our image.
-            let egl_client_buffer =
-                texture_object.map_or(0, |tex| tex.0.get()) as usize as EGLClientBuffer;
-            let egl_image = (EGL_EXTENSION_FUNCTIONS.CreateImageKHR)(
+            // Restore the old bindings.
+            state.restore(gl);
+
+            let (surface, framebuffer_status) = EGLBackedSurface::new_with_texture(
+                gl,
                 egl_display,
                 egl_context,
-                EGL_GL_TEXTURE_2D_KHR,
-                egl_client_buffer,
-                egl_image_attribs.as_ptr(),
+                context_id,
+                context_attributes,
+                size,
+                texture_object,
+                true,
             );
+            debug_assert_eq!(framebuffer_status, gl::FRAMEBUFFER_COMPLETE);
+            surface
+        }
+    }
 
-            // Create the framebuffer, and bind the texture to it.
-            let framebuffer_object =
-                gl_utils::create_and_bind_framebuffer(gl, gl::TEXTURE_2D, texture_object);
+    /// Wraps an existing `TEXTURE_2D` owned by the caller. The texture is not deleted when the
+    /// surface is destroyed.
+    pub(crate) fn from_gl_texture(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        egl_context: EGLContext,
+        context_id: ContextID,
+        context_attributes: &ContextAttributes,
+        size: &Size2D<i32>,
+        texture: Texture,
+    ) -> Result<EGLBackedSurface, Error> {
+        unsafe {
+            let (mut surface, framebuffer_status) = EGLBackedSurface::new_with_texture(
+                gl,
+                egl_display,
+                egl_context,
+                context_id,
+                context_attributes,
+                size,
+                Some(texture),
+                false,
+            );
 
-            // Bind renderbuffers as appropriate.
-            let renderbuffers = Renderbuffers::new(gl, size, context_attributes);
-            renderbuffers.bind_to_current_framebuffer(gl);
+            // The texture might not be color-renderable, in which case we can't use it.
+            if framebuffer_status != gl::FRAMEBUFFER_COMPLETE {
+                surface.destroy(gl, egl_display, context_id)?;
+                return Err(Error::SurfaceImportFailed(WindowingApiError::BadMatch));
+            }
 
-            debug_assert_eq!(
-                gl.check_framebuffer_status(gl::FRAMEBUFFER),
-                gl::FRAMEBUFFER_COMPLETE
-            );
+            Ok(surface)
+        }
+    }
 
-            EGLBackedSurface {
-                context_id,
-                size: *size,
-                objects: EGLSurfaceObjects::TextureImage {
-                    egl_image,
-                    framebuffer_object: Some(framebuffer_object),
-                    texture_object,
-                    renderbuffers,
-                },
-                destroyed: false,
+    /// Wraps an existing EGL image, which is not destroyed when the surface is destroyed.
+    pub(crate) unsafe fn from_native_surface(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        context_id: ContextID,
+        context_attributes: &ContextAttributes,
+        native_surface: NativeSurface,
+    ) -> Result<EGLBackedSurface, Error> {
+        let egl_image = native_surface.egl_image as EGLImageKHR;
+        if egl_image == EGL_NO_IMAGE_KHR {
+            return Err(Error::SurfaceImportFailed(WindowingApiError::BadParameter));
+        }
+        if !gl_supports_egl_images(gl, egl_display) {
+            return Err(Error::RequiredExtensionUnavailable);
+        }
+
+        EGLBackedSurface::from_egl_image(
+            gl,
+            egl_display,
+            context_id,
+            context_attributes,
+            &native_surface.size,
+            egl_image,
+            false,
+        )
+    }
+
+    // Creates a surface that renders into the given EGL image. If `owns_egl_image` is true, the
+    // image is destroyed along with the surface, including if this fails.
+    pub(crate) unsafe fn from_egl_image(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        context_id: ContextID,
+        context_attributes: &ContextAttributes,
+        size: &Size2D<i32>,
+        egl_image: EGLImageKHR,
+        owns_egl_image: bool,
+    ) -> Result<EGLBackedSurface, Error> {
+        let texture_object = match bind_egl_image_to_gl_texture(gl, egl_display, egl_image) {
+            Ok(texture_object) => texture_object,
+            Err(err) => {
+                if owns_egl_image {
+                    if let Some(destroy_image) =
+                        EGLDisplayExtensionFunctions::get(egl_display).DestroyImageKHR
+                    {
+                        destroy_image(egl_display, egl_image);
+                    }
+                }
+                return Err(err);
             }
+        };
+        let (framebuffer_object, mut renderbuffers, framebuffer_status) =
+            create_framebuffer_for_texture(gl, size, context_attributes, Some(texture_object));
+
+        // The image might not be color-renderable, in which case we can't use it.
+        if framebuffer_status != gl::FRAMEBUFFER_COMPLETE {
+            gl.delete_framebuffer(framebuffer_object);
+            renderbuffers.destroy(gl);
+            gl.delete_texture(texture_object);
+            if owns_egl_image {
+                if let Some(destroy_image) =
+                    EGLDisplayExtensionFunctions::get(egl_display).DestroyImageKHR
+                {
+                    destroy_image(egl_display, egl_image);
+                }
+            }
+            return Err(Error::SurfaceImportFailed(WindowingApiError::BadMatch));
         }
+
+        Ok(EGLBackedSurface {
+            context_id,
+            size: *size,
+            objects: EGLSurfaceObjects::TextureImage {
+                egl_image,
+                owns_egl_image,
+                framebuffer_object: Some(framebuffer_object),
+                texture_object: Some(texture_object),
+                owns_texture: true,
+                renderbuffers,
+            },
+            memory_object: None,
+            destroyed: false,
+        })
+    }
+
+    // Creates an EGL image (if we can) and a framebuffer for the given texture. Returns the
+    // surface along with the completeness status of its framebuffer.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) unsafe fn new_with_texture(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        egl_context: EGLContext,
+        context_id: ContextID,
+        context_attributes: &ContextAttributes,
+        size: &Size2D<i32>,
+        texture_object: Option<Texture>,
+        owns_texture: bool,
+    ) -> (EGLBackedSurface, u32) {
+        let egl_image_attribs = [
+            EGL_IMAGE_PRESERVED_KHR as EGLint,
+            egl::FALSE as EGLint,
+            egl::NONE as EGLint,
+            0,
+        ];
+
+        // Create our image, if we can.
+        let extensions = EGLDisplayExtensionFunctions::get(egl_display);
+        let egl_image = match extensions.CreateImageKHR {
+            Some(create_image)
+                if extensions.supports_egl_images()
+                    && extensions.gl_texture_2d_images
+                    && gl_supports_egl_images(gl, egl_display) =>
+            {
+                let egl_client_buffer =
+                    texture_object.map_or(0, |tex| tex.0.get()) as usize as EGLClientBuffer;
+                create_image(
+                    egl_display,
+                    egl_context,
+                    EGL_GL_TEXTURE_2D_KHR,
+                    egl_client_buffer,
+                    egl_image_attribs.as_ptr(),
+                )
+            }
+            _ => EGL_NO_IMAGE_KHR,
+        };
+
+        let (framebuffer_object, renderbuffers, framebuffer_status) =
+            create_framebuffer_for_texture(gl, size, context_attributes, texture_object);
+
+        // Fall back to copying pixels if we couldn't create an image.
+        let objects = if egl_image != EGL_NO_IMAGE_KHR {
+            EGLSurfaceObjects::TextureImage {
+                egl_image,
+                owns_egl_image: true,
+                framebuffer_object: Some(framebuffer_object),
+                texture_object,
+                owns_texture,
+                renderbuffers,
+            }
+        } else {
+            EGLSurfaceObjects::CopiedTexture {
+                id: NEXT_COPIED_TEXTURE_ID.fetch_add(1, Ordering::Relaxed),
+                framebuffer_object: Some(framebuffer_object),
+                texture_object,
+                owns_texture,
+                renderbuffers,
+                pixels: vec![],
+            }
+        };
+
+        let surface = EGLBackedSurface {
+            context_id,
+            size: *size,
+            objects,
+            memory_object: None,
+            destroyed: false,
+        };
+        (surface, framebuffer_status)
     }
 
     pub(crate) fn new_window(
@@ -183,6 +381,7 @@ impl EGLBackedSurface {
                     native_window,
                     egl_surface,
                 },
+                memory_object: None,
                 destroyed: false,
             }
         })
@@ -191,13 +390,21 @@ impl EGLBackedSurface {
     pub(crate) fn to_surface_texture(
         self,
         gl: &Gl,
+        egl_display: EGLDisplay,
     ) -> Result<EGLSurfaceTexture, (Error, EGLBackedSurface)> {
         unsafe {
-            let egl_image = match self.objects {
-                EGLSurfaceObjects::TextureImage { egl_image, .. } => egl_image,
+            let texture_object = match self.objects {
+                EGLSurfaceObjects::TextureImage { egl_image, .. } => {
+                    match bind_egl_image_to_gl_texture(gl, egl_display, egl_image) {
+                        Ok(texture_object) => texture_object,
+                        Err(err) => return Err((err, self)),
+                    }
+                }
+                EGLSurfaceObjects::CopiedTexture { ref pixels, .. } => {
+                    upload_pixels_to_gl_texture(gl, &self.size, pixels)
+                }
                 EGLSurfaceObjects::Window { .. } => return Err((Error::WidgetAttached, self)),
             };
-            let texture_object = bind_egl_image_to_gl_texture(gl, egl_image);
             Ok(EGLSurfaceTexture {
                 surface: self,
                 texture_object: Some(texture_object),
@@ -220,8 +427,10 @@ impl EGLBackedSurface {
             match self.objects {
                 EGLSurfaceObjects::TextureImage {
                     ref mut egl_image,
+                    owns_egl_image,
                     ref mut framebuffer_object,
                     ref mut texture_object,
+                    owns_texture,
                     ref mut renderbuffers,
                 } => {
                     gl.bind_framebuffer(gl::FRAMEBUFFER, None);
@@ -230,17 +439,57 @@ impl EGLBackedSurface {
                     }
                     renderbuffers.destroy(gl);
 
-                    let result = (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, *egl_image);
-                    assert_ne!(result, egl::FALSE);
+                    // Without `EGL_KHR_image_base`, the image can't have been created by us.
+                    let destroy_image =
+                        EGLDisplayExtensionFunctions::get(egl_display).DestroyImageKHR;
+                    if let (true, Some(destroy_image)) = (owns_egl_image, destroy_image) {
+                        if destroy_image(egl_display, *egl_image) == egl::FALSE {
+                            warn!("Failed to destroy an EGL image");
+                        }
+                    }
                     *egl_image = EGL_NO_IMAGE_KHR;
 
                     if let Some(texture) = texture_object.take() {
-                        gl.delete_texture(texture);
+                        if owns_texture {
+                            gl.delete_texture(texture);
+                        }
+                    }
+                    #[cfg(free_unix)]
+                    if let Some(memory_object) = self.memory_object.take() {
+                        super::external_objects::delete_memory_object(memory_object);
                     }
 
                     self.destroyed = true;
                     Ok(None)
                 }
+                EGLSurfaceObjects::CopiedTexture {
+                    ref mut framebuffer_object,
+                    ref mut texture_object,
+                    owns_texture,
+                    ref mut renderbuffers,
+                    ref mut pixels,
+                    ..
+                } => {
+                    gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+                    if let Some(framebuffer) = framebuffer_object.take() {
+                        gl.delete_framebuffer(framebuffer);
+                    }
+                    renderbuffers.destroy(gl);
+
+                    if let Some(texture) = texture_object.take() {
+                        if owns_texture {
+                            gl.delete_texture(texture);
+                        }
+                    }
+                    #[cfg(free_unix)]
+                    if let Some(memory_object) = self.memory_object.take() {
+                        super::external_objects::delete_memory_object(memory_object);
+                    }
+                    *pixels = vec![];
+
+                    self.destroyed = true;
+                    Ok(None)
+                }
                 EGLSurfaceObjects::Window {
                     ref mut egl_surface,
                     ref mut native_window,
@@ -288,7 +537,8 @@ impl EGLBackedSurface {
                         }
                     })
                 }
-                EGLSurfaceObjects::TextureImage { .. } => Err(Error::NoWidgetAttached),
+                EGLSurfaceObjects::TextureImage { .. }
+                | EGLSurfaceObjects::CopiedTexture { .. } => Err(Error::NoWidgetAttached),
             }
         }
     }
@@ -301,6 +551,9 @@ impl EGLBackedSurface {
             framebuffer_object: match self.objects {
                 EGLSurfaceObjects::TextureImage {
                     framebuffer_object, ..
+                }
+                | EGLSurfaceObjects::CopiedTexture {
+                    framebuffer_object, ..
                 } => framebuffer_object,
                 EGLSurfaceObjects::Window { .. } => None,
             },
@@ -310,13 +563,16 @@ impl EGLBackedSurface {
     pub(crate) fn id(&self) -> SurfaceID {
         match self.objects {
             EGLSurfaceObjects::TextureImage { egl_image, .. } => SurfaceID(egl_image as usize),
+            EGLSurfaceObjects::CopiedTexture { id, .. } => SurfaceID(id),
             EGLSurfaceObjects::Window { egl_surface, .. } => SurfaceID(egl_surface as usize),
         }
     }
 
     pub(crate) fn native_window(&self) -> Result<*const c_void, Error> {
         match self.objects {
-            EGLSurfaceObjects::TextureImage { .. } => Err(Error::NoWidgetAttached),
+            EGLSurfaceObjects::TextureImage { .. } | EGLSurfaceObjects::CopiedTexture { .. } => {
+                Err(Error::NoWidgetAttached)
+            }
             EGLSurfaceObjects::Window { native_window, .. } => Ok(native_window),
         }
     }
@@ -335,10 +591,16 @@ impl EGLBackedSurface {
                     EGLSurfaceObjects::TextureImage {
                         framebuffer_object: Some(framebuffer_object),
                         ..
+                    }
+                    | EGLSurfaceObjects::CopiedTexture {
+                        framebuffer_object: Some(framebuffer_object),
+                        ..
                     } => {
                         gl_utils::unbind_framebuffer_if_necessary(gl, framebuffer_object);
                     }
-                    EGLSurfaceObjects::TextureImage { .. } | EGLSurfaceObjects::Window { .. } => {}
+                    EGLSurfaceObjects::TextureImage { .. }
+                    | EGLSurfaceObjects::CopiedTexture { .. }
+                    | EGLSurfaceObjects::Window { .. } => {}
                 }
             })
         }
@@ -350,7 +612,51 @@ impl EGLBackedSurface {
                 draw: egl_surface,
                 read: egl_surface,
             },
-            EGLSurfaceObjects::TextureImage { .. } => ExternalEGLSurfaces::default(),
+            EGLSurfaceObjects::TextureImage { .. } | EGLSurfaceObjects::CopiedTexture { .. } => {
+                ExternalEGLSurfaces::default()
+            }
+        }
+    }
+
+    pub(crate) fn native_surface(&self) -> Result<NativeSurface, Error> {
+        match self.objects {
+            EGLSurfaceObjects::TextureImage { egl_image, .. } => Ok(NativeSurface {
+                egl_image: egl_image as egl::types::EGLImageKHR,
+                size: self.size,
+            }),
+            // There's no image to export if EGL images are unavailable.
+            EGLSurfaceObjects::CopiedTexture { .. } => Err(Error::RequiredExtensionUnavailable),
+            EGLSurfaceObjects::Window { .. } => Err(Error::WidgetAttached),
+        }
+    }
+
+    pub(crate) fn texture_object(&self) -> Option<Texture> {
+        match self.objects {
+            EGLSurfaceObjects::TextureImage { texture_object, .. }
+            | EGLSurfaceObjects::CopiedTexture { texture_object, .. } => texture_object,
+            EGLSurfaceObjects::Window { .. } => None,
+        }
+    }
+
+    // Returns true if surface textures are created by copying this surface's pixels, which must
+    // be read back with `read_back_pixels()` before the surface leaves its context.
+    pub(crate) fn needs_read_back(&self) -> bool {
+        matches!(self.objects, EGLSurfaceObjects::CopiedTexture { .. })
+    }
+
+    // Copies the contents of the surface's framebuffer into memory. The surface's context must be
+    // current.
+    pub(crate) fn read_back_pixels(&mut self, gl: &Gl) {
+        let size = self.size;
+        if let EGLSurfaceObjects::CopiedTexture {
+            framebuffer_object,
+            ref mut pixels,
+            ..
+        } = self.objects
+        {
+            unsafe {
+                read_framebuffer_pixels(gl, framebuffer_object, &size, pixels);
+            }
         }
     }
 }
@@ -404,21 +710,147 @@ pub(crate) unsafe fn create_pbuffer_surface(
     })
 }
 
+// Reads the RGBA contents of a framebuffer into `pixels`, bottom row first.
+pub(crate) unsafe fn read_framebuffer_pixels(
+    gl: &Gl,
+    framebuffer_object: Option<Framebuffer>,
+    size: &Size2D<i32>,
+    pixels: &mut Vec<u8>,
+) {
+    let state = GLStateSnapshot::capture(
+        gl,
+        GLStateGroups::FRAMEBUFFER_BINDINGS | GLStateGroups::PIXEL_PACK_BUFFER_BINDING,
+    );
+    gl.bind_framebuffer(gl::READ_FRAMEBUFFER, framebuffer_object);
+    if gl_utils::supports_pixel_buffer_objects(gl) {
+        gl.bind_buffer(gl::PIXEL_PACK_BUFFER, None);
+    }
+    pixels.resize(size.width as usize * size.height as usize * 4, 0);
+    gl.read_pixels(
+        0,
+        0,
+        size.width,
+        size.height,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        PixelPackData::Slice(Some(pixels)),
+    );
+    state.restore(gl);
+}
+
+// Creates a framebuffer with the given texture as its color attachment, along with any
+// renderbuffers that the context attributes call for. Also returns the completeness status of the
+// framebuffer.
+unsafe fn create_framebuffer_for_texture(
+    gl: &Gl,
+    size: &Size2D<i32>,
+    context_attributes: &ContextAttributes,
+    texture_object: Option<Texture>,
+) -> (Framebuffer, Renderbuffers, u32) {
+    // Save the bindings that we're about to change, so that they don't leak into the context.
+    let state = GLStateSnapshot::capture(
+        gl,
+        GLStateGroups::FRAMEBUFFER_BINDINGS
+            | GLStateGroups::TEXTURE_2D_BINDING
+            | GLStateGroups::RENDERBUFFER_BINDING,
+    );
+
+    // Create the framebuffer, and bind the texture to it.
+    let framebuffer_object =
+        gl_utils::create_and_bind_framebuffer(gl, gl::TEXTURE_2D, texture_object);
+
+    // Bind renderbuffers as appropriate.
+    let renderbuffers = Renderbuffers::new(gl, size, context_attributes);
+    renderbuffers.bind_to_current_framebuffer(gl);
+
+    let framebuffer_status = gl.check_framebuffer_status(gl::FRAMEBUFFER);
+
+    // Restore the old bindings.
+    state.restore(gl);
+
+    (framebuffer_object, renderbuffers, framebuffer_status)
+}
+
+// Returns true if EGL images can be bound to textures in the given OpenGL context.
+//
+// `glEGLImageTargetTexture2DOES` is looked up with `eglGetProcAddress`, which may return a
+// function even if the driver lacks `GL_OES_EGL_image`, so the GL extension string has the final
+// say.
+pub(crate) fn gl_supports_egl_images(gl: &Gl, egl_display: EGLDisplay) -> bool {
+    EGLDisplayExtensionFunctions::get(egl_display)
+        .ImageTargetTexture2DOES
+        .is_some()
+        && gl.supported_extensions().contains("GL_OES_EGL_image")
+}
+
 #[allow(dead_code)]
-pub(crate) unsafe fn bind_egl_image_to_gl_texture(gl: &Gl, egl_image: EGLImageKHR) -> Texture {
+pub(crate) unsafe fn bind_egl_image_to_gl_texture(
+    gl: &Gl,
+    egl_display: EGLDisplay,
+    egl_image: EGLImageKHR,
+) -> Result<Texture, Error> {
+    let image_target_texture_2d =
+        match EGLDisplayExtensionFunctions::get(egl_display).ImageTargetTexture2DOES {
+            Some(image_target_texture_2d) if gl_supports_egl_images(gl, egl_display) => {
+                image_target_texture_2d
+            }
+            _ => return Err(Error::RequiredExtensionUnavailable),
+        };
+
     let texture = gl.create_texture().unwrap();
 
     let texture_binding = gl.get_parameter_texture(gl::TEXTURE_BINDING_2D);
 
     // FIXME(pcwalton): Should this be `GL_TEXTURE_EXTERNAL_OES`?
     gl.bind_texture(gl::TEXTURE_2D, Some(texture));
-    (EGL_EXTENSION_FUNCTIONS.ImageTargetTexture2DOES)(gl::TEXTURE_2D, egl_image);
+    image_target_texture_2d(gl::TEXTURE_2D, egl_image);
     gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
     gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
     gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as _);
     gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as _);
     gl.bind_texture(gl::TEXTURE_2D, texture_binding);
 
+    debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
+    Ok(texture)
+}
+
+// The fallback for `bind_egl_image_to_gl_texture()` when EGL images are unavailable.
+unsafe fn upload_pixels_to_gl_texture(gl: &Gl, size: &Size2D<i32>, pixels: &[u8]) -> Texture {
+    let texture = gl.create_texture().unwrap();
+
+    let state = GLStateSnapshot::capture(
+        gl,
+        GLStateGroups::TEXTURE_2D_BINDING | GLStateGroups::PIXEL_UNPACK_BUFFER_BINDING,
+    );
+
+    // If the surface was never read back, it has no contents yet.
+    let pixels = if pixels.is_empty() {
+        None
+    } else {
+        Some(pixels)
+    };
+
+    gl.bind_texture(gl::TEXTURE_2D, Some(texture));
+    if gl_utils::supports_pixel_buffer_objects(gl) {
+        gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, None);
+    }
+    gl.tex_image_2d(
+        gl::TEXTURE_2D,
+        0,
+        gl::RGBA as i32,
+        size.width,
+        size.height,
+        0,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        PixelUnpackData::Slice(pixels),
+    );
+    gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
+    gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
+    gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as _);
+    gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as _);
+    state.restore(gl);
+
     debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
     texture
 }
@@ -0,0 +1,435 @@
+// surfman/surfman/src/platform/generic/egl/surface_handle.rs
+//
+//! Sharing generic surfaces between processes, via dma-bufs or shared memory.
+
+use super::device::EGL_FUNCTIONS;
+use super::error::ToWindowingApiError;
+use super::ffi::{EGLDisplayExtensionFunctions, EGLImageKHR, DRM_FORMAT_MOD_INVALID};
+use super::ffi::{EGL_DMA_BUF_PLANE0_FD_EXT, EGL_DMA_BUF_PLANE0_MODIFIER_HI_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE0_MODIFIER_LO_EXT, EGL_DMA_BUF_PLANE0_OFFSET_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE0_PITCH_EXT, EGL_DMA_BUF_PLANE1_FD_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE1_MODIFIER_HI_EXT, EGL_DMA_BUF_PLANE1_MODIFIER_LO_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE1_OFFSET_EXT, EGL_DMA_BUF_PLANE1_PITCH_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE2_FD_EXT, EGL_DMA_BUF_PLANE2_MODIFIER_HI_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE2_MODIFIER_LO_EXT, EGL_DMA_BUF_PLANE2_OFFSET_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE2_PITCH_EXT, EGL_DMA_BUF_PLANE3_FD_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE3_MODIFIER_HI_EXT, EGL_DMA_BUF_PLANE3_MODIFIER_LO_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE3_OFFSET_EXT, EGL_DMA_BUF_PLANE3_PITCH_EXT};
+use super::ffi::{EGL_LINUX_DMA_BUF_EXT, EGL_LINUX_DRM_FOURCC_EXT, EGL_NO_IMAGE_KHR};
+use super::surface::{read_framebuffer_pixels, EGLBackedSurface, EGLSurfaceObjects};
+use crate::egl;
+use crate::egl::types::{EGLContext, EGLDisplay, EGLenum, EGLint};
+use crate::gl;
+use crate::gl_utils::{self, GLStateGroups, GLStateSnapshot};
+use crate::{ContextAttributes, ContextID, Error, Gl, WindowingApiError};
+
+use euclid::default::Size2D;
+use glow::{HasContext, PixelUnpackData};
+use std::fs::File;
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::raw::c_int;
+use std::os::unix::fs::FileExt;
+use std::ptr;
+
+// dma-bufs can have at most four planes.
+const MAX_DMA_BUF_PLANES: usize = 4;
+
+// Attributes for each plane of a dma-buf: fd, offset, pitch, modifier (low), modifier (high).
+const DMA_BUF_PLANE_ATTRIBUTES: [[EGLenum; 5]; MAX_DMA_BUF_PLANES] = [
+    [
+        EGL_DMA_BUF_PLANE0_FD_EXT,
+        EGL_DMA_BUF_PLANE0_OFFSET_EXT,
+        EGL_DMA_BUF_PLANE0_PITCH_EXT,
+        EGL_DMA_BUF_PLANE0_MODIFIER_LO_EXT,
+        EGL_DMA_BUF_PLANE0_MODIFIER_HI_EXT,
+    ],
+    [
+        EGL_DMA_BUF_PLANE1_FD_EXT,
+        EGL_DMA_BUF_PLANE1_OFFSET_EXT,
+        EGL_DMA_BUF_PLANE1_PITCH_EXT,
+        EGL_DMA_BUF_PLANE1_MODIFIER_LO_EXT,
+        EGL_DMA_BUF_PLANE1_MODIFIER_HI_EXT,
+    ],
+    [
+        EGL_DMA_BUF_PLANE2_FD_EXT,
+        EGL_DMA_BUF_PLANE2_OFFSET_EXT,
+        EGL_DMA_BUF_PLANE2_PITCH_EXT,
+        EGL_DMA_BUF_PLANE2_MODIFIER_LO_EXT,
+        EGL_DMA_BUF_PLANE2_MODIFIER_HI_EXT,
+    ],
+    [
+        EGL_DMA_BUF_PLANE3_FD_EXT,
+        EGL_DMA_BUF_PLANE3_OFFSET_EXT,
+        EGL_DMA_BUF_PLANE3_PITCH_EXT,
+        EGL_DMA_BUF_PLANE3_MODIFIER_LO_EXT,
+        EGL_DMA_BUF_PLANE3_MODIFIER_HI_EXT,
+    ],
+];
+
+/// A handle to the contents of a generic surface that can be sent to another process.
+///
+/// The file descriptors can be transferred over a Unix domain socket with `SCM_RIGHTS`. The
+/// receiving process rebuilds the handle from them and passes it to
+/// `Device::create_surface_from_handle()`.
+#[derive(Debug)]
+pub enum SurfaceHandle {
+    /// The memory of the surface itself, exported as a dma-buf via
+    /// `EGL_MESA_image_dma_buf_export`.
+    ///
+    /// Surfaces imported from this handle share memory with the exported surface, so no copies
+    /// are made.
+    DmaBuf {
+        /// The size of the surface in pixels.
+        size: Size2D<i32>,
+        /// The DRM fourcc code of the pixel format.
+        fourcc: u32,
+        /// The DRM format modifier describing the memory layout, or
+        /// `DRM_FORMAT_MOD_INVALID` (`0x00ff_ffff_ffff_ffff`) if the layout is implicit.
+        modifier: u64,
+        /// The planes of the image.
+        planes: Vec<DmaBufPlane>,
+    },
+    /// A copy of the pixels of the surface in a memfd, as tightly packed 8-bit RGBA rows, bottom
+    /// row first.
+    ///
+    /// This is used when dma-bufs can't be exported. Surfaces imported from this handle get their
+    /// own copy of the pixels.
+    SharedMemory {
+        /// The size of the surface in pixels.
+        size: Size2D<i32>,
+        /// The memfd containing the pixels.
+        fd: OwnedFd,
+    },
+}
+
+/// One plane of a dma-buf.
+#[derive(Debug)]
+pub struct DmaBufPlane {
+    /// The dma-buf file descriptor.
+    pub fd: OwnedFd,
+    /// The offset of the plane within the dma-buf in bytes.
+    pub offset: u32,
+    /// The stride of each row of the plane in bytes.
+    pub stride: u32,
+}
+
+impl SurfaceHandle {
+    /// Returns the size of the surface in pixels.
+    #[inline]
+    pub fn size(&self) -> Size2D<i32> {
+        match *self {
+            SurfaceHandle::DmaBuf { size, .. } | SurfaceHandle::SharedMemory { size, .. } => size,
+        }
+    }
+}
+
+impl EGLBackedSurface {
+    // Exports the surface, preferring a dma-buf and falling back to a shared memory copy. The
+    // surface's context must be current.
+    pub(crate) fn export_handle(
+        &self,
+        gl: &Gl,
+        egl_display: EGLDisplay,
+    ) -> Result<SurfaceHandle, Error> {
+        let framebuffer_object = match self.objects {
+            EGLSurfaceObjects::TextureImage {
+                framebuffer_object, ..
+            }
+            | EGLSurfaceObjects::CopiedTexture {
+                framebuffer_object, ..
+            } => framebuffer_object,
+            EGLSurfaceObjects::Window { .. } => return Err(Error::WidgetAttached),
+        };
+
+        unsafe {
+            // The other process has no way to wait on our rendering, so finish it now.
+            gl.finish();
+
+            if let EGLSurfaceObjects::TextureImage { egl_image, .. } = self.objects {
+                if let Some(handle) = export_dma_buf(egl_display, egl_image, &self.size) {
+                    return Ok(handle);
+                }
+            }
+
+            let mut pixels = vec![];
+            read_framebuffer_pixels(gl, framebuffer_object, &self.size, &mut pixels);
+            Ok(SurfaceHandle::SharedMemory {
+                size: self.size,
+                fd: create_shared_memory(&pixels)?,
+            })
+        }
+    }
+
+    // Imports a surface exported by `export_handle()`, possibly in another process.
+    pub(crate) unsafe fn from_handle(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        egl_context: EGLContext,
+        context_id: ContextID,
+        context_attributes: &ContextAttributes,
+        handle: &SurfaceHandle,
+    ) -> Result<EGLBackedSurface, Error> {
+        match *handle {
+            SurfaceHandle::DmaBuf {
+                size,
+                fourcc,
+                modifier,
+                ref planes,
+            } => {
+                let egl_image = import_dma_buf(egl_display, &size, fourcc, modifier, planes)?;
+                EGLBackedSurface::from_egl_image(
+                    gl,
+                    egl_display,
+                    context_id,
+                    context_attributes,
+                    &size,
+                    egl_image,
+                    true,
+                )
+            }
+            SurfaceHandle::SharedMemory { size, ref fd } => {
+                let pixels = read_shared_memory(&size, fd)?;
+
+                let mut surface = EGLBackedSurface::new_generic(
+                    gl,
+                    egl_display,
+                    egl_context,
+                    context_id,
+                    context_attributes,
+                    &size,
+                );
+                upload_pixels_to_surface(gl, &mut surface, pixels);
+                Ok(surface)
+            }
+        }
+    }
+}
+
+unsafe fn export_dma_buf(
+    egl_display: EGLDisplay,
+    egl_image: EGLImageKHR,
+    size: &Size2D<i32>,
+) -> Option<SurfaceHandle> {
+    let extensions = EGLDisplayExtensionFunctions::get(egl_display);
+    let (query_image, export_image) = match (
+        extensions.ExportDMABUFImageQueryMESA,
+        extensions.ExportDMABUFImageMESA,
+    ) {
+        (Some(query_image), Some(export_image)) => (query_image, export_image),
+        _ => return None,
+    };
+
+    let (mut fourcc, mut plane_count, mut modifier) = (0, 0, DRM_FORMAT_MOD_INVALID);
+    let result = query_image(
+        egl_display,
+        egl_image,
+        &mut fourcc,
+        &mut plane_count,
+        &mut modifier,
+    );
+    if result == egl::FALSE || plane_count < 1 || plane_count as usize > MAX_DMA_BUF_PLANES {
+        return None;
+    }
+    let plane_count = plane_count as usize;
+
+    let mut fds: [c_int; MAX_DMA_BUF_PLANES] = [-1; MAX_DMA_BUF_PLANES];
+    let mut strides: [EGLint; MAX_DMA_BUF_PLANES] = [0; MAX_DMA_BUF_PLANES];
+    let mut offsets: [EGLint; MAX_DMA_BUF_PLANES] = [0; MAX_DMA_BUF_PLANES];
+    let result = export_image(
+        egl_display,
+        egl_image,
+        fds.as_mut_ptr(),
+        strides.as_mut_ptr(),
+        offsets.as_mut_ptr(),
+    );
+    if result == egl::FALSE {
+        return None;
+    }
+
+    // Take ownership of the file descriptors right away, so that none of them leak if we bail out
+    // below. Planes that live in the same buffer may share a file descriptor, or not get one at
+    // all; those get a duplicate of the earlier plane's, so that every plane owns a distinct one.
+    let mut owned_fds: [Option<OwnedFd>; MAX_DMA_BUF_PLANES] = Default::default();
+    for plane_index in 0..plane_count {
+        let fd = fds[plane_index];
+        if fd >= 0 && !fds[..plane_index].contains(&fd) {
+            owned_fds[plane_index] = Some(OwnedFd::from_raw_fd(fd));
+        }
+    }
+    owned_fds[0].as_ref()?;
+
+    let mut planes: Vec<DmaBufPlane> = Vec::with_capacity(plane_count);
+    for plane_index in 0..plane_count {
+        let fd = match owned_fds[plane_index].take() {
+            Some(fd) => fd,
+            None => {
+                let fd = fds[plane_index];
+                let previous = fds[..plane_index]
+                    .iter()
+                    .position(|&other| fd >= 0 && other == fd)
+                    .unwrap_or(0);
+                planes[previous].fd.try_clone().ok()?
+            }
+        };
+        planes.push(DmaBufPlane {
+            fd,
+            offset: offsets[plane_index] as u32,
+            stride: strides[plane_index] as u32,
+        });
+    }
+
+    Some(SurfaceHandle::DmaBuf {
+        size: *size,
+        fourcc: fourcc as u32,
+        modifier,
+        planes,
+    })
+}
+
+unsafe fn import_dma_buf(
+    egl_display: EGLDisplay,
+    size: &Size2D<i32>,
+    fourcc: u32,
+    modifier: u64,
+    planes: &[DmaBufPlane],
+) -> Result<EGLImageKHR, Error> {
+    use std::os::fd::AsRawFd;
+
+    let extensions = EGLDisplayExtensionFunctions::get(egl_display);
+    let create_image = match extensions.CreateImageKHR {
+        Some(create_image) if extensions.dma_buf_import => create_image,
+        _ => return Err(Error::RequiredExtensionUnavailable),
+    };
+    let explicit_modifier = modifier != DRM_FORMAT_MOD_INVALID;
+    if explicit_modifier && !extensions.dma_buf_import_modifiers {
+        return Err(Error::RequiredExtensionUnavailable);
+    }
+    if planes.is_empty() || planes.len() > MAX_DMA_BUF_PLANES {
+        return Err(Error::SurfaceImportFailed(WindowingApiError::BadParameter));
+    }
+
+    let mut attributes = vec![
+        egl::WIDTH as EGLint,
+        size.width,
+        egl::HEIGHT as EGLint,
+        size.height,
+        EGL_LINUX_DRM_FOURCC_EXT as EGLint,
+        fourcc as EGLint,
+    ];
+    for (plane, plane_attributes) in planes.iter().zip(DMA_BUF_PLANE_ATTRIBUTES.iter()) {
+        attributes.extend_from_slice(&[
+            plane_attributes[0] as EGLint,
+            plane.fd.as_raw_fd(),
+            plane_attributes[1] as EGLint,
+            plane.offset as EGLint,
+            plane_attributes[2] as EGLint,
+            plane.stride as EGLint,
+        ]);
+        if explicit_modifier {
+            attributes.extend_from_slice(&[
+                plane_attributes[3] as EGLint,
+                modifier as u32 as EGLint,
+                plane_attributes[4] as EGLint,
+                (modifier >> 32) as u32 as EGLint,
+            ]);
+        }
+    }
+    attributes.push(egl::NONE as EGLint);
+
+    // EGL doesn't take ownership of the file descriptors, so the handle stays usable.
+    let egl_image = create_image(
+        egl_display,
+        egl::NO_CONTEXT,
+        EGL_LINUX_DMA_BUF_EXT,
+        ptr::null_mut(),
+        attributes.as_ptr(),
+    );
+    if egl_image == EGL_NO_IMAGE_KHR {
+        let err = EGL_FUNCTIONS.with(|egl| egl.GetError().to_windowing_api_error());
+        return Err(Error::SurfaceImportFailed(err));
+    }
+    Ok(egl_image)
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn create_shared_memory(pixels: &[u8]) -> Result<OwnedFd, Error> {
+    unsafe {
+        let fd = libc::memfd_create(c"surfman-surface".as_ptr(), libc::MFD_CLOEXEC);
+        if fd < 0 {
+            return Err(Error::SurfaceExportFailed(WindowingApiError::BadAlloc));
+        }
+        let file = File::from(OwnedFd::from_raw_fd(fd));
+        file.write_all_at(pixels, 0)
+            .map_err(|_| Error::SurfaceExportFailed(WindowingApiError::BadAlloc))?;
+        Ok(file.into())
+    }
+}
+
+// Reads the pixels out of a shared memory handle. The handle may have come from another process,
+// so its size is checked against the memfd before anything is read.
+fn read_shared_memory(size: &Size2D<i32>, fd: &OwnedFd) -> Result<Vec<u8>, Error> {
+    let bad_handle = || Error::SurfaceImportFailed(WindowingApiError::BadParameter);
+    if size.width <= 0 || size.height <= 0 {
+        return Err(bad_handle());
+    }
+    let length = (size.width as usize)
+        .checked_mul(size.height as usize)
+        .and_then(|pixel_count| pixel_count.checked_mul(4))
+        .ok_or_else(bad_handle)?;
+
+    let file = fd.try_clone().map(File::from).map_err(|_| bad_handle())?;
+    match file.metadata() {
+        Ok(metadata) if metadata.len() >= length as u64 => {}
+        _ => return Err(bad_handle()),
+    }
+    let mut pixels = vec![0; length];
+    file.read_exact_at(&mut pixels, 0)
+        .map_err(|_| bad_handle())?;
+    Ok(pixels)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+fn create_shared_memory(_: &[u8]) -> Result<OwnedFd, Error> {
+    Err(Error::UnsupportedOnThisPlatform)
+}
+
+// Copies imported pixels into a freshly created generic surface.
+unsafe fn upload_pixels_to_surface(gl: &Gl, surface: &mut EGLBackedSurface, pixels: Vec<u8>) {
+    let size = surface.size;
+    let texture_object = match surface.objects {
+        EGLSurfaceObjects::TextureImage { texture_object, .. }
+        | EGLSurfaceObjects::CopiedTexture { texture_object, .. } => texture_object,
+        EGLSurfaceObjects::Window { .. } => return,
+    };
+
+    let state = GLStateSnapshot::capture(
+        gl,
+        GLStateGroups::TEXTURE_2D_BINDING | GLStateGroups::PIXEL_UNPACK_BUFFER_BINDING,
+    );
+    gl.bind_texture(gl::TEXTURE_2D, texture_object);
+    if gl_utils::supports_pixel_buffer_objects(gl) {
+        gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, None);
+    }
+    gl.tex_sub_image_2d(
+        gl::TEXTURE_2D,
+        0,
+        0,
+        0,
+        size.width,
+        size.height,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        PixelUnpackData::Slice(Some(&pixels)),
+    );
+    state.restore(gl);
+
+    // Surfaces without EGL images share their contents by copying pixels, which would otherwise
+    // only be read back once the surface has been bound to its context.
+    if let EGLSurfaceObjects::CopiedTexture {
+        pixels: ref mut copied_pixels,
+        ..
+    } = surface.objects
+    {
+        *copied_pixels = pixels;
+    }
+}
@@ -5,6 +5,8 @@
 use super::device::{Adapter, Device, NativeDevice};
 use super::surface::NativeWidget;
 use crate::connection::Connection as ConnectionInterface;
+#[cfg(free_unix)]
+use crate::connection::GLApiConnection;
 use crate::device::Device as DeviceInterface;
 use crate::Error;
 use crate::GLApi;
@@ -254,6 +256,28 @@ where
     }
 }
 
+#[cfg(free_unix)]
+impl<Def, Alt> Connection<Def, Alt>
+where
+    Def: DeviceInterface,
+    Alt: DeviceInterface,
+    Def::Connection: ConnectionInterface<Device = Def> + GLApiConnection,
+    Alt::Connection: ConnectionInterface<Device = Alt> + GLApiConnection,
+{
+    /// Returns this connection with the OpenGL API flavor that its devices create contexts with
+    /// set to `gl_api`.
+    ///
+    /// Devices that have already been created from this connection are unaffected.
+    pub fn with_gl_api(self, gl_api: GLApi) -> Connection<Def, Alt> {
+        match self {
+            Connection::Default(connection) => Connection::Default(connection.with_gl_api(gl_api)),
+            Connection::Alternate(connection) => {
+                Connection::Alternate(connection.with_gl_api(gl_api))
+            }
+        }
+    }
+}
+
 impl<Def, Alt> ConnectionInterface for Connection<Def, Alt>
 where
     Def: DeviceInterface,
@@ -354,3 +378,17 @@ where
         Connection::create_native_widget_from_window_handle(self, handle, size)
     }
 }
+
+#[cfg(free_unix)]
+impl<Def, Alt> GLApiConnection for Connection<Def, Alt>
+where
+    Def: DeviceInterface,
+    Alt: DeviceInterface,
+    Def::Connection: ConnectionInterface<Device = Def> + GLApiConnection,
+    Alt::Connection: ConnectionInterface<Device = Alt> + GLApiConnection,
+{
+    #[inline]
+    fn with_gl_api(self, gl_api: GLApi) -> Connection<Def, Alt> {
+        Connection::with_gl_api(self, gl_api)
+    }
+}
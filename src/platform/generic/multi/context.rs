@@ -5,7 +5,7 @@
 use super::device::Device;
 use super::surface::Surface;
 use crate::device::Device as DeviceInterface;
-use crate::{ContextAttributes, ContextID, Error, SurfaceInfo};
+use crate::{ContextAttributes, ContextGL, ContextID, Error, SurfaceInfo};
 
 use std::os::raw::c_void;
 
@@ -51,6 +51,21 @@ where
     Alternate(Alt::ContextDescriptor),
 }
 
+/// Restores the previously-current context when dropped.
+///
+/// This is returned by `Device::make_context_current_scoped()`.
+#[must_use]
+pub enum CurrentContextGuard<Def, Alt>
+where
+    Def: DeviceInterface,
+    Alt: DeviceInterface,
+{
+    /// The default guard type.
+    Default(Def::CurrentContextGuard),
+    /// The alternate guard type.
+    Alternate(Alt::CurrentContextGuard),
+}
+
 /// Wraps a platform-specific native context.
 pub enum NativeContext<Def, Alt>
 where
@@ -85,6 +100,24 @@ where
         }
     }
 
+    /// Creates a context descriptor for the first of `candidates` that this device can actually
+    /// create a context with.
+    ///
+    /// Candidates are tried in order, so list them from most to least preferred.
+    pub fn negotiate_context_descriptor(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor<Def, Alt>, Error> {
+        match *self {
+            Device::Default(ref mut device) => device
+                .negotiate_context_descriptor(candidates)
+                .map(ContextDescriptor::Default),
+            Device::Alternate(ref mut device) => device
+                .negotiate_context_descriptor(candidates)
+                .map(ContextDescriptor::Alternate),
+        }
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -211,6 +244,26 @@ where
         }
     }
 
+    /// Makes the context the current OpenGL context for this thread until the returned guard is
+    /// dropped.
+    ///
+    /// When the guard is dropped, whichever context and draw/read surfaces were current before
+    /// this call are made current again, even if they weren't created by `surfman`.
+    pub fn make_context_current_scoped(
+        &self,
+        context: &Context<Def, Alt>,
+    ) -> Result<CurrentContextGuard<Def, Alt>, Error> {
+        match (self, context) {
+            (Device::Default(device), Context::Default(context)) => device
+                .make_context_current_scoped(context)
+                .map(CurrentContextGuard::Default),
+            (Device::Alternate(device), Context::Alternate(context)) => device
+                .make_context_current_scoped(context)
+                .map(CurrentContextGuard::Alternate),
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
     /// Attaches a surface to a context for rendering.
     ///
     /// This function takes ownership of the surface. The surface must have been created with this
@@ -264,6 +317,52 @@ where
         }
     }
 
+    /// Attaches a surface to a context for reading, separately from the surface it renders to.
+    ///
+    /// The surface must have been created with this context, or an `IncompatibleSurface` error is
+    /// returned. If a read surface is already bound, a `SurfaceAlreadyBound` error is returned.
+    ///
+    /// If an error is returned, the surface is returned alongside it.
+    pub fn bind_read_surface_to_context(
+        &self,
+        context: &mut Context<Def, Alt>,
+        surface: Surface<Def, Alt>,
+    ) -> Result<(), (Error, Surface<Def, Alt>)> {
+        match (self, &mut *context) {
+            (Device::Default(device), &mut Context::Default(ref mut context)) => match surface {
+                Surface::Default(surface) => device
+                    .bind_read_surface_to_context(context, surface)
+                    .map_err(|(err, surface)| (err, Surface::Default(surface))),
+                _ => Err((Error::IncompatibleSurface, surface)),
+            },
+            (Device::Alternate(device), &mut Context::Alternate(ref mut context)) => {
+                match surface {
+                    Surface::Alternate(surface) => device
+                        .bind_read_surface_to_context(context, surface)
+                        .map_err(|(err, surface)| (err, Surface::Alternate(surface))),
+                    _ => Err((Error::IncompatibleSurface, surface)),
+                }
+            }
+            _ => Err((Error::IncompatibleContext, surface)),
+        }
+    }
+
+    /// Removes and returns any read surface attached to this context.
+    pub fn unbind_read_surface_from_context(
+        &self,
+        context: &mut Context<Def, Alt>,
+    ) -> Result<Option<Surface<Def, Alt>>, Error> {
+        match (self, &mut *context) {
+            (Device::Default(device), &mut Context::Default(ref mut context)) => device
+                .unbind_read_surface_from_context(context)
+                .map(|surface| surface.map(Surface::Default)),
+            (Device::Alternate(device), &mut Context::Alternate(ref mut context)) => device
+                .unbind_read_surface_from_context(context)
+                .map(|surface| surface.map(Surface::Alternate)),
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
     /// Returns the attributes that the context descriptor was created with.
     pub fn context_descriptor_attributes(
         &self,
@@ -303,6 +402,27 @@ where
         }
     }
 
+    /// Makes the context current and returns the OpenGL function table that surfman loaded for
+    /// it.
+    ///
+    /// This lets applications share surfman's function pointers instead of loading their own with
+    /// `get_proc_address`. The context stays current until the returned value is dropped, at which
+    /// point whichever context was current before is made current again.
+    pub fn context_gl<'a>(
+        &self,
+        context: &'a Context<Def, Alt>,
+    ) -> Result<ContextGL<'a, CurrentContextGuard<Def, Alt>>, Error> {
+        match (self, context) {
+            (Device::Default(device), Context::Default(context)) => device
+                .context_gl(context)
+                .map(|gl| gl.map_guard(CurrentContextGuard::Default)),
+            (Device::Alternate(device), Context::Alternate(context)) => device
+                .context_gl(context)
+                .map(|gl| gl.map_guard(CurrentContextGuard::Alternate)),
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
     /// Returns a unique ID representing a context.
     ///
     /// This ID is unique to all currently-allocated contexts. If you destroy a context and create
@@ -332,4 +452,22 @@ where
             _ => Err(Error::IncompatibleContext),
         }
     }
+
+    /// Returns various information about the read surface attached to a context, if any.
+    ///
+    /// This includes, most notably, the OpenGL framebuffer object needed to read from the surface.
+    pub fn context_read_surface_info(
+        &self,
+        context: &Context<Def, Alt>,
+    ) -> Result<Option<SurfaceInfo>, Error> {
+        match (self, context) {
+            (Device::Default(device), Context::Default(context)) => {
+                device.context_read_surface_info(context)
+            }
+            (Device::Alternate(device), Context::Alternate(context)) => {
+                device.context_read_surface_info(context)
+            }
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
 }
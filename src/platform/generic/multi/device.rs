@@ -3,12 +3,17 @@
 //! A device abstraction that allows the choice of backends dynamically.
 
 use super::connection::Connection;
-use super::context::{Context, ContextDescriptor, NativeContext};
+use super::context::{Context, ContextDescriptor, CurrentContextGuard, NativeContext};
 use super::surface::{NativeWidget, Surface, SurfaceTexture};
 use crate::connection::Connection as ConnectionInterface;
-use crate::context::ContextAttributes;
+use crate::context::{ContextAttributeFlags, ContextAttributes};
 use crate::device::Device as DeviceInterface;
-use crate::{ContextID, Error, GLApi, SurfaceAccess, SurfaceInfo, SurfaceType};
+#[cfg(free_unix)]
+use crate::device::SurfaceHandleDevice;
+#[cfg(free_unix)]
+use crate::platform::generic::egl::surface_handle::SurfaceHandle;
+use crate::{ContextGL, ContextID, DeviceCapabilities, Error, GLApi};
+use crate::{SurfaceAccess, SurfaceInfo, SurfaceType};
 use euclid::default::Size2D;
 use glow::Texture;
 
@@ -107,6 +112,15 @@ where
             Device::Alternate(ref device) => device.gl_api(),
         }
     }
+
+    /// Returns the OpenGL versions, limits, surface formats, and extensions that this device
+    /// supports.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        match *self {
+            Device::Default(ref mut device) => device.capabilities(),
+            Device::Alternate(ref mut device) => device.capabilities(),
+        }
+    }
 }
 
 impl<Def, Alt> DeviceInterface for Device<Def, Alt>
@@ -119,6 +133,7 @@ where
     type Connection = Connection<Def, Alt>;
     type Context = Context<Def, Alt>;
     type ContextDescriptor = ContextDescriptor<Def, Alt>;
+    type CurrentContextGuard = CurrentContextGuard<Def, Alt>;
     type NativeContext = NativeContext<Def, Alt>;
     type Surface = Surface<Def, Alt>;
     type SurfaceTexture = SurfaceTexture<Def, Alt>;
@@ -145,6 +160,11 @@ where
         Device::gl_api(self)
     }
 
+    #[inline]
+    fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        Device::capabilities(self)
+    }
+
     // context.rs
 
     #[inline]
@@ -155,6 +175,14 @@ where
         Device::create_context_descriptor(self, attributes)
     }
 
+    #[inline]
+    fn negotiate_context_descriptor(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor<Def, Alt>, Error> {
+        Device::negotiate_context_descriptor(self, candidates)
+    }
+
     #[inline]
     fn create_context(
         &mut self,
@@ -197,6 +225,14 @@ where
         Device::make_no_context_current(self)
     }
 
+    #[inline]
+    fn make_context_current_scoped(
+        &self,
+        context: &Context<Def, Alt>,
+    ) -> Result<CurrentContextGuard<Def, Alt>, Error> {
+        Device::make_context_current_scoped(self, context)
+    }
+
     #[inline]
     fn context_descriptor_attributes(
         &self,
@@ -210,6 +246,14 @@ where
         Device::get_proc_address(self, context, symbol_name)
     }
 
+    #[inline]
+    fn context_gl<'a>(
+        &self,
+        context: &'a Context<Def, Alt>,
+    ) -> Result<ContextGL<'a, CurrentContextGuard<Def, Alt>>, Error> {
+        Device::context_gl(self, context)
+    }
+
     #[inline]
     fn bind_surface_to_context(
         &self,
@@ -227,6 +271,23 @@ where
         Device::unbind_surface_from_context(self, context)
     }
 
+    #[inline]
+    fn bind_read_surface_to_context(
+        &self,
+        context: &mut Context<Def, Alt>,
+        surface: Surface<Def, Alt>,
+    ) -> Result<(), (Error, Surface<Def, Alt>)> {
+        Device::bind_read_surface_to_context(self, context, surface)
+    }
+
+    #[inline]
+    fn unbind_read_surface_from_context(
+        &self,
+        context: &mut Context<Def, Alt>,
+    ) -> Result<Option<Surface<Def, Alt>>, Error> {
+        Device::unbind_read_surface_from_context(self, context)
+    }
+
     #[inline]
     fn context_id(&self, context: &Context<Def, Alt>) -> ContextID {
         Device::context_id(self, context)
@@ -240,6 +301,14 @@ where
         Device::context_surface_info(self, context)
     }
 
+    #[inline]
+    fn context_read_surface_info(
+        &self,
+        context: &Context<Def, Alt>,
+    ) -> Result<Option<SurfaceInfo>, Error> {
+        Device::context_read_surface_info(self, context)
+    }
+
     // surface.rs
 
     #[inline]
@@ -252,6 +321,33 @@ where
         Device::create_surface(self, context, surface_access, surface_type)
     }
 
+    #[inline]
+    fn create_surface_with_attributes(
+        &mut self,
+        context: &Context<Def, Alt>,
+        surface_access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget<Def, Alt>>,
+        surface_flags: ContextAttributeFlags,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        Device::create_surface_with_attributes(
+            self,
+            context,
+            surface_access,
+            surface_type,
+            surface_flags,
+        )
+    }
+
+    #[inline]
+    fn create_surface_from_gl_texture(
+        &mut self,
+        context: &Context<Def, Alt>,
+        size: &Size2D<i32>,
+        texture: Texture,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        Device::create_surface_from_gl_texture(self, context, size, texture)
+    }
+
     #[inline]
     fn create_surface_texture(
         &self,
@@ -316,3 +412,30 @@ where
         Device::surface_texture_object(self, surface_texture)
     }
 }
+
+#[cfg(free_unix)]
+impl<Def, Alt> SurfaceHandleDevice for Device<Def, Alt>
+where
+    Def: SurfaceHandleDevice,
+    Alt: SurfaceHandleDevice,
+    Def::Connection: ConnectionInterface<Device = Def>,
+    Alt::Connection: ConnectionInterface<Device = Alt>,
+{
+    #[inline]
+    fn export_surface(
+        &self,
+        context: &Context<Def, Alt>,
+        surface: &Surface<Def, Alt>,
+    ) -> Result<SurfaceHandle, Error> {
+        Device::export_surface(self, context, surface)
+    }
+
+    #[inline]
+    fn create_surface_from_handle(
+        &mut self,
+        context: &Context<Def, Alt>,
+        handle: &SurfaceHandle,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        Device::create_surface_from_handle(self, context, handle)
+    }
+}
@@ -6,7 +6,11 @@ use super::context::Context;
 use super::device::Device;
 use crate::connection::Connection as ConnectionInterface;
 use crate::device::Device as DeviceInterface;
-use crate::{Error, SurfaceAccess, SurfaceInfo, SurfaceType};
+#[cfg(free_unix)]
+use crate::device::SurfaceHandleDevice;
+#[cfg(free_unix)]
+use crate::platform::generic::egl::surface_handle::SurfaceHandle;
+use crate::{ContextAttributeFlags, Error, SurfaceAccess, SurfaceInfo, SurfaceType};
 use euclid::default::Size2D;
 use glow::Texture;
 
@@ -105,6 +109,31 @@ where
         context: &Context<Def, Alt>,
         surface_access: SurfaceAccess,
         surface_type: SurfaceType<NativeWidget<Def, Alt>>,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        self.create_surface_with_flags(context, surface_access, surface_type, None)
+    }
+
+    /// Like `create_surface()`, but the format of a generic surface is chosen by the `ALPHA`,
+    /// `DEPTH`, and `STENCIL` flags in `surface_flags` instead of by the context's descriptor.
+    /// Other flags are ignored.
+    ///
+    /// Widget surfaces always use the format of the context's descriptor.
+    pub fn create_surface_with_attributes(
+        &mut self,
+        context: &Context<Def, Alt>,
+        surface_access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget<Def, Alt>>,
+        surface_flags: ContextAttributeFlags,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        self.create_surface_with_flags(context, surface_access, surface_type, Some(surface_flags))
+    }
+
+    fn create_surface_with_flags(
+        &mut self,
+        context: &Context<Def, Alt>,
+        surface_access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget<Def, Alt>>,
+        surface_flags: Option<ContextAttributeFlags>,
     ) -> Result<Surface<Def, Alt>, Error> {
         match (&mut *self, context) {
             (&mut Device::Default(ref mut device), Context::Default(context)) => {
@@ -117,9 +146,16 @@ where
                         return Err(Error::IncompatibleNativeWidget)
                     }
                 };
-                device
-                    .create_surface(context, surface_access, surface_type)
-                    .map(Surface::Default)
+                match surface_flags {
+                    None => device.create_surface(context, surface_access, surface_type),
+                    Some(surface_flags) => device.create_surface_with_attributes(
+                        context,
+                        surface_access,
+                        surface_type,
+                        surface_flags,
+                    ),
+                }
+                .map(Surface::Default)
             }
             (&mut Device::Alternate(ref mut device), Context::Alternate(context)) => {
                 let surface_type = match surface_type {
@@ -131,14 +167,41 @@ where
                         return Err(Error::IncompatibleNativeWidget)
                     }
                 };
-                device
-                    .create_surface(context, surface_access, surface_type)
-                    .map(Surface::Alternate)
+                match surface_flags {
+                    None => device.create_surface(context, surface_access, surface_type),
+                    Some(surface_flags) => device.create_surface_with_attributes(
+                        context,
+                        surface_access,
+                        surface_type,
+                        surface_flags,
+                    ),
+                }
+                .map(Surface::Alternate)
             }
             _ => Err(Error::IncompatibleContext),
         }
     }
 
+    /// Creates a generic surface that renders into an existing OpenGL texture.
+    ///
+    /// The texture remains owned by the caller: destroying the surface does not delete it.
+    pub fn create_surface_from_gl_texture(
+        &mut self,
+        context: &Context<Def, Alt>,
+        size: &Size2D<i32>,
+        texture: Texture,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        match (&mut *self, context) {
+            (&mut Device::Default(ref mut device), Context::Default(context)) => device
+                .create_surface_from_gl_texture(context, size, texture)
+                .map(Surface::Default),
+            (&mut Device::Alternate(ref mut device), Context::Alternate(context)) => device
+                .create_surface_from_gl_texture(context, size, texture)
+                .map(Surface::Alternate),
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
     /// Creates a surface texture from an existing generic surface for use with the given context.
     ///
     /// The surface texture is local to the supplied context and takes ownership of the surface.
@@ -342,3 +405,54 @@ where
         }
     }
 }
+
+#[cfg(free_unix)]
+impl<Def, Alt> Device<Def, Alt>
+where
+    Def: SurfaceHandleDevice,
+    Alt: SurfaceHandleDevice,
+{
+    /// Exports the contents of a generic surface into a handle that can be sent to another
+    /// process, which can import it with `create_surface_from_handle()`.
+    ///
+    /// The supplied context must be the context the surface is associated with, or this returns
+    /// an `IncompatibleSurface` error. Exporting a widget surface returns a `WidgetAttached` error.
+    pub fn export_surface(
+        &self,
+        context: &Context<Def, Alt>,
+        surface: &Surface<Def, Alt>,
+    ) -> Result<SurfaceHandle, Error> {
+        match (self, context) {
+            (Device::Default(device), Context::Default(context)) => match *surface {
+                Surface::Default(ref surface) => device.export_surface(context, surface),
+                _ => Err(Error::IncompatibleSurface),
+            },
+            (Device::Alternate(device), Context::Alternate(context)) => match *surface {
+                Surface::Alternate(ref surface) => device.export_surface(context, surface),
+                _ => Err(Error::IncompatibleSurface),
+            },
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
+    /// Creates a generic surface from a handle produced by `export_surface()`, possibly in another
+    /// process.
+    ///
+    /// The handle does not record which backend exported it, so it is imported with whichever
+    /// backend this device uses.
+    pub fn create_surface_from_handle(
+        &mut self,
+        context: &Context<Def, Alt>,
+        handle: &SurfaceHandle,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        match (&mut *self, context) {
+            (&mut Device::Default(ref mut device), Context::Default(context)) => device
+                .create_surface_from_handle(context, handle)
+                .map(Surface::Default),
+            (&mut Device::Alternate(ref mut device), Context::Alternate(context)) => device
+                .create_surface_from_handle(context, handle)
+                .map(Surface::Alternate),
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+}
@@ -6,10 +6,11 @@ use super::device::Device;
 use super::error::ToWindowingApiError;
 use super::ffi::{CGLReleaseContext, CGLRetainContext};
 use super::surface::Surface;
-use crate::context::{ContextID, CREATE_CONTEXT_MUTEX};
+use crate::context::{ContextGL, ContextID, CREATE_CONTEXT_MUTEX};
 use crate::gl_utils;
 use crate::surface::Framebuffer;
-use crate::{ContextAttributeFlags, ContextAttributes, Error, GLVersion, Gl, SurfaceInfo};
+use crate::SurfaceInfo;
+use crate::{ContextAttributeFlags, ContextAttributes, ContextPriority, Error, GLVersion, Gl};
 
 use cgl::{kCGLPFAAllowOfflineRenderers, kCGLPFAAlphaSize, kCGLPFADepthSize};
 use cgl::{kCGLPFAOpenGLProfile, kCGLPFAStencilSize};
@@ -151,6 +152,13 @@ impl Device {
             return Err(Error::UnsupportedGLProfile);
         };
 
+        if attributes.flags.contains(ContextAttributeFlags::NO_ERROR) {
+            warn!("CGL can't create contexts without error checking; ignoring `NO_ERROR`");
+        }
+        if attributes.priority != ContextPriority::Medium {
+            warn!("CGL can't prioritize contexts; ignoring context priority");
+        }
+
         let profile = if attributes.version.major >= 4 {
             kCGLOGLPVersion_GL4_Core
         } else if attributes.version.major == 3 {
@@ -216,6 +224,19 @@ impl Device {
         }
     }
 
+    /// Creates a context descriptor for the first of `candidates` that this device can actually
+    /// create a context with.
+    ///
+    /// Candidates are tried in order, so list them from most to least preferred. Whatever context
+    /// was current before the call remains current.
+    pub fn negotiate_context_descriptor(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor, Error> {
+        let _guard = CurrentContextGuard::new();
+        crate::context::negotiate_context_descriptor(self, candidates)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -330,6 +351,21 @@ impl Device {
         }
     }
 
+    /// Makes the context the current OpenGL context for this thread until the returned guard is
+    /// dropped.
+    ///
+    /// When the guard is dropped, whichever context was current before this call is made current
+    /// again, even if it wasn't created by `surfman`. If no context was current, the context is
+    /// released.
+    pub fn make_context_current_scoped(
+        &self,
+        context: &Context,
+    ) -> Result<CurrentContextGuard, Error> {
+        let guard = CurrentContextGuard::new();
+        self.make_context_current(context)?;
+        Ok(guard)
+    }
+
     pub(crate) fn temporarily_make_context_current(
         &self,
         context: &Context,
@@ -404,6 +440,30 @@ impl Device {
         }
     }
 
+    /// Attaches a surface to a context for reading, separately from the surface it renders to.
+    ///
+    /// This backend can't read from a separate surface, so this always returns an
+    /// `UnsupportedOnThisPlatform` error, alongside the surface.
+    #[inline]
+    pub fn bind_read_surface_to_context(
+        &self,
+        _: &mut Context,
+        surface: Surface,
+    ) -> Result<(), (Error, Surface)> {
+        Err((Error::UnsupportedOnThisPlatform, surface))
+    }
+
+    /// Removes and returns any read surface attached to this context.
+    ///
+    /// Read surfaces can't be bound on this backend, so this always returns `None`.
+    #[inline]
+    pub fn unbind_read_surface_from_context(
+        &self,
+        _: &mut Context,
+    ) -> Result<Option<Surface>, Error> {
+        Ok(None)
+    }
+
     /// Returns the attributes that the context descriptor was created with.
     pub fn context_descriptor_attributes(
         &self,
@@ -433,6 +493,7 @@ impl Device {
             return ContextAttributes {
                 flags: attribute_flags,
                 version,
+                priority: ContextPriority::Medium,
             };
         }
 
@@ -464,6 +525,20 @@ impl Device {
         get_proc_address(symbol_name)
     }
 
+    /// Makes the context current and returns the OpenGL function table that surfman loaded for
+    /// it.
+    ///
+    /// This lets applications share surfman's function pointers instead of loading their own with
+    /// `get_proc_address`. The context stays current until the returned value is dropped, at which
+    /// point whichever context was current before is made current again.
+    pub fn context_gl<'a>(
+        &self,
+        context: &'a Context,
+    ) -> Result<ContextGL<'a, CurrentContextGuard>, Error> {
+        let guard = self.make_context_current_scoped(context)?;
+        Ok(ContextGL::new(&context.gl, guard))
+    }
+
     /// Returns various information about the surface attached to a context.
     ///
     /// This includes, most notably, the OpenGL framebuffer object needed to render to the surface.
@@ -475,6 +550,14 @@ impl Device {
         }
     }
 
+    /// Returns various information about the read surface attached to a context, if any.
+    ///
+    /// Read surfaces can't be bound on this backend, so this always returns `None`.
+    #[inline]
+    pub fn context_read_surface_info(&self, _: &Context) -> Result<Option<SurfaceInfo>, Error> {
+        Ok(None)
+    }
+
     /// Returns a unique ID representing a context.
     ///
     /// This ID is unique to all currently-allocated contexts. If you destroy a context and create
@@ -501,8 +584,12 @@ fn get_proc_address(symbol_name: &str) -> *const c_void {
     })
 }
 
+/// Restores the previously-current CGL context when dropped.
+///
+/// This is returned by `Device::make_context_current_scoped()`. The previous state is captured
+/// straight from CGL, so contexts that were not created by `surfman` are restored as well.
 #[must_use]
-pub(crate) struct CurrentContextGuard {
+pub struct CurrentContextGuard {
     old_cgl_context: CGLContextObj,
 }
 
@@ -515,7 +602,7 @@ impl Drop for CurrentContextGuard {
 }
 
 impl CurrentContextGuard {
-    fn new() -> CurrentContextGuard {
+    pub(crate) fn new() -> CurrentContextGuard {
         unsafe {
             CurrentContextGuard {
                 old_cgl_context: CGLGetCurrentContext(),
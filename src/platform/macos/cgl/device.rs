@@ -3,8 +3,11 @@
 //! A handle to the device. (This is a no-op, because handles are implicit in Apple's Core OpenGL.)
 
 use super::connection::Connection;
+use super::context::CurrentContextGuard;
 use crate::platform::macos::system::device::{Adapter as SystemAdapter, Device as SystemDevice};
-use crate::GLApi;
+use crate::{DeviceCapabilities, Error, GLApi};
+
+use std::collections::HashSet;
 
 pub use crate::platform::macos::system::device::NativeDevice;
 
@@ -44,4 +47,15 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GL
     }
+
+    /// Returns the OpenGL versions, limits, surface formats, and extensions that this device
+    /// supports.
+    ///
+    /// This creates and destroys temporary contexts, so callers should cache the result.
+    ///
+    /// CGL has no extension string of its own, so `platform_extensions` is always empty.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        let _guard = CurrentContextGuard::new();
+        DeviceCapabilities::probe(self, HashSet::new(), true, true)
+    }
 }
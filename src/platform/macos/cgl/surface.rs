@@ -8,7 +8,8 @@ use crate::context::ContextID;
 use crate::gl_utils;
 use crate::platform::macos::system::surface::Surface as SystemSurface;
 use crate::renderbuffers::Renderbuffers;
-use crate::{gl, Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType, WindowingApiError};
+use crate::WindowingApiError;
+use crate::{gl, ContextAttributeFlags, Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType};
 use glow::Context as Gl;
 
 use core_foundation::base::TCFType;
@@ -86,6 +87,47 @@ impl Device {
         context: &Context,
         access: SurfaceAccess,
         surface_type: SurfaceType<NativeWidget>,
+    ) -> Result<Surface, Error> {
+        self.create_surface_with_flags(context, access, surface_type, None)
+    }
+
+    /// Like `create_surface()`, but the format of a generic surface is chosen by the `ALPHA`,
+    /// `DEPTH`, and `STENCIL` flags in `surface_flags` instead of by the context's descriptor.
+    ///
+    /// Widget surfaces always use the format of the context's descriptor.
+    pub fn create_surface_with_attributes(
+        &mut self,
+        context: &Context,
+        access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget>,
+        surface_flags: ContextAttributeFlags,
+    ) -> Result<Surface, Error> {
+        let surface_flags = match surface_type {
+            SurfaceType::Generic { .. } => Some(surface_flags),
+            SurfaceType::Widget { .. } => None,
+        };
+        self.create_surface_with_flags(context, access, surface_type, surface_flags)
+    }
+
+    /// Creates a generic surface that renders into an existing OpenGL texture.
+    ///
+    /// Surfaces on macOS are backed by `IOSurface`s, so OpenGL textures cannot be wrapped; this
+    /// always returns `UnsupportedOnThisPlatform`.
+    pub fn create_surface_from_gl_texture(
+        &mut self,
+        _: &Context,
+        _: &Size2D<i32>,
+        _: Texture,
+    ) -> Result<Surface, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
+    fn create_surface_with_flags(
+        &mut self,
+        context: &Context,
+        access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget>,
+        surface_flags: Option<ContextAttributeFlags>,
     ) -> Result<Surface, Error> {
         let mut system_surface = self.0.create_surface(access, surface_type)?;
         self.0.set_surface_flipped(&mut system_surface, true);
@@ -109,7 +151,10 @@ impl Device {
             );
 
             let context_descriptor = self.context_descriptor(context);
-            let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+            let mut context_attributes = self.context_descriptor_attributes(&context_descriptor);
+            if let Some(surface_flags) = surface_flags {
+                context_attributes = context_attributes.with_surface_flags(surface_flags);
+            }
 
             let mut renderbuffers =
                 Renderbuffers::new(gl, &system_surface.size, &context_attributes);
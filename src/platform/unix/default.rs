@@ -23,6 +23,7 @@ pub mod connection {
 pub mod context {
     use crate::platform::generic::multi::context::Context as MultiContext;
     use crate::platform::generic::multi::context::ContextDescriptor as MultiContextDescriptor;
+    use crate::platform::generic::multi::context::CurrentContextGuard as MultiCurrentContextGuard;
     use crate::platform::generic::multi::context::NativeContext as MultiNativeContext;
     use crate::platform::generic::multi::device::Device as MultiDevice;
     use crate::platform::unix::generic::device::Device as SWDevice;
@@ -54,6 +55,9 @@ pub mod context {
     /// These are local to a device.
     pub type ContextDescriptor = MultiContextDescriptor<HWDevice, SWDevice>;
 
+    /// Restores the previously-current context when dropped.
+    pub type CurrentContextGuard = MultiCurrentContextGuard<HWDevice, SWDevice>;
+
     /// Either a Wayland or an X11 native context
     pub type NativeContext = MultiNativeContext<HWDevice, SWDevice>;
 }
@@ -95,6 +99,8 @@ pub mod surface {
     use crate::platform::unix::x11::device::Device as X11Device;
     type HWDevice = MultiDevice<WaylandDevice, X11Device>;
 
+    pub use crate::platform::generic::egl::surface_handle::{DmaBufPlane, SurfaceHandle};
+
     /// A wrapper for a Wayland surface or an X11 `Window`, as appropriate.
     pub type NativeWidget = MultiNativeWidget<HWDevice, SWDevice>;
 
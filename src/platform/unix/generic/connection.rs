@@ -7,19 +7,25 @@ use super::surface::NativeWidget;
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDisplay};
 use crate::info::GLApi;
-use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::device::{initialize_owned_egl_display, load_egl_library};
+use crate::platform::generic::egl::device::{terminate_owned_egl_display, EGL_FUNCTIONS};
 use crate::platform::generic::egl::ffi::EGL_PLATFORM_SURFACELESS_MESA;
 use crate::Error;
 
 use euclid::default::Size2D;
 
+use std::env;
 use std::os::raw::c_void;
 use std::sync::Arc;
 
+/// The environment variable that makes connections create OpenGL ES contexts by default.
+const FORCE_GLES_ENV_VAR: &str = "SURFMAN_FORCE_GLES";
+
 /// A no-op connection.
 #[derive(Clone)]
 pub struct Connection {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
+    pub(crate) gl_api: GLApi,
 }
 
 /// Native connections.
@@ -34,10 +40,29 @@ pub struct NativeConnectionWrapper {
 unsafe impl Send for NativeConnectionWrapper {}
 unsafe impl Sync for NativeConnectionWrapper {}
 
+impl Drop for NativeConnectionWrapper {
+    #[inline]
+    fn drop(&mut self) {
+        // The display is always initialized by `Connection::new()`, so we own it.
+        unsafe { terminate_owned_egl_display(self.egl_display) }
+    }
+}
+
+// Returns the OpenGL API flavor that new connections use, which is OpenGL unless overridden by
+// `SURFMAN_FORCE_GLES`.
+pub(crate) fn default_gl_api() -> GLApi {
+    if env::var_os(FORCE_GLES_ENV_VAR).is_some() {
+        GLApi::GLES
+    } else {
+        GLApi::GL
+    }
+}
+
 impl Connection {
     /// Opens a surfaceless Mesa display.
     #[inline]
     pub fn new() -> Result<Connection, Error> {
+        load_egl_library()?;
         unsafe {
             EGL_FUNCTIONS.with(|egl| {
                 let egl_display_attributes = [egl::NONE as EGLAttrib];
@@ -50,10 +75,7 @@ impl Connection {
                     return Err(Error::ConnectionFailed);
                 }
 
-                let (mut egl_major_version, mut egl_minor_version) = (0, 0);
-                let ok =
-                    egl.Initialize(egl_display, &mut egl_major_version, &mut egl_minor_version);
-                if ok == egl::FALSE {
+                if !initialize_owned_egl_display(egl_display) {
                     return Err(Error::ConnectionFailed);
                 }
 
@@ -70,8 +92,10 @@ impl Connection {
     pub unsafe fn from_native_connection(
         native_connection: NativeConnection,
     ) -> Result<Connection, Error> {
+        load_egl_library()?;
         Ok(Connection {
             native_connection: native_connection.0,
+            gl_api: default_gl_api(),
         })
     }
 
@@ -84,7 +108,19 @@ impl Connection {
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Returns this connection with the OpenGL API flavor that its devices create contexts with
+    /// set to `gl_api`.
+    ///
+    /// This selects between `EGL_OPENGL_API` and `EGL_OPENGL_ES_API` on the same display. The
+    /// default is OpenGL, unless the `SURFMAN_FORCE_GLES` environment variable is set. Devices
+    /// that have already been created from this connection are unaffected.
+    #[inline]
+    pub fn with_gl_api(mut self, gl_api: GLApi) -> Connection {
+        self.gl_api = gl_api;
+        self
     }
 
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
@@ -3,7 +3,9 @@
 //! A wrapper around surfaceless Mesa `EGLDisplay`s.
 
 use super::connection::{Connection, NativeConnectionWrapper};
-use crate::{Error, GLApi};
+use super::context::CurrentContextGuard;
+use crate::platform::generic::egl::device::egl_extensions;
+use crate::{DeviceCapabilities, Error, GLApi};
 
 use std::env;
 use std::sync::Arc;
@@ -68,6 +70,7 @@ impl Adapter {
 pub struct Device {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
     pub(crate) adapter: Adapter,
+    pub(crate) gl_api: GLApi,
 }
 
 /// Wraps an adapter.
@@ -85,6 +88,7 @@ impl Device {
         Ok(Device {
             native_connection: connection.native_connection.clone(),
             adapter: (*adapter).clone(),
+            gl_api: connection.gl_api,
         })
     }
 
@@ -104,6 +108,7 @@ impl Device {
     pub fn connection(&self) -> Connection {
         Connection {
             native_connection: self.native_connection.clone(),
+            gl_api: self.gl_api,
         }
     }
 
@@ -116,6 +121,18 @@ impl Device {
     /// Returns the OpenGL API flavor that this device supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Returns the OpenGL versions, limits, surface formats, and extensions that this device
+    /// supports.
+    ///
+    /// This creates and destroys temporary contexts, so callers should cache the result.
+    ///
+    /// This backend supports neither widget surfaces nor CPU access to surfaces.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        let _guard = CurrentContextGuard::with_release_display(self.native_connection.egl_display);
+        let extensions = unsafe { egl_extensions(self.native_connection.egl_display) };
+        DeviceCapabilities::probe(self, extensions, false, false)
     }
 }
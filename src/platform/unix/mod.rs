@@ -16,3 +16,6 @@ pub mod generic;
 pub mod wayland;
 #[cfg(x11_platform)]
 pub mod x11;
+
+#[cfg(test)]
+pub(crate) mod vulkan_test_support;
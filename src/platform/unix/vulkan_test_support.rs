@@ -0,0 +1,533 @@
+// surfman/surfman/src/platform/unix/vulkan_test_support.rs
+//
+//! Just enough of Vulkan, loaded at runtime, to allocate memory and semaphores that can be
+//! exported to OpenGL. This lets the external object tests run against a real exporter, such as
+//! lavapipe alongside llvmpipe, wherever a Vulkan driver for the same device is installed.
+//!
+//! This lives outside the shared tests so that it is compiled once rather than once per backend.
+
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::mem;
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::ptr;
+
+type VkHandle = u64;
+type VkResult = i32;
+type PFNVoidFunction = Option<unsafe extern "C" fn()>;
+type PFNGetProcAddr = unsafe extern "C" fn(*mut c_void, *const c_char) -> PFNVoidFunction;
+
+const VK_SUCCESS: VkResult = 0;
+const VK_API_VERSION_1_1: u32 = 1 << 22 | 1 << 12;
+const VK_STRUCTURE_TYPE_APPLICATION_INFO: u32 = 0;
+const VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO: u32 = 1;
+const VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO: u32 = 2;
+const VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO: u32 = 3;
+const VK_STRUCTURE_TYPE_MEMORY_ALLOCATE_INFO: u32 = 5;
+const VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO: u32 = 9;
+const VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO: u32 = 14;
+const VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_PROPERTIES_2: u32 = 1000059001;
+const VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_ID_PROPERTIES: u32 = 1000071004;
+const VK_STRUCTURE_TYPE_EXTERNAL_MEMORY_IMAGE_CREATE_INFO: u32 = 1000072001;
+const VK_STRUCTURE_TYPE_EXPORT_MEMORY_ALLOCATE_INFO: u32 = 1000072002;
+const VK_STRUCTURE_TYPE_MEMORY_GET_FD_INFO_KHR: u32 = 1000074002;
+const VK_STRUCTURE_TYPE_EXPORT_SEMAPHORE_CREATE_INFO: u32 = 1000077000;
+const VK_STRUCTURE_TYPE_SEMAPHORE_GET_FD_INFO_KHR: u32 = 1000079001;
+const VK_STRUCTURE_TYPE_MEMORY_DEDICATED_ALLOCATE_INFO: u32 = 1000127001;
+const VK_EXTERNAL_HANDLE_TYPE_OPAQUE_FD_BIT: u32 = 0x1;
+const VK_IMAGE_TYPE_2D: u32 = 1;
+const VK_FORMAT_R8G8B8A8_UNORM: u32 = 37;
+const VK_SAMPLE_COUNT_1_BIT: u32 = 1;
+const VK_IMAGE_TILING_OPTIMAL: u32 = 0;
+// `TRANSFER_SRC | TRANSFER_DST | SAMPLED | COLOR_ATTACHMENT`.
+const VK_IMAGE_USAGE_FLAGS: u32 = 0x1 | 0x2 | 0x4 | 0x10;
+
+#[repr(C)]
+struct VkApplicationInfo {
+    s_type: u32,
+    p_next: *const c_void,
+    p_application_name: *const c_char,
+    application_version: u32,
+    p_engine_name: *const c_char,
+    engine_version: u32,
+    api_version: u32,
+}
+
+#[repr(C)]
+struct VkInstanceCreateInfo {
+    s_type: u32,
+    p_next: *const c_void,
+    flags: u32,
+    p_application_info: *const VkApplicationInfo,
+    enabled_layer_count: u32,
+    pp_enabled_layer_names: *const *const c_char,
+    enabled_extension_count: u32,
+    pp_enabled_extension_names: *const *const c_char,
+}
+
+#[repr(C)]
+struct VkPhysicalDeviceIDProperties {
+    s_type: u32,
+    p_next: *mut c_void,
+    device_uuid: [u8; 16],
+    driver_uuid: [u8; 16],
+    device_luid: [u8; 8],
+    device_node_mask: u32,
+    device_luid_valid: u32,
+}
+
+#[repr(C)]
+struct VkPhysicalDeviceProperties2 {
+    s_type: u32,
+    p_next: *mut c_void,
+    // `VkPhysicalDeviceProperties`, which is 824 bytes; only the chained ID properties are
+    // read.
+    properties: [u64; 128],
+}
+
+#[repr(C)]
+struct VkDeviceQueueCreateInfo {
+    s_type: u32,
+    p_next: *const c_void,
+    flags: u32,
+    queue_family_index: u32,
+    queue_count: u32,
+    p_queue_priorities: *const f32,
+}
+
+#[repr(C)]
+struct VkDeviceCreateInfo {
+    s_type: u32,
+    p_next: *const c_void,
+    flags: u32,
+    queue_create_info_count: u32,
+    p_queue_create_infos: *const VkDeviceQueueCreateInfo,
+    enabled_layer_count: u32,
+    pp_enabled_layer_names: *const *const c_char,
+    enabled_extension_count: u32,
+    pp_enabled_extension_names: *const *const c_char,
+    p_enabled_features: *const c_void,
+}
+
+#[repr(C)]
+struct VkExternalCreateInfo {
+    s_type: u32,
+    p_next: *const c_void,
+    handle_types: u32,
+}
+
+#[repr(C)]
+struct VkImageCreateInfo {
+    s_type: u32,
+    p_next: *const c_void,
+    flags: u32,
+    image_type: u32,
+    format: u32,
+    extent: [u32; 3],
+    mip_levels: u32,
+    array_layers: u32,
+    samples: u32,
+    tiling: u32,
+    usage: u32,
+    sharing_mode: u32,
+    queue_family_index_count: u32,
+    p_queue_family_indices: *const u32,
+    initial_layout: u32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct VkMemoryRequirements {
+    size: u64,
+    alignment: u64,
+    memory_type_bits: u32,
+}
+
+#[repr(C)]
+struct VkMemoryDedicatedAllocateInfo {
+    s_type: u32,
+    p_next: *const c_void,
+    image: VkHandle,
+    buffer: VkHandle,
+}
+
+#[repr(C)]
+struct VkMemoryAllocateInfo {
+    s_type: u32,
+    p_next: *const c_void,
+    allocation_size: u64,
+    memory_type_index: u32,
+}
+
+#[repr(C)]
+struct VkSemaphoreCreateInfo {
+    s_type: u32,
+    p_next: *const c_void,
+    flags: u32,
+}
+
+// `VkMemoryGetFdInfoKHR` and `VkSemaphoreGetFdInfoKHR` share this layout.
+#[repr(C)]
+struct VkGetFdInfo {
+    s_type: u32,
+    p_next: *const c_void,
+    object: VkHandle,
+    handle_type: u32,
+}
+
+#[allow(non_snake_case)]
+struct Functions {
+    DestroyInstance: unsafe extern "C" fn(*mut c_void, *const c_void),
+    EnumeratePhysicalDevices:
+        unsafe extern "C" fn(*mut c_void, *mut u32, *mut *mut c_void) -> VkResult,
+    GetPhysicalDeviceProperties2:
+        unsafe extern "C" fn(*mut c_void, *mut VkPhysicalDeviceProperties2),
+    CreateDevice: unsafe extern "C" fn(
+        *mut c_void,
+        *const VkDeviceCreateInfo,
+        *const c_void,
+        *mut *mut c_void,
+    ) -> VkResult,
+    DestroyDevice: unsafe extern "C" fn(*mut c_void, *const c_void),
+    CreateImage: unsafe extern "C" fn(
+        *mut c_void,
+        *const VkImageCreateInfo,
+        *const c_void,
+        *mut VkHandle,
+    ) -> VkResult,
+    DestroyImage: unsafe extern "C" fn(*mut c_void, VkHandle, *const c_void),
+    GetImageMemoryRequirements:
+        unsafe extern "C" fn(*mut c_void, VkHandle, *mut VkMemoryRequirements),
+    AllocateMemory: unsafe extern "C" fn(
+        *mut c_void,
+        *const VkMemoryAllocateInfo,
+        *const c_void,
+        *mut VkHandle,
+    ) -> VkResult,
+    FreeMemory: unsafe extern "C" fn(*mut c_void, VkHandle, *const c_void),
+    BindImageMemory: unsafe extern "C" fn(*mut c_void, VkHandle, VkHandle, u64) -> VkResult,
+    GetMemoryFdKHR: unsafe extern "C" fn(*mut c_void, *const VkGetFdInfo, *mut c_int) -> VkResult,
+    CreateSemaphore: unsafe extern "C" fn(
+        *mut c_void,
+        *const VkSemaphoreCreateInfo,
+        *const c_void,
+        *mut VkHandle,
+    ) -> VkResult,
+    DestroySemaphore: unsafe extern "C" fn(*mut c_void, VkHandle, *const c_void),
+    GetSemaphoreFdKHR:
+        unsafe extern "C" fn(*mut c_void, *const VkGetFdInfo, *mut c_int) -> VkResult,
+}
+
+/// A Vulkan device on the same physical device and driver as an OpenGL context.
+pub(crate) struct VulkanDevice {
+    functions: Functions,
+    instance: *mut c_void,
+    device: *mut c_void,
+}
+
+/// An image whose memory has been exported as an opaque file descriptor.
+pub(crate) struct ExportedImage {
+    image: VkHandle,
+    memory: VkHandle,
+    pub(crate) size: u64,
+}
+
+impl VulkanDevice {
+    /// Opens the Vulkan device whose UUIDs match the given OpenGL device and driver UUIDs, if
+    /// there is one and it can export memory and semaphores.
+    pub(crate) fn open(device_uuid: &[u8; 16], driver_uuid: &[u8; 16]) -> Option<VulkanDevice> {
+        unsafe {
+            let library = libc::dlopen(c"libvulkan.so.1".as_ptr(), libc::RTLD_NOW);
+            if library.is_null() {
+                return None;
+            }
+            let get_instance_proc_addr = libc::dlsym(library, c"vkGetInstanceProcAddr".as_ptr());
+            if get_instance_proc_addr.is_null() {
+                return None;
+            }
+            let get_instance_proc_addr: PFNGetProcAddr = mem::transmute(get_instance_proc_addr);
+
+            let create_instance: unsafe extern "C" fn(
+                *const VkInstanceCreateInfo,
+                *const c_void,
+                *mut *mut c_void,
+            ) -> VkResult = mem::transmute(get_instance_proc_addr(
+                ptr::null_mut(),
+                c"vkCreateInstance".as_ptr(),
+            )?);
+            let application_info = VkApplicationInfo {
+                s_type: VK_STRUCTURE_TYPE_APPLICATION_INFO,
+                p_next: ptr::null(),
+                p_application_name: c"surfman-test".as_ptr(),
+                application_version: 0,
+                p_engine_name: ptr::null(),
+                engine_version: 0,
+                api_version: VK_API_VERSION_1_1,
+            };
+            let instance_create_info = VkInstanceCreateInfo {
+                s_type: VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: 0,
+                p_application_info: &application_info,
+                enabled_layer_count: 0,
+                pp_enabled_layer_names: ptr::null(),
+                enabled_extension_count: 0,
+                pp_enabled_extension_names: ptr::null(),
+            };
+            let mut instance = ptr::null_mut();
+            if create_instance(&instance_create_info, ptr::null(), &mut instance) != VK_SUCCESS {
+                return None;
+            }
+
+            let lookup = |name: &CStr| get_instance_proc_addr(instance, name.as_ptr());
+            let functions = match load_functions(lookup) {
+                Some(functions) => functions,
+                None => return None,
+            };
+
+            let mut physical_device_count = 0;
+            (functions.EnumeratePhysicalDevices)(
+                instance,
+                &mut physical_device_count,
+                ptr::null_mut(),
+            );
+            let mut physical_devices = vec![ptr::null_mut(); physical_device_count as usize];
+            (functions.EnumeratePhysicalDevices)(
+                instance,
+                &mut physical_device_count,
+                physical_devices.as_mut_ptr(),
+            );
+            physical_devices.truncate(physical_device_count as usize);
+
+            let physical_device = physical_devices.into_iter().find(|&physical_device| {
+                let mut id_properties = VkPhysicalDeviceIDProperties {
+                    s_type: VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_ID_PROPERTIES,
+                    p_next: ptr::null_mut(),
+                    device_uuid: [0; 16],
+                    driver_uuid: [0; 16],
+                    device_luid: [0; 8],
+                    device_node_mask: 0,
+                    device_luid_valid: 0,
+                };
+                let mut properties = VkPhysicalDeviceProperties2 {
+                    s_type: VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_PROPERTIES_2,
+                    p_next: &mut id_properties as *mut _ as *mut c_void,
+                    properties: [0; 128],
+                };
+                (functions.GetPhysicalDeviceProperties2)(physical_device, &mut properties);
+                id_properties.device_uuid == *device_uuid
+                    && id_properties.driver_uuid == *driver_uuid
+            });
+            let physical_device = match physical_device {
+                Some(physical_device) => physical_device,
+                None => {
+                    (functions.DestroyInstance)(instance, ptr::null());
+                    return None;
+                }
+            };
+
+            let queue_priority = 1.0;
+            let queue_create_info = VkDeviceQueueCreateInfo {
+                s_type: VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: 0,
+                queue_family_index: 0,
+                queue_count: 1,
+                p_queue_priorities: &queue_priority,
+            };
+            let extensions = [
+                c"VK_KHR_external_memory_fd".as_ptr(),
+                c"VK_KHR_external_semaphore_fd".as_ptr(),
+            ];
+            let device_create_info = VkDeviceCreateInfo {
+                s_type: VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: 0,
+                queue_create_info_count: 1,
+                p_queue_create_infos: &queue_create_info,
+                enabled_layer_count: 0,
+                pp_enabled_layer_names: ptr::null(),
+                enabled_extension_count: extensions.len() as u32,
+                pp_enabled_extension_names: extensions.as_ptr(),
+                p_enabled_features: ptr::null(),
+            };
+            let mut device = ptr::null_mut();
+            if (functions.CreateDevice)(
+                physical_device,
+                &device_create_info,
+                ptr::null(),
+                &mut device,
+            ) != VK_SUCCESS
+            {
+                (functions.DestroyInstance)(instance, ptr::null());
+                return None;
+            }
+
+            Some(VulkanDevice {
+                functions,
+                instance,
+                device,
+            })
+        }
+    }
+
+    /// Allocates an `R8G8B8A8_UNORM` image with dedicated memory and exports the memory.
+    pub(crate) fn export_image(&self, width: u32, height: u32) -> (ExportedImage, OwnedFd) {
+        unsafe {
+            let external_info = VkExternalCreateInfo {
+                s_type: VK_STRUCTURE_TYPE_EXTERNAL_MEMORY_IMAGE_CREATE_INFO,
+                p_next: ptr::null(),
+                handle_types: VK_EXTERNAL_HANDLE_TYPE_OPAQUE_FD_BIT,
+            };
+            let image_create_info = VkImageCreateInfo {
+                s_type: VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO,
+                p_next: &external_info as *const _ as *const c_void,
+                flags: 0,
+                image_type: VK_IMAGE_TYPE_2D,
+                format: VK_FORMAT_R8G8B8A8_UNORM,
+                extent: [width, height, 1],
+                mip_levels: 1,
+                array_layers: 1,
+                samples: VK_SAMPLE_COUNT_1_BIT,
+                tiling: VK_IMAGE_TILING_OPTIMAL,
+                usage: VK_IMAGE_USAGE_FLAGS,
+                sharing_mode: 0,
+                queue_family_index_count: 0,
+                p_queue_family_indices: ptr::null(),
+                initial_layout: 0,
+            };
+            let mut image = 0;
+            let result = (self.functions.CreateImage)(
+                self.device,
+                &image_create_info,
+                ptr::null(),
+                &mut image,
+            );
+            assert_eq!(result, VK_SUCCESS);
+
+            let mut requirements = VkMemoryRequirements::default();
+            (self.functions.GetImageMemoryRequirements)(self.device, image, &mut requirements);
+            let dedicated_info = VkMemoryDedicatedAllocateInfo {
+                s_type: VK_STRUCTURE_TYPE_MEMORY_DEDICATED_ALLOCATE_INFO,
+                p_next: ptr::null(),
+                image,
+                buffer: 0,
+            };
+            let export_info = VkExternalCreateInfo {
+                s_type: VK_STRUCTURE_TYPE_EXPORT_MEMORY_ALLOCATE_INFO,
+                p_next: &dedicated_info as *const _ as *const c_void,
+                handle_types: VK_EXTERNAL_HANDLE_TYPE_OPAQUE_FD_BIT,
+            };
+            let allocate_info = VkMemoryAllocateInfo {
+                s_type: VK_STRUCTURE_TYPE_MEMORY_ALLOCATE_INFO,
+                p_next: &export_info as *const _ as *const c_void,
+                allocation_size: requirements.size,
+                memory_type_index: requirements.memory_type_bits.trailing_zeros(),
+            };
+            let mut memory = 0;
+            let result = (self.functions.AllocateMemory)(
+                self.device,
+                &allocate_info,
+                ptr::null(),
+                &mut memory,
+            );
+            assert_eq!(result, VK_SUCCESS);
+            let result = (self.functions.BindImageMemory)(self.device, image, memory, 0);
+            assert_eq!(result, VK_SUCCESS);
+
+            let get_fd_info = VkGetFdInfo {
+                s_type: VK_STRUCTURE_TYPE_MEMORY_GET_FD_INFO_KHR,
+                p_next: ptr::null(),
+                object: memory,
+                handle_type: VK_EXTERNAL_HANDLE_TYPE_OPAQUE_FD_BIT,
+            };
+            let mut fd = -1;
+            let result = (self.functions.GetMemoryFdKHR)(self.device, &get_fd_info, &mut fd);
+            assert_eq!(result, VK_SUCCESS);
+
+            let image = ExportedImage {
+                image,
+                memory,
+                size: requirements.size,
+            };
+            (image, OwnedFd::from_raw_fd(fd))
+        }
+    }
+
+    /// Creates a binary semaphore and exports it. The Vulkan semaphore itself is destroyed
+    /// right away; the exported payload lives on in the file descriptor.
+    pub(crate) fn export_semaphore(&self) -> OwnedFd {
+        unsafe {
+            let export_info = VkExternalCreateInfo {
+                s_type: VK_STRUCTURE_TYPE_EXPORT_SEMAPHORE_CREATE_INFO,
+                p_next: ptr::null(),
+                handle_types: VK_EXTERNAL_HANDLE_TYPE_OPAQUE_FD_BIT,
+            };
+            let create_info = VkSemaphoreCreateInfo {
+                s_type: VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+                p_next: &export_info as *const _ as *const c_void,
+                flags: 0,
+            };
+            let mut semaphore = 0;
+            let result = (self.functions.CreateSemaphore)(
+                self.device,
+                &create_info,
+                ptr::null(),
+                &mut semaphore,
+            );
+            assert_eq!(result, VK_SUCCESS);
+
+            let get_fd_info = VkGetFdInfo {
+                s_type: VK_STRUCTURE_TYPE_SEMAPHORE_GET_FD_INFO_KHR,
+                p_next: ptr::null(),
+                object: semaphore,
+                handle_type: VK_EXTERNAL_HANDLE_TYPE_OPAQUE_FD_BIT,
+            };
+            let mut fd = -1;
+            let result = (self.functions.GetSemaphoreFdKHR)(self.device, &get_fd_info, &mut fd);
+            assert_eq!(result, VK_SUCCESS);
+            (self.functions.DestroySemaphore)(self.device, semaphore, ptr::null());
+            OwnedFd::from_raw_fd(fd)
+        }
+    }
+
+    /// Frees the Vulkan side of an exported image. OpenGL keeps the memory alive for as long
+    /// as it has imported it.
+    pub(crate) fn destroy_image(&self, image: ExportedImage) {
+        unsafe {
+            (self.functions.DestroyImage)(self.device, image.image, ptr::null());
+            (self.functions.FreeMemory)(self.device, image.memory, ptr::null());
+        }
+    }
+}
+
+impl Drop for VulkanDevice {
+    fn drop(&mut self) {
+        unsafe {
+            (self.functions.DestroyDevice)(self.device, ptr::null());
+            (self.functions.DestroyInstance)(self.instance, ptr::null());
+        }
+    }
+}
+
+unsafe fn load_functions<F>(lookup: F) -> Option<Functions>
+where
+    F: Fn(&CStr) -> PFNVoidFunction,
+{
+    Some(Functions {
+        DestroyInstance: mem::transmute(lookup(c"vkDestroyInstance")?),
+        EnumeratePhysicalDevices: mem::transmute(lookup(c"vkEnumeratePhysicalDevices")?),
+        GetPhysicalDeviceProperties2: mem::transmute(lookup(c"vkGetPhysicalDeviceProperties2")?),
+        CreateDevice: mem::transmute(lookup(c"vkCreateDevice")?),
+        DestroyDevice: mem::transmute(lookup(c"vkDestroyDevice")?),
+        CreateImage: mem::transmute(lookup(c"vkCreateImage")?),
+        DestroyImage: mem::transmute(lookup(c"vkDestroyImage")?),
+        GetImageMemoryRequirements: mem::transmute(lookup(c"vkGetImageMemoryRequirements")?),
+        AllocateMemory: mem::transmute(lookup(c"vkAllocateMemory")?),
+        FreeMemory: mem::transmute(lookup(c"vkFreeMemory")?),
+        BindImageMemory: mem::transmute(lookup(c"vkBindImageMemory")?),
+        GetMemoryFdKHR: mem::transmute(lookup(c"vkGetMemoryFdKHR")?),
+        CreateSemaphore: mem::transmute(lookup(c"vkCreateSemaphore")?),
+        DestroySemaphore: mem::transmute(lookup(c"vkDestroySemaphore")?),
+        GetSemaphoreFdKHR: mem::transmute(lookup(c"vkGetSemaphoreFdKHR")?),
+    })
+}
@@ -7,8 +7,10 @@ use super::surface::NativeWidget;
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDisplay};
 use crate::info::GLApi;
-use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::device::{initialize_owned_egl_display, load_egl_library};
+use crate::platform::generic::egl::device::{terminate_owned_egl_display, EGL_FUNCTIONS};
 use crate::platform::generic::egl::ffi::EGL_PLATFORM_WAYLAND_KHR;
+use crate::platform::unix::generic::connection::default_gl_api;
 use crate::Error;
 
 use euclid::default::Size2D;
@@ -21,10 +23,12 @@ use wayland_sys::client::{wl_display, wl_proxy, WAYLAND_CLIENT_HANDLE};
 #[derive(Clone)]
 pub struct Connection {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
+    pub(crate) gl_api: GLApi,
 }
 
 pub(crate) struct NativeConnectionWrapper {
     pub(crate) egl_display: EGLDisplay,
+    egl_display_is_owned: bool,
     wayland_display: Option<*mut wl_display>,
 }
 
@@ -47,11 +51,12 @@ impl Connection {
     ///
     /// The display is not retained, as there is no way to do this in the EGL API. Therefore, it is
     /// the caller's responsibility to ensure that the EGL display remains alive as long as the
-    /// connection is.
+    /// connection is. The EGL display is never terminated by `surfman`.
     pub unsafe fn from_native_connection(
         native_connection: NativeConnection,
     ) -> Result<Connection, Error> {
-        Connection::from_egl_display(native_connection.0, None)
+        load_egl_library()?;
+        Connection::from_egl_display(native_connection.0, false, None)
     }
 
     /// Returns the underlying native connection.
@@ -63,11 +68,19 @@ impl Connection {
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        if std::env::var("SURFMAN_FORCE_GLES").is_ok() {
-            GLApi::GLES
-        } else {
-            GLApi::GL
-        }
+        self.gl_api
+    }
+
+    /// Returns this connection with the OpenGL API flavor that its devices create contexts with
+    /// set to `gl_api`.
+    ///
+    /// This selects between `EGL_OPENGL_API` and `EGL_OPENGL_ES_API` on the same display. The
+    /// default is OpenGL, unless the `SURFMAN_FORCE_GLES` environment variable is set. Devices
+    /// that have already been created from this connection are unaffected.
+    #[inline]
+    pub fn with_gl_api(mut self, gl_api: GLApi) -> Connection {
+        self.gl_api = gl_api;
+        self
     }
 
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
@@ -120,6 +133,7 @@ impl Connection {
         wayland_display: *mut wl_display,
         is_owned: bool,
     ) -> Result<Connection, Error> {
+        load_egl_library()?;
         if wayland_display.is_null() {
             return Err(Error::ConnectionFailed);
         }
@@ -131,32 +145,42 @@ impl Connection {
                 wayland_display as *mut c_void,
                 display_attributes.as_ptr(),
             );
-            if egl_display == egl::NO_DISPLAY {
-                return Err(Error::DeviceOpenFailed);
+            let error = if egl_display == egl::NO_DISPLAY {
+                Some(Error::DeviceOpenFailed)
+            } else if !initialize_owned_egl_display(egl_display) {
+                Some(Error::ConnectionFailed)
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                // No connection owns the Wayland display yet, so it's ours to disconnect.
+                if is_owned {
+                    (WAYLAND_CLIENT_HANDLE.wl_display_disconnect)(wayland_display);
+                }
+                return Err(error);
             }
 
-            let (mut egl_major_version, mut egl_minor_version) = (0, 0);
-            let ok = egl.Initialize(egl_display, &mut egl_major_version, &mut egl_minor_version);
-            assert_ne!(ok, egl::FALSE);
-
             let owned_display = if is_owned {
                 Some(wayland_display)
             } else {
                 None
             };
-            Connection::from_egl_display(egl_display, owned_display)
+            Connection::from_egl_display(egl_display, true, owned_display)
         })
     }
 
     fn from_egl_display(
         egl_display: EGLDisplay,
+        egl_display_is_owned: bool,
         wayland_display: Option<*mut wl_display>,
     ) -> Result<Connection, Error> {
         Ok(Connection {
             native_connection: Arc::new(NativeConnectionWrapper {
                 egl_display,
+                egl_display_is_owned,
                 wayland_display,
             }),
+            gl_api: default_gl_api(),
         })
     }
 
@@ -250,6 +274,10 @@ impl Connection {
 impl Drop for NativeConnectionWrapper {
     fn drop(&mut self) {
         unsafe {
+            // The EGL display must go away before the Wayland display it was created from.
+            if self.egl_display_is_owned {
+                terminate_owned_egl_display(self.egl_display);
+            }
             if let Some(wayland_display) = self.wayland_display {
                 (WAYLAND_CLIENT_HANDLE.wl_display_disconnect)(wayland_display);
             }
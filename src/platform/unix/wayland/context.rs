@@ -4,15 +4,17 @@
 
 use super::device::Device;
 use super::surface::Surface;
-use crate::context::ContextID;
+use crate::context::{ContextGL, ContextID};
 use crate::egl;
 use crate::egl::types::EGLint;
-use crate::platform::generic::egl::context::{self, CurrentContextGuard, EGLBackedContext};
+use crate::platform::generic::egl::context::{self, EGLBackedContext};
 use crate::{ContextAttributes, Error, Gl, SurfaceInfo};
 
 use std::os::raw::c_void;
 
-pub use crate::platform::generic::egl::context::{ContextDescriptor, NativeContext};
+pub use crate::platform::generic::egl::context::{
+    ContextDescriptor, CurrentContextGuard, NativeContext,
+};
 
 /// Represents an OpenGL rendering context.
 ///
@@ -44,6 +46,8 @@ impl Device {
         // Set environment variables as appropriate.
         self.adapter.set_environment_variables();
 
+        let renderable_type = context::renderable_type(self.gl_api(), attributes)?;
+
         unsafe {
             ContextDescriptor::new(
                 self.native_connection.egl_display,
@@ -52,12 +56,25 @@ impl Device {
                     egl::SURFACE_TYPE as EGLint,
                     egl::WINDOW_BIT as EGLint,
                     egl::RENDERABLE_TYPE as EGLint,
-                    egl::OPENGL_BIT as EGLint,
+                    renderable_type,
                 ],
             )
         }
     }
 
+    /// Creates a context descriptor for the first of `candidates` that this device can actually
+    /// create a context with.
+    ///
+    /// Candidates are tried in order, so list them from most to least preferred. Whatever context
+    /// was current before the call remains current.
+    pub fn negotiate_context_descriptor(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor, Error> {
+        let _guard = CurrentContextGuard::with_release_display(self.native_connection.egl_display);
+        crate::context::negotiate_context_descriptor(self, candidates)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -106,6 +123,9 @@ impl Device {
         if let Ok(Some(mut surface)) = self.unbind_surface_from_context(context) {
             self.destroy_surface(context, &mut surface)?;
         }
+        if let Ok(Some(mut surface)) = self.unbind_read_surface_from_context(context) {
+            self.destroy_surface(context, &mut surface)?;
+        }
 
         unsafe {
             context.0.destroy(self.native_connection.egl_display);
@@ -136,7 +156,11 @@ impl Device {
     /// After calling this function, it is valid to use OpenGL rendering commands.
     #[inline]
     pub fn make_context_current(&self, context: &Context) -> Result<(), Error> {
-        unsafe { context.0.make_current(self.native_connection.egl_display) }
+        unsafe {
+            context.0.make_current(self.native_connection.egl_display)?;
+            context.0.bind_read_and_draw_framebuffers(&context.1);
+            Ok(())
+        }
     }
 
     /// Removes the current OpenGL context from this thread.
@@ -148,6 +172,21 @@ impl Device {
         unsafe { context::make_no_context_current(self.native_connection.egl_display) }
     }
 
+    /// Makes the context the current OpenGL context for this thread until the returned guard is
+    /// dropped.
+    ///
+    /// When the guard is dropped, whichever context and draw/read surfaces were current before
+    /// this call are made current again, even if they weren't created by `surfman`. If no context
+    /// was current, the context is released.
+    pub fn make_context_current_scoped(
+        &self,
+        context: &Context,
+    ) -> Result<CurrentContextGuard, Error> {
+        let guard = CurrentContextGuard::with_release_display(self.native_connection.egl_display);
+        self.make_context_current(context)?;
+        Ok(guard)
+    }
+
     #[inline]
     pub(crate) fn temporarily_make_context_current(
         &self,
@@ -179,6 +218,20 @@ impl Device {
         context::get_proc_address(symbol_name)
     }
 
+    /// Makes the context current and returns the OpenGL function table that surfman loaded for
+    /// it.
+    ///
+    /// This lets applications share surfman's function pointers instead of loading their own with
+    /// `get_proc_address`. The context stays current until the returned value is dropped, at which
+    /// point whichever context was current before is made current again.
+    pub fn context_gl<'a>(
+        &self,
+        context: &'a Context,
+    ) -> Result<ContextGL<'a, CurrentContextGuard>, Error> {
+        let guard = self.make_context_current_scoped(context)?;
+        Ok(ContextGL::new(&context.1, guard))
+    }
+
     /// Attaches a surface to a context for rendering.
     ///
     /// This function takes ownership of the surface. The surface must have been created with this
@@ -198,7 +251,7 @@ impl Device {
         unsafe {
             context
                 .0
-                .bind_surface(self.native_connection.egl_display, surface.0)
+                .bind_surface(&context.1, self.native_connection.egl_display, surface.0)
                 .map_err(|(err, surface)| (err, Surface(surface)))
         }
     }
@@ -219,6 +272,45 @@ impl Device {
         }
     }
 
+    /// Attaches a surface to a context for reading, separately from the surface it renders to.
+    ///
+    /// While a read surface is bound, the context reads from it and draws to the surface bound
+    /// with `bind_surface_to_context`. For generic surfaces, the framebuffer objects of the read
+    /// and draw surfaces are bound to `GL_READ_FRAMEBUFFER` and `GL_DRAW_FRAMEBUFFER` whenever
+    /// the context is made current or the read surface is bound. Unbinding the read surface binds
+    /// the draw surface's framebuffer object to both.
+    ///
+    /// The surface must have been created with this context, or an `IncompatibleSurface` error is
+    /// returned. If a read surface is already bound, a `SurfaceAlreadyBound` error is returned.
+    ///
+    /// If an error is returned, the surface is returned alongside it.
+    #[inline]
+    pub fn bind_read_surface_to_context(
+        &self,
+        context: &mut Context,
+        surface: Surface,
+    ) -> Result<(), (Error, Surface)> {
+        unsafe {
+            context
+                .0
+                .bind_read_surface(&context.1, self.native_connection.egl_display, surface.0)
+                .map_err(|(err, surface)| (err, Surface(surface)))
+        }
+    }
+
+    /// Removes and returns any read surface attached to this context.
+    pub fn unbind_read_surface_from_context(
+        &self,
+        context: &mut Context,
+    ) -> Result<Option<Surface>, Error> {
+        unsafe {
+            context
+                .0
+                .unbind_read_surface(&context.1, self.native_connection.egl_display)
+                .map(|maybe_surface| maybe_surface.map(Surface))
+        }
+    }
+
     /// Returns a unique ID representing a context.
     ///
     /// This ID is unique to all currently-allocated contexts. If you destroy a context and create
@@ -235,4 +327,15 @@ impl Device {
     pub fn context_surface_info(&self, context: &Context) -> Result<Option<SurfaceInfo>, Error> {
         context.0.surface_info()
     }
+
+    /// Returns various information about the read surface attached to a context, if any.
+    ///
+    /// This includes, most notably, the OpenGL framebuffer object needed to read from the surface.
+    #[inline]
+    pub fn context_read_surface_info(
+        &self,
+        context: &Context,
+    ) -> Result<Option<SurfaceInfo>, Error> {
+        context.0.read_surface_info()
+    }
 }
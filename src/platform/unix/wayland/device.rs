@@ -3,7 +3,9 @@
 //! A wrapper around Wayland `EGLDisplay`s.
 
 use super::connection::{Connection, NativeConnectionWrapper};
-use crate::{Error, GLApi};
+use super::context::CurrentContextGuard;
+use crate::platform::generic::egl::device::egl_extensions;
+use crate::{DeviceCapabilities, Error, GLApi};
 
 use std::sync::Arc;
 
@@ -15,6 +17,7 @@ pub use crate::platform::unix::generic::device::Adapter;
 pub struct Device {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
     pub(crate) adapter: Adapter,
+    pub(crate) gl_api: GLApi,
 }
 
 /// Wraps an adapter.
@@ -32,6 +35,7 @@ impl Device {
         Ok(Device {
             native_connection: connection.native_connection.clone(),
             adapter: (*adapter).clone(),
+            gl_api: connection.gl_api,
         })
     }
 
@@ -51,6 +55,7 @@ impl Device {
     pub fn connection(&self) -> Connection {
         Connection {
             native_connection: self.native_connection.clone(),
+            gl_api: self.gl_api,
         }
     }
 
@@ -63,10 +68,16 @@ impl Device {
     /// Returns the OpenGL API flavor that this device supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        if std::env::var("SURFMAN_FORCE_GLES").is_ok() {
-            GLApi::GLES
-        } else {
-            GLApi::GL
-        }
+        self.gl_api
+    }
+
+    /// Returns the OpenGL versions, limits, surface formats, and extensions that this device
+    /// supports.
+    ///
+    /// This creates and destroys temporary contexts, so callers should cache the result.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        let _guard = CurrentContextGuard::with_release_display(self.native_connection.egl_display);
+        let extensions = unsafe { egl_extensions(self.native_connection.egl_display) };
+        DeviceCapabilities::probe(self, extensions, true, false)
     }
 }
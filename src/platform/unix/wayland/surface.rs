@@ -7,7 +7,7 @@ use super::device::Device;
 use crate::gl;
 use crate::platform::generic::egl::context;
 use crate::platform::generic::egl::surface::{EGLBackedSurface, EGLSurfaceTexture};
-use crate::{Error, SurfaceAccess, SurfaceInfo, SurfaceType};
+use crate::{ContextAttributeFlags, Error, SurfaceAccess, SurfaceInfo, SurfaceType};
 
 use euclid::default::Size2D;
 use glow::Texture;
@@ -16,6 +16,12 @@ use std::os::raw::c_void;
 use wayland_sys::client::wl_proxy;
 use wayland_sys::egl::{wl_egl_window, WAYLAND_EGL_HANDLE};
 
+pub use crate::platform::generic::egl::external_objects::{ExternalMemory, ExternalSemaphore};
+pub use crate::platform::generic::egl::surface::NativeSurface;
+pub use crate::platform::generic::egl::surface_handle::{DmaBufPlane, SurfaceHandle};
+
+crate::platform::generic::egl::external_objects::implement_external_objects!();
+
 // FIXME(pcwalton): Is this right, or should it be `TEXTURE_EXTERNAL_OES`?
 const SURFACE_GL_TEXTURE_TARGET: u32 = gl::TEXTURE_2D;
 
@@ -71,7 +77,7 @@ impl Device {
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
         match surface_type {
-            SurfaceType::Generic { size } => self.create_generic_surface(context, &size),
+            SurfaceType::Generic { size } => self.create_generic_surface(context, &size, None),
             SurfaceType::Widget { native_widget } => unsafe {
                 self.create_window_surface(
                     context,
@@ -82,14 +88,37 @@ impl Device {
         }
     }
 
+    /// Like `create_surface()`, but the format of a generic surface is chosen by the `ALPHA`,
+    /// `DEPTH`, and `STENCIL` flags in `surface_flags` instead of by the context's descriptor.
+    ///
+    /// Widget surfaces always use the format of the context's descriptor.
+    pub fn create_surface_with_attributes(
+        &mut self,
+        context: &Context,
+        surface_access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget>,
+        surface_flags: ContextAttributeFlags,
+    ) -> Result<Surface, Error> {
+        match surface_type {
+            SurfaceType::Generic { size } => {
+                self.create_generic_surface(context, &size, Some(surface_flags))
+            }
+            surface_type => self.create_surface(context, surface_access, surface_type),
+        }
+    }
+
     fn create_generic_surface(
         &mut self,
         context: &Context,
         size: &Size2D<i32>,
+        surface_flags: Option<ContextAttributeFlags>,
     ) -> Result<Surface, Error> {
         let _guard = self.temporarily_make_context_current(context)?;
         let context_descriptor = self.context_descriptor(context);
-        let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+        let mut context_attributes = self.context_descriptor_attributes(&context_descriptor);
+        if let Some(surface_flags) = surface_flags {
+            context_attributes = context_attributes.with_surface_flags(surface_flags);
+        }
 
         Ok(Surface(EGLBackedSurface::new_generic(
             &context.1,
@@ -101,22 +130,149 @@ impl Device {
         )))
     }
 
+    /// Creates a generic surface that renders into an existing OpenGL texture.
+    ///
+    /// The texture must be a `TEXTURE_2D` of the given size with level 0 defined in a
+    /// color-renderable format, and it must belong to the given context (or to a context in its
+    /// share group). Depth and stencil buffers are allocated according to the context's
+    /// descriptor. Like any other generic surface, the result can be wrapped up in a
+    /// `SurfaceTexture` for reading by other contexts.
+    ///
+    /// The texture remains owned by the caller: destroying the surface does not delete it, but the
+    /// texture must not be deleted or respecified while the surface is alive.
+    ///
+    /// Returns a `SurfaceImportFailed` error if the texture cannot be rendered to.
+    pub fn create_surface_from_gl_texture(
+        &mut self,
+        context: &Context,
+        size: &Size2D<i32>,
+        texture: Texture,
+    ) -> Result<Surface, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        let context_descriptor = self.context_descriptor(context);
+        let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+
+        EGLBackedSurface::from_gl_texture(
+            &context.1,
+            self.native_connection.egl_display,
+            context.0.egl_context,
+            context.0.id,
+            &context_attributes,
+            size,
+            texture,
+        )
+        .map(Surface)
+    }
+
+    /// Returns the EGL image backing a generic surface.
+    ///
+    /// The image remains owned by the surface and is destroyed along with it. Other EGL clients
+    /// on this device's `EGLDisplay` can use it to share the surface's contents without copies.
+    ///
+    /// Returns a `WidgetAttached` error for widget surfaces, and `RequiredExtensionUnavailable` if
+    /// the surface could not be backed by an EGL image.
+    pub fn native_surface(&self, surface: &Surface) -> Result<NativeSurface, Error> {
+        surface.0.native_surface()
+    }
+
+    /// Creates a generic surface that renders into an existing EGL image.
+    ///
+    /// Depth and stencil buffers are allocated according to the context's descriptor. The image
+    /// remains owned by the caller and is not destroyed along with the surface.
+    ///
+    /// # Safety
+    ///
+    /// The image must have been created on this device's `EGLDisplay`, must be at least the given
+    /// size, and must outlive the surface.
+    pub unsafe fn create_surface_from_native_surface(
+        &mut self,
+        context: &Context,
+        native_surface: NativeSurface,
+    ) -> Result<Surface, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        let context_descriptor = self.context_descriptor(context);
+        let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+
+        EGLBackedSurface::from_native_surface(
+            &context.1,
+            self.native_connection.egl_display,
+            context.0.id,
+            &context_attributes,
+            native_surface,
+        )
+        .map(Surface)
+    }
+
+    /// Exports the contents of a generic surface into a handle that can be sent to another
+    /// process, which can import it with `create_surface_from_handle()`.
+    ///
+    /// Where `EGL_MESA_image_dma_buf_export` is available, the handle refers to the memory of the
+    /// surface itself, so later rendering to the surface is visible to the importer. Otherwise,
+    /// the handle contains a copy of the current contents of the surface in shared memory.
+    ///
+    /// The supplied context must be the context the surface is associated with, or this returns
+    /// an `IncompatibleSurface` error. Exporting a widget surface returns a `WidgetAttached` error.
+    pub fn export_surface(
+        &self,
+        context: &Context,
+        surface: &Surface,
+    ) -> Result<SurfaceHandle, Error> {
+        if context.0.id != surface.0.context_id {
+            return Err(Error::IncompatibleSurface);
+        }
+
+        let _guard = self.temporarily_make_context_current(context)?;
+        surface
+            .0
+            .export_handle(&context.1, self.native_connection.egl_display)
+    }
+
+    /// Creates a generic surface from a handle produced by `export_surface()`, possibly in another
+    /// process.
+    ///
+    /// Depth and stencil buffers are allocated according to the context's descriptor. The handle
+    /// can be dropped once the surface has been created. To read from the surface in another
+    /// context, wrap it in a surface texture with `create_surface_texture()`.
+    pub fn create_surface_from_handle(
+        &mut self,
+        context: &Context,
+        handle: &SurfaceHandle,
+    ) -> Result<Surface, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        let context_descriptor = self.context_descriptor(context);
+        let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+
+        unsafe {
+            EGLBackedSurface::from_handle(
+                &context.1,
+                self.native_connection.egl_display,
+                context.0.egl_context,
+                context.0.id,
+                &context_attributes,
+                handle,
+            )
+            .map(Surface)
+        }
+    }
+
     unsafe fn create_window_surface(
         &mut self,
         context: &Context,
         wayland_surface: *mut wl_proxy,
         size: &Size2D<i32>,
     ) -> Result<Surface, Error> {
+        let egl_config_id =
+            context::surface_config_id(self.native_connection.egl_display, context.0.egl_context);
+        if egl_config_id == 0 {
+            return Err(Error::NoPixelFormatFound);
+        }
+        let egl_config =
+            context::egl_config_from_id(self.native_connection.egl_display, egl_config_id);
+
         let egl_window =
             (WAYLAND_EGL_HANDLE.wl_egl_window_create)(wayland_surface, size.width, size.height);
         assert!(!egl_window.is_null());
 
-        let context_descriptor = self.context_descriptor(context);
-        let egl_config = context::egl_config_from_id(
-            self.native_connection.egl_display,
-            context_descriptor.egl_config_id,
-        );
-
         Ok(Surface(EGLBackedSurface::new_window(
             self.native_connection.egl_display,
             egl_config,
@@ -146,7 +302,10 @@ impl Device {
             Err(err) => return Err((err, surface)),
         };
 
-        match surface.0.to_surface_texture(&context.1) {
+        match surface
+            .0
+            .to_surface_texture(&context.1, self.native_connection.egl_display)
+        {
             Ok(surface_texture) => Ok(SurfaceTexture(surface_texture)),
             Err((err, surface)) => Err((err, Surface(surface))),
         }
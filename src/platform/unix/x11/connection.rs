@@ -8,8 +8,10 @@ use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDisplay};
 use crate::error::Error;
 use crate::info::GLApi;
-use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::device::{initialize_owned_egl_display, load_egl_library};
+use crate::platform::generic::egl::device::{terminate_owned_egl_display, EGL_FUNCTIONS};
 use crate::platform::generic::egl::ffi::EGL_PLATFORM_X11_KHR;
+use crate::platform::unix::generic::connection::default_gl_api;
 use crate::platform::unix::generic::device::Adapter;
 
 use euclid::default::Size2D;
@@ -26,12 +28,14 @@ static X_THREADS_INIT: Once = Once::new();
 #[derive(Clone)]
 pub struct Connection {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
+    pub(crate) gl_api: GLApi,
 }
 
 unsafe impl Send for Connection {}
 
 pub(crate) struct NativeConnectionWrapper {
     pub(crate) egl_display: EGLDisplay,
+    egl_display_is_owned: bool,
     x11_display: *mut Display,
     x11_display_is_owned: bool,
 }
@@ -53,6 +57,11 @@ impl Drop for NativeConnectionWrapper {
     #[inline]
     fn drop(&mut self) {
         unsafe {
+            // The EGL display must go away before the X11 display it was created from.
+            if self.egl_display_is_owned {
+                terminate_owned_egl_display(self.egl_display);
+            }
+            self.egl_display = egl::NO_DISPLAY;
             if self.x11_display_is_owned {
                 XCloseDisplay(self.x11_display);
             }
@@ -65,6 +74,7 @@ impl Connection {
     /// Connects to the default display.
     #[inline]
     pub fn new() -> Result<Connection, Error> {
+        load_egl_library()?;
         unsafe {
             X_THREADS_INIT.call_once(|| {
                 XInitThreads();
@@ -75,14 +85,22 @@ impl Connection {
                 return Err(Error::ConnectionFailed);
             }
 
-            let egl_display = create_egl_display(x11_display);
+            let egl_display = match create_egl_display(x11_display) {
+                Ok(egl_display) => egl_display,
+                Err(err) => {
+                    XCloseDisplay(x11_display);
+                    return Err(err);
+                }
+            };
 
             Ok(Connection {
                 native_connection: Arc::new(NativeConnectionWrapper {
                     x11_display,
                     x11_display_is_owned: true,
                     egl_display,
+                    egl_display_is_owned: true,
                 }),
+                gl_api: default_gl_api(),
             })
         }
     }
@@ -97,29 +115,44 @@ impl Connection {
     ///
     /// The display is not retained, as there is no way to do that in the X11 API. Therefore, it is
     /// the caller's responsibility to ensure that the display connection is not closed before this
-    /// `Connection` object is disposed of.
+    /// `Connection` object is disposed of. Likewise, the EGL display is never terminated by
+    /// `surfman`; that remains the caller's responsibility.
     #[inline]
     pub unsafe fn from_native_connection(
         native_connection: NativeConnection,
     ) -> Result<Connection, Error> {
+        load_egl_library()?;
         Ok(Connection {
             native_connection: Arc::new(NativeConnectionWrapper {
                 egl_display: native_connection.egl_display,
+                egl_display_is_owned: false,
                 x11_display: native_connection.x11_display,
                 x11_display_is_owned: false,
             }),
+            gl_api: default_gl_api(),
         })
     }
 
     fn from_x11_display(x11_display: *mut Display, is_owned: bool) -> Result<Connection, Error> {
+        load_egl_library()?;
         unsafe {
-            let egl_display = create_egl_display(x11_display);
+            let egl_display = match create_egl_display(x11_display) {
+                Ok(egl_display) => egl_display,
+                Err(err) => {
+                    if is_owned {
+                        XCloseDisplay(x11_display);
+                    }
+                    return Err(err);
+                }
+            };
             Ok(Connection {
                 native_connection: Arc::new(NativeConnectionWrapper {
                     egl_display,
+                    egl_display_is_owned: true,
                     x11_display,
                     x11_display_is_owned: is_owned,
                 }),
+                gl_api: default_gl_api(),
             })
         }
     }
@@ -136,7 +169,19 @@ impl Connection {
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Returns this connection with the OpenGL API flavor that its devices create contexts with
+    /// set to `gl_api`.
+    ///
+    /// This selects between `EGL_OPENGL_API` and `EGL_OPENGL_ES_API` on the same display. The
+    /// default is OpenGL, unless the `SURFMAN_FORCE_GLES` environment variable is set. Devices
+    /// that have already been created from this connection are unaffected.
+    #[inline]
+    pub fn with_gl_api(mut self, gl_api: GLApi) -> Connection {
+        self.gl_api = gl_api;
+        self
     }
 
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
@@ -300,7 +345,7 @@ impl<'a> DisplayGuard<'a> {
     }
 }
 
-unsafe fn create_egl_display(display: *mut Display) -> EGLDisplay {
+unsafe fn create_egl_display(display: *mut Display) -> Result<EGLDisplay, Error> {
     EGL_FUNCTIONS.with(|egl| {
         let display_attributes = [egl::NONE as EGLAttrib];
         let egl_display = egl.GetPlatformDisplay(
@@ -308,11 +353,10 @@ unsafe fn create_egl_display(display: *mut Display) -> EGLDisplay {
             display as *mut c_void,
             display_attributes.as_ptr(),
         );
+        if egl_display == egl::NO_DISPLAY || !initialize_owned_egl_display(egl_display) {
+            return Err(Error::ConnectionFailed);
+        }
 
-        let (mut egl_major_version, mut egl_minor_version) = (0, 0);
-        let ok = egl.Initialize(egl_display, &mut egl_major_version, &mut egl_minor_version);
-        assert_ne!(ok, egl::FALSE);
-
-        egl_display
+        Ok(egl_display)
     })
 }
@@ -10,6 +10,7 @@
 use super::device::{Adapter, Device, NativeDevice, VendorPreference};
 use super::surface::NativeWidget;
 use crate::egl::types::{EGLDisplay, EGLNativeWindowType};
+use crate::platform::generic::egl::device::load_egl_library;
 use crate::Error;
 use crate::GLApi;
 
@@ -44,6 +45,7 @@ impl Connection {
     /// Connects to the default display.
     #[inline]
     pub fn new() -> Result<Connection, Error> {
+        load_egl_library()?;
         Ok(Connection)
     }
 
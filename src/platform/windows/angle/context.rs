@@ -4,10 +4,10 @@
 
 use super::device::Device;
 use super::surface::{Surface, Synchronization, Win32Objects};
-use crate::context::{ContextID, CREATE_CONTEXT_MUTEX};
+use crate::context::{ContextGL, ContextID, CREATE_CONTEXT_MUTEX};
 use crate::egl;
 use crate::egl::types::{EGLConfig, EGLContext, EGLint};
-use crate::platform::generic::egl::context::{self, CurrentContextGuard};
+use crate::platform::generic::egl::context;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::error::ToWindowingApiError;
 use crate::platform::generic::egl::surface::ExternalEGLSurfaces;
@@ -21,7 +21,9 @@ use std::thread;
 use winapi::shared::winerror::S_OK;
 use winapi::um::winbase::INFINITE;
 
-pub use crate::platform::generic::egl::context::{ContextDescriptor, NativeContext};
+pub use crate::platform::generic::egl::context::{
+    ContextDescriptor, CurrentContextGuard, NativeContext,
+};
 
 /// Represents an OpenGL rendering context.
 ///
@@ -81,6 +83,19 @@ impl Device {
         }
     }
 
+    /// Creates a context descriptor for the first of `candidates` that this device can actually
+    /// create a context with.
+    ///
+    /// Candidates are tried in order, so list them from most to least preferred. Whatever context
+    /// was current before the call remains current.
+    pub fn negotiate_context_descriptor(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor, Error> {
+        let _guard = CurrentContextGuard::with_release_display(self.egl_display);
+        crate::context::negotiate_context_descriptor(self, candidates)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -173,6 +188,7 @@ impl Device {
             );
 
             if context.context_is_owned {
+                context::forget_created_context(self.egl_display, context.egl_context);
                 let result = egl.DestroyContext(self.egl_display, context.egl_context);
                 egl.DestroyContext(self.egl_display, context.egl_context);
                 assert_ne!(result, egl::FALSE);
@@ -226,6 +242,21 @@ impl Device {
         unsafe { context::make_no_context_current(self.egl_display) }
     }
 
+    /// Makes the context the current OpenGL context for this thread until the returned guard is
+    /// dropped.
+    ///
+    /// When the guard is dropped, whichever context and draw/read surfaces were current before
+    /// this call are made current again, even if they weren't created by `surfman`. If no context
+    /// was current, the context is released.
+    pub fn make_context_current_scoped(
+        &self,
+        context: &Context,
+    ) -> Result<CurrentContextGuard, Error> {
+        let guard = CurrentContextGuard::with_release_display(self.egl_display);
+        self.make_context_current(context)?;
+        Ok(guard)
+    }
+
     pub(crate) fn temporarily_make_context_current(
         &self,
         context: &Context,
@@ -260,6 +291,20 @@ impl Device {
         context::get_proc_address(symbol_name)
     }
 
+    /// Makes the context current and returns the OpenGL function table that surfman loaded for
+    /// it.
+    ///
+    /// This lets applications share surfman's function pointers instead of loading their own with
+    /// `get_proc_address`. The context stays current until the returned value is dropped, at which
+    /// point whichever context was current before is made current again.
+    pub fn context_gl<'a>(
+        &self,
+        context: &'a Context,
+    ) -> Result<ContextGL<'a, CurrentContextGuard>, Error> {
+        let guard = self.make_context_current_scoped(context)?;
+        Ok(ContextGL::new(&context.gl, guard))
+    }
+
     #[inline]
     pub(crate) fn context_descriptor_to_egl_config(
         &self,
@@ -359,6 +404,30 @@ impl Device {
         Ok(Some(surface))
     }
 
+    /// Attaches a surface to a context for reading, separately from the surface it renders to.
+    ///
+    /// This backend can't read from a separate surface, so this always returns an
+    /// `UnsupportedOnThisPlatform` error, alongside the surface.
+    #[inline]
+    pub fn bind_read_surface_to_context(
+        &self,
+        _: &mut Context,
+        surface: Surface,
+    ) -> Result<(), (Error, Surface)> {
+        Err((Error::UnsupportedOnThisPlatform, surface))
+    }
+
+    /// Removes and returns any read surface attached to this context.
+    ///
+    /// Read surfaces can't be bound on this backend, so this always returns `None`.
+    #[inline]
+    pub fn unbind_read_surface_from_context(
+        &self,
+        _: &mut Context,
+    ) -> Result<Option<Surface>, Error> {
+        Ok(None)
+    }
+
     /// Returns a unique ID representing a context.
     ///
     /// This ID is unique to all currently-allocated contexts. If you destroy a context and create
@@ -379,6 +448,14 @@ impl Device {
         }
     }
 
+    /// Returns various information about the read surface attached to a context, if any.
+    ///
+    /// Read surfaces can't be bound on this backend, so this always returns `None`.
+    #[inline]
+    pub fn context_read_surface_info(&self, _: &Context) -> Result<Option<SurfaceInfo>, Error> {
+        Ok(None)
+    }
+
     /// Given a context, returns its underlying EGL context and attached surfaces.
     pub fn native_context(&self, context: &Context) -> NativeContext {
         let (egl_draw_surface, egl_read_surface) = match context.framebuffer {
@@ -3,13 +3,16 @@
 //! A thread-local handle to the device.
 
 use super::connection::Connection;
+use super::context::CurrentContextGuard;
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDeviceEXT, EGLDisplay, EGLint};
+use crate::platform::generic::egl::device::terminate_owned_egl_display;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::device::{egl_extensions, initialize_owned_egl_display};
 use crate::platform::generic::egl::ffi::EGL_DEVICE_EXT;
 use crate::platform::generic::egl::ffi::{EGL_D3D11_DEVICE_ANGLE, EGL_EXTENSION_FUNCTIONS};
 use crate::platform::generic::egl::ffi::{EGL_NO_DEVICE_EXT, EGL_PLATFORM_DEVICE_EXT};
-use crate::{Error, GLApi};
+use crate::{DeviceCapabilities, Error, GLApi};
 
 use std::cell::{RefCell, RefMut};
 use std::mem;
@@ -212,9 +215,8 @@ impl Device {
                 assert_ne!(egl_display, egl::NO_DISPLAY);
 
                 // I don't think this should ever fail.
-                let (mut major_version, mut minor_version) = (0, 0);
-                let result = egl.Initialize(egl_display, &mut major_version, &mut minor_version);
-                assert_ne!(result, egl::FALSE);
+                let result = initialize_owned_egl_display(egl_display);
+                assert!(result);
 
                 Ok(Device {
                     egl_display,
@@ -323,17 +325,24 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GLES
     }
+
+    /// Returns the OpenGL versions, limits, surface formats, and extensions that this device
+    /// supports.
+    ///
+    /// This creates and destroys temporary contexts, so callers should cache the result.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        let _guard = CurrentContextGuard::with_release_display(self.egl_display);
+        let extensions = unsafe { egl_extensions(self.egl_display) };
+        DeviceCapabilities::probe(self, extensions, true, false)
+    }
 }
 
 impl Drop for Device {
     fn drop(&mut self) {
         unsafe {
             if self.display_is_owned {
-                EGL_FUNCTIONS.with(|egl| {
-                    let result = egl.Terminate(self.egl_display);
-                    assert_ne!(result, egl::FALSE);
-                    self.egl_display = egl::NO_DISPLAY;
-                })
+                terminate_owned_egl_display(self.egl_display);
+                self.egl_display = egl::NO_DISPLAY;
             }
         }
     }
@@ -11,11 +11,11 @@ use crate::egl::{self, EGLint};
 use crate::gl;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::error::ToWindowingApiError;
+use crate::platform::generic::egl::ffi::EGLDisplayExtensionFunctions;
 use crate::platform::generic::egl::ffi::EGL_D3D_TEXTURE_2D_SHARE_HANDLE_ANGLE;
 use crate::platform::generic::egl::ffi::EGL_D3D_TEXTURE_ANGLE;
 use crate::platform::generic::egl::ffi::EGL_DXGI_KEYED_MUTEX_ANGLE;
-use crate::platform::generic::egl::ffi::EGL_EXTENSION_FUNCTIONS;
-use crate::{Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType};
+use crate::{ContextAttributeFlags, Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType};
 
 use euclid::default::Size2D;
 use glow::HasContext;
@@ -134,21 +134,73 @@ impl Device {
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
         match surface_type {
-            SurfaceType::Generic { ref size } => self.create_pbuffer_surface(context, size, None),
+            SurfaceType::Generic { ref size } => {
+                self.create_pbuffer_surface(context, size, None, None)
+            }
             SurfaceType::Widget { ref native_widget } => {
                 self.create_window_surface(context, native_widget)
             }
         }
     }
 
+    /// Like `create_surface()`, but the format of a generic surface is chosen by the `ALPHA`,
+    /// `DEPTH`, and `STENCIL` flags in `surface_flags` instead of by the context's descriptor.
+    ///
+    /// Unless the context was created with `ContextAttributeFlags::NO_CONFIG`, this fails with
+    /// `IncompatibleContextDescriptor` if that format differs from the context's. Widget surfaces
+    /// always use the format of the context's descriptor.
+    pub fn create_surface_with_attributes(
+        &mut self,
+        context: &Context,
+        surface_access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget>,
+        surface_flags: ContextAttributeFlags,
+    ) -> Result<Surface, Error> {
+        match surface_type {
+            SurfaceType::Generic { ref size } => {
+                self.create_pbuffer_surface(context, size, None, Some(surface_flags))
+            }
+            surface_type => self.create_surface(context, surface_access, surface_type),
+        }
+    }
+
+    /// Creates a generic surface that renders into an existing OpenGL texture.
+    ///
+    /// ANGLE surfaces are backed by D3D11 textures, so OpenGL textures cannot be wrapped; this
+    /// always returns `UnsupportedOnThisPlatform`. Use `create_surface_from_texture()` to wrap a
+    /// D3D11 texture instead.
+    pub fn create_surface_from_gl_texture(
+        &mut self,
+        _: &Context,
+        _: &Size2D<i32>,
+        _: glow::Texture,
+    ) -> Result<Surface, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
     #[allow(non_snake_case)]
     fn create_pbuffer_surface(
         &mut self,
         context: &Context,
         size: &Size2D<i32>,
         texture: Option<ComPtr<d3d11::ID3D11Texture2D>>,
+        surface_flags: Option<ContextAttributeFlags>,
     ) -> Result<Surface, Error> {
-        let context_descriptor = self.context_descriptor(context);
+        let mut context_descriptor = self.context_descriptor(context);
+        if let Some(surface_flags) = surface_flags {
+            let surface_attributes = self
+                .context_descriptor_attributes(&context_descriptor)
+                .with_surface_flags(surface_flags);
+            let surface_descriptor = self.create_context_descriptor(&surface_attributes)?;
+            // Pbuffers are real EGL surfaces, so only contexts without a config can render to
+            // pbuffers with a config different from their own.
+            if !context_descriptor.no_config
+                && surface_descriptor.egl_config_id != context_descriptor.egl_config_id
+            {
+                return Err(Error::IncompatibleContextDescriptor);
+            }
+            context_descriptor = surface_descriptor;
+        }
         let egl_config = self.context_descriptor_to_egl_config(&context_descriptor);
 
         unsafe {
@@ -186,10 +238,12 @@ impl Device {
                 };
 
                 let eglQuerySurfacePointerANGLE =
-                    EGL_EXTENSION_FUNCTIONS.QuerySurfacePointerANGLE.expect(
-                        "Where's the `EGL_ANGLE_query_surface_pointer` \
+                    EGLDisplayExtensionFunctions::get(self.egl_display)
+                        .QuerySurfacePointerANGLE
+                        .expect(
+                            "Where's the `EGL_ANGLE_query_surface_pointer` \
                                                  extension?",
-                    );
+                        );
 
                 let mut share_handle = INVALID_HANDLE_VALUE;
                 let result = eglQuerySurfacePointerANGLE(
@@ -243,7 +297,7 @@ impl Device {
         size: &Size2D<i32>,
         texture: ComPtr<d3d11::ID3D11Texture2D>,
     ) -> Result<Surface, Error> {
-        self.create_pbuffer_surface(context, size, Some(texture))
+        self.create_pbuffer_surface(context, size, Some(texture), None)
     }
 
     fn create_window_surface(
@@ -347,7 +401,9 @@ impl Device {
 
                 let mut local_keyed_mutex: *mut IDXGIKeyedMutex = ptr::null_mut();
                 let eglQuerySurfacePointerANGLE =
-                    EGL_EXTENSION_FUNCTIONS.QuerySurfacePointerANGLE.unwrap();
+                    EGLDisplayExtensionFunctions::get(self.egl_display)
+                        .QuerySurfacePointerANGLE
+                        .unwrap();
                 let result = eglQuerySurfacePointerANGLE(
                     self.egl_display,
                     local_egl_surface,
@@ -430,7 +486,7 @@ impl Device {
         size: &Size2D<i32>,
         texture: ComPtr<d3d11::ID3D11Texture2D>,
     ) -> Result<SurfaceTexture, Error> {
-        let surface = self.create_pbuffer_surface(context, size, Some(texture))?;
+        let surface = self.create_pbuffer_surface(context, size, Some(texture), None)?;
         let local_egl_surface = surface.egl_surface;
         self.create_surface_texture_from_local_surface(context, surface, local_egl_surface, None)
             .map_err(|(err, mut surface)| {
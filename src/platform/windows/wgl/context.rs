@@ -4,10 +4,10 @@
 
 use super::device::{DCGuard, Device, HiddenWindow};
 use super::surface::{Surface, Win32Objects};
-use crate::context::{self, CREATE_CONTEXT_MUTEX};
+use crate::context::{self, ContextGL, CREATE_CONTEXT_MUTEX};
 use crate::surface::Framebuffer;
-use crate::{ContextAttributeFlags, ContextAttributes, ContextID, Error, GLVersion};
-use crate::{SurfaceInfo, WindowingApiError};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextID, ContextPriority, Error};
+use crate::{GLVersion, SurfaceInfo, WindowingApiError};
 
 use crate::gl;
 type GLenum = c_uint;
@@ -16,6 +16,7 @@ type GLuint = c_uint;
 use crate::Gl;
 use glow::HasContext;
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::ffi::{CStr, CString};
 use std::mem;
 use std::os::raw::{c_char, c_int, c_uint, c_void};
@@ -48,6 +49,7 @@ const WGL_TYPE_RGBA_ARB: GLenum = 0x202b;
 const WGL_CONTEXT_MAJOR_VERSION_ARB: GLenum = 0x2091;
 const WGL_CONTEXT_MINOR_VERSION_ARB: GLenum = 0x2092;
 const WGL_CONTEXT_PROFILE_MASK_ARB: GLenum = 0x9126;
+const WGL_CONTEXT_OPENGL_NO_ERROR_ARB: GLenum = 0x31b3;
 
 const WGL_CONTEXT_CORE_PROFILE_BIT_ARB: GLenum = 0x00000001;
 const WGL_CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB: GLenum = 0x00000002;
@@ -59,6 +61,7 @@ pub(crate) struct WGLExtensionFunctions {
         unsafe extern "C" fn(hDC: HDC, shareContext: HGLRC, attribList: *const c_int) -> HGLRC,
     >,
     GetExtensionsStringARB: Option<unsafe extern "C" fn(hdc: HDC) -> *const c_char>,
+    pub(crate) extensions: HashSet<String>,
     pub(crate) pixel_format_functions: Option<WGLPixelFormatExtensionFunctions>,
     pub(crate) dx_interop_functions: Option<WGLDXInteropExtensionFunctions>,
 }
@@ -111,6 +114,7 @@ pub struct ContextDescriptor {
     pixel_format: c_int,
     gl_version: GLVersion,
     compatibility_profile: bool,
+    no_error: bool,
 }
 
 /// Represents an OpenGL rendering context.
@@ -136,6 +140,8 @@ pub struct Context {
     hidden_window: Option<HiddenWindow>,
     pub(crate) framebuffer: Framebuffer<Surface, ()>,
     status: ContextStatus,
+    // Whether error checking was disabled, if we created the context. WGL has no query for this.
+    no_error: Option<bool>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -187,6 +193,20 @@ impl Device {
         };
         let compatibility_profile = flags.contains(ContextAttributeFlags::COMPATIBILITY_PROFILE);
 
+        let mut no_error = flags.contains(ContextAttributeFlags::NO_ERROR);
+        if no_error
+            && !WGL_EXTENSION_FUNCTIONS
+                .extensions
+                .contains("WGL_ARB_create_context_no_error")
+        {
+            warn!("`WGL_ARB_create_context_no_error` is unavailable; ignoring `NO_ERROR`");
+            no_error = false;
+        }
+
+        if attributes.priority != ContextPriority::Medium {
+            warn!("WGL can't prioritize contexts; ignoring context priority");
+        }
+
         let attrib_i_list = [
             WGL_DRAW_TO_WINDOW_ARB as c_int,
             gl::TRUE as c_int,
@@ -236,10 +256,24 @@ impl Device {
                 pixel_format,
                 gl_version: attributes.version,
                 compatibility_profile,
+                no_error,
             })
         }
     }
 
+    /// Creates a context descriptor for the first of `candidates` that this device can actually
+    /// create a context with.
+    ///
+    /// Candidates are tried in order, so list them from most to least preferred. Whatever context
+    /// was current before the call remains current.
+    pub fn negotiate_context_descriptor(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor, Error> {
+        let _guard = CurrentContextGuard::new();
+        context::negotiate_context_descriptor(self, candidates)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -274,15 +308,19 @@ impl Device {
                 } else {
                     WGL_CONTEXT_CORE_PROFILE_BIT_ARB
                 };
-                let wgl_attributes = [
+                let mut wgl_attributes = vec![
                     WGL_CONTEXT_MAJOR_VERSION_ARB as c_int,
                     descriptor.gl_version.major as c_int,
                     WGL_CONTEXT_MINOR_VERSION_ARB as c_int,
                     descriptor.gl_version.minor as c_int,
                     WGL_CONTEXT_PROFILE_MASK_ARB as c_int,
                     profile_mask as c_int,
-                    0,
                 ];
+                if descriptor.no_error {
+                    wgl_attributes
+                        .extend(&[WGL_CONTEXT_OPENGL_NO_ERROR_ARB as c_int, gl::TRUE as c_int]);
+                }
+                wgl_attributes.push(0);
                 glrc = wglCreateContextAttribsARB(
                     dc,
                     share_with.map_or(ptr::null_mut(), |ctx| ctx.glrc),
@@ -309,6 +347,7 @@ impl Device {
                 hidden_window: Some(hidden_window),
                 framebuffer: Framebuffer::None,
                 status: ContextStatus::Owned,
+                no_error: Some(descriptor.no_error),
             };
             next_context_id.0 += 1;
             Ok(context)
@@ -344,6 +383,7 @@ impl Device {
             hidden_window: Some(hidden_window),
             framebuffer: Framebuffer::External(()),
             status: ContextStatus::Referenced,
+            no_error: None,
         };
         next_context_id.0 += 1;
         Ok(context)
@@ -387,11 +427,15 @@ impl Device {
             let gl_version = GLVersion::current(&context.gl);
             let compatibility_profile =
                 context::current_context_uses_compatibility_profile(&context.gl);
+            let no_error = context
+                .no_error
+                .unwrap_or_else(|| context::current_context_has_no_error(&context.gl));
 
             ContextDescriptor {
                 pixel_format,
                 gl_version,
                 compatibility_profile,
+                no_error,
             }
         }
     }
@@ -438,6 +482,7 @@ impl Device {
             let mut attributes = ContextAttributes {
                 version: context_descriptor.gl_version,
                 flags: ContextAttributeFlags::empty(),
+                priority: ContextPriority::Medium,
             };
             if alpha_bits > 0 {
                 attributes.flags.insert(ContextAttributeFlags::ALPHA);
@@ -448,6 +493,9 @@ impl Device {
             if stencil_bits > 0 {
                 attributes.flags.insert(ContextAttributeFlags::STENCIL);
             }
+            if context_descriptor.no_error {
+                attributes.flags.insert(ContextAttributeFlags::NO_ERROR);
+            }
 
             attributes
         }
@@ -465,6 +513,21 @@ impl Device {
         }
     }
 
+    /// Makes the context the current OpenGL context for this thread until the returned guard is
+    /// dropped.
+    ///
+    /// When the guard is dropped, whichever context and device context were current before this
+    /// call are made current again, even if they weren't created by `surfman`. If no context was
+    /// current, the context is released.
+    pub fn make_context_current_scoped(
+        &self,
+        context: &Context,
+    ) -> Result<CurrentContextGuard, Error> {
+        let guard = CurrentContextGuard::new();
+        self.make_context_current(context)?;
+        Ok(guard)
+    }
+
     pub(crate) fn temporarily_make_context_current(
         &self,
         context: &Context,
@@ -517,6 +580,20 @@ impl Device {
         get_proc_address(symbol_name)
     }
 
+    /// Makes the context current and returns the OpenGL function table that surfman loaded for
+    /// it.
+    ///
+    /// This lets applications share surfman's function pointers instead of loading their own with
+    /// `get_proc_address`. The context stays current until the returned value is dropped, at which
+    /// point whichever context was current before is made current again.
+    pub fn context_gl<'a>(
+        &self,
+        context: &'a Context,
+    ) -> Result<ContextGL<'a, CurrentContextGuard>, Error> {
+        let guard = self.make_context_current_scoped(context)?;
+        Ok(ContextGL::new(&context.gl, guard))
+    }
+
     #[inline]
     fn context_is_current(&self, context: &Context) -> bool {
         unsafe { wglGetCurrentContext() == context.glrc }
@@ -578,6 +655,30 @@ impl Device {
         }
     }
 
+    /// Attaches a surface to a context for reading, separately from the surface it renders to.
+    ///
+    /// This backend can't read from a separate surface, so this always returns an
+    /// `UnsupportedOnThisPlatform` error, alongside the surface.
+    #[inline]
+    pub fn bind_read_surface_to_context(
+        &self,
+        _: &mut Context,
+        surface: Surface,
+    ) -> Result<(), (Error, Surface)> {
+        Err((Error::UnsupportedOnThisPlatform, surface))
+    }
+
+    /// Removes and returns any read surface attached to this context.
+    ///
+    /// Read surfaces can't be bound on this backend, so this always returns `None`.
+    #[inline]
+    pub fn unbind_read_surface_from_context(
+        &self,
+        _: &mut Context,
+    ) -> Result<Option<Surface>, Error> {
+        Ok(None)
+    }
+
     pub(crate) fn get_context_dc<'a>(&self, context: &'a Context) -> DCGuard<'a> {
         unsafe {
             match context.framebuffer {
@@ -615,6 +716,14 @@ impl Device {
         }
     }
 
+    /// Returns various information about the read surface attached to a context, if any.
+    ///
+    /// Read surfaces can't be bound on this backend, so this always returns `None`.
+    #[inline]
+    pub fn context_read_surface_info(&self, _: &Context) -> Result<Option<SurfaceInfo>, Error> {
+        Ok(None)
+    }
+
     /// Given a context, returns its underlying `HGLRC`.
     #[inline]
     pub fn native_context(&self, context: &Context) -> NativeContext {
@@ -748,6 +857,9 @@ extern "system" fn extension_loader_window_proc(
                     None => Cow::Borrowed(""),
                 };
 
+                (*wgl_extension_functions).extensions =
+                    extensions.split_whitespace().map(String::from).collect();
+
                 // Load function pointers.
                 for extension in extensions.split(' ') {
                     if extension == "WGL_ARB_pixel_format" {
@@ -845,8 +957,12 @@ impl<'a> FramebufferGuard<'a> {
     }
 }
 
+/// Restores the previously-current WGL context and device context when dropped.
+///
+/// This is returned by `Device::make_context_current_scoped()`. The previous state is captured
+/// straight from WGL, so contexts that were not created by `surfman` are restored as well.
 #[must_use]
-pub(crate) struct CurrentContextGuard {
+pub struct CurrentContextGuard {
     old_dc: HDC,
     old_glrc: HGLRC,
 }
@@ -862,7 +978,7 @@ impl Drop for CurrentContextGuard {
 
 impl CurrentContextGuard {
     #[inline]
-    fn new() -> CurrentContextGuard {
+    pub(crate) fn new() -> CurrentContextGuard {
         unsafe {
             CurrentContextGuard {
                 old_dc: wglGetCurrentDC(),
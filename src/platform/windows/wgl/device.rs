@@ -3,8 +3,8 @@
 //! An implementation of the GPU device for Windows using the WGL API.
 
 use super::connection::Connection;
-use super::context::WGL_EXTENSION_FUNCTIONS;
-use crate::{Error, GLApi};
+use super::context::{CurrentContextGuard, WGL_EXTENSION_FUNCTIONS};
+use crate::{DeviceCapabilities, Error, GLApi};
 
 use std::ffi::CStr;
 use std::marker::PhantomData;
@@ -236,6 +236,16 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GL
     }
+
+    /// Returns the OpenGL versions, limits, surface formats, and extensions that this device
+    /// supports.
+    ///
+    /// This creates and destroys temporary contexts, so callers should cache the result.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        let _guard = CurrentContextGuard::new();
+        let extensions = WGL_EXTENSION_FUNCTIONS.extensions.clone();
+        DeviceCapabilities::probe(self, extensions, true, false)
+    }
 }
 
 impl Adapter {
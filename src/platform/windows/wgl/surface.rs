@@ -6,7 +6,8 @@ use super::context::{self, Context, WGL_EXTENSION_FUNCTIONS};
 use super::device::Device;
 use crate::error::WindowingApiError;
 use crate::renderbuffers::Renderbuffers;
-use crate::{ContextID, Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType};
+use crate::SurfaceType;
+use crate::{ContextAttributeFlags, ContextID, Error, SurfaceAccess, SurfaceID, SurfaceInfo};
 
 use crate::gl;
 type GLenum = c_uint;
@@ -139,17 +140,50 @@ impl Device {
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
         match surface_type {
-            SurfaceType::Generic { size } => self.create_generic_surface(context, &size),
+            SurfaceType::Generic { size } => self.create_generic_surface(context, &size, None),
             SurfaceType::Widget { native_widget } => {
                 self.create_widget_surface(context, native_widget)
             }
         }
     }
 
+    /// Like `create_surface()`, but the format of a generic surface is chosen by the `ALPHA`,
+    /// `DEPTH`, and `STENCIL` flags in `surface_flags` instead of by the context's descriptor.
+    ///
+    /// Widget surfaces always use the format of the context's descriptor.
+    pub fn create_surface_with_attributes(
+        &mut self,
+        context: &Context,
+        surface_access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget>,
+        surface_flags: ContextAttributeFlags,
+    ) -> Result<Surface, Error> {
+        match surface_type {
+            SurfaceType::Generic { size } => {
+                self.create_generic_surface(context, &size, Some(surface_flags))
+            }
+            surface_type => self.create_surface(context, surface_access, surface_type),
+        }
+    }
+
+    /// Creates a generic surface that renders into an existing OpenGL texture.
+    ///
+    /// WGL surfaces are backed by shared D3D11 textures, so OpenGL textures cannot be wrapped;
+    /// this always returns `UnsupportedOnThisPlatform`.
+    pub fn create_surface_from_gl_texture(
+        &mut self,
+        _: &Context,
+        _: &Size2D<i32>,
+        _: glow::Texture,
+    ) -> Result<Surface, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
     fn create_generic_surface(
         &mut self,
         context: &Context,
         size: &Size2D<i32>,
+        surface_flags: Option<ContextAttributeFlags>,
     ) -> Result<Surface, Error> {
         let dx_interop_functions = match WGL_EXTENSION_FUNCTIONS.dx_interop_functions {
             None => return Err(Error::RequiredExtensionUnavailable),
@@ -247,7 +281,10 @@ impl Device {
 
             // Create renderbuffers as appropriate, and attach them.
             let context_descriptor = self.context_descriptor(context);
-            let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+            let mut context_attributes = self.context_descriptor_attributes(&context_descriptor);
+            if let Some(surface_flags) = surface_flags {
+                context_attributes = context_attributes.with_surface_flags(surface_flags);
+            }
             let renderbuffers = Renderbuffers::new(&context.gl, &size, &context_attributes);
             renderbuffers.bind_to_current_framebuffer(&context.gl);
 
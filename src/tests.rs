@@ -13,11 +13,15 @@ use super::context::{Context, ContextDescriptor, NativeContext};
 use super::device::{Adapter, Device};
 use super::surface::Surface;
 use crate::gl;
-use crate::{ContextAttributeFlags, ContextAttributes, Error, GLApi, GLVersion, Gl, SurfaceAccess};
-use crate::{SurfaceType, WindowingApiError};
+use crate::gl_utils::{GLStateGroups, GLStateSnapshot};
+use crate::owned::{DestructionQueue, SharedDevice};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextPriority, Error, GLApi, GLVersion};
+#[cfg(free_unix)]
+use crate::{DirectoryShaderCache, ShaderCache};
+use crate::{Gl, SurfaceAccess, SurfaceType, WindowingApiError};
 
 use euclid::default::Size2D;
-use glow::{Framebuffer, HasContext, PixelPackData, Texture};
+use glow::{Framebuffer, HasContext, PixelPackData, PixelUnpackData, Texture};
 #[cfg(not(feature = "sm-test"))]
 use serial_test::serial;
 use std::sync::mpsc;
@@ -81,6 +85,125 @@ pub fn test_device_accessors() {
     drop(device.adapter());
 }
 
+// Tests that device capabilities can be queried without disturbing the current context.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_device_capabilities() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let capabilities = env.device.capabilities().unwrap();
+    assert_eq!(capabilities.gl_api, env.device.gl_api());
+    assert!(capabilities.gl_versions.contains(&GLVersion::new(3, 0)));
+    assert!(capabilities.max_texture_size >= 64);
+    assert!(capabilities.max_renderbuffer_size >= 64);
+    assert!(capabilities
+        .surface_formats
+        .contains(&ContextAttributeFlags::empty()));
+
+    // The environment's context should still be current.
+    clear(&env.gl, &[0, 255, 255, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 255, 255]);
+    check_gl(&env.gl);
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that context descriptor negotiation skips versions the device can't create contexts with.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_negotiate_context_descriptor() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let candidates = [
+        ContextAttributes {
+            version: GLVersion::new(99, 0),
+            flags: ContextAttributeFlags::empty(),
+            priority: ContextPriority::Medium,
+        },
+        ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::DEPTH,
+            priority: ContextPriority::Medium,
+        },
+    ];
+    let descriptor = env
+        .device
+        .negotiate_context_descriptor(&candidates)
+        .unwrap();
+    let attributes = env.device.context_descriptor_attributes(&descriptor);
+    assert_eq!(attributes.version, GLVersion::new(3, 0));
+    assert!(attributes.flags.contains(ContextAttributeFlags::DEPTH));
+
+    assert!(env.device.negotiate_context_descriptor(&[]).is_err());
+
+    // The environment's context should still be current.
+    clear(&env.gl, &[255, 255, 0, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 255, 0, 255]);
+    check_gl(&env.gl);
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that a desktop Linux connection can be switched to creating OpenGL ES contexts.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_gles_connection() {
+    let connection = Connection::new().unwrap().with_gl_api(GLApi::GLES);
+    assert_eq!(connection.gl_api(), GLApi::GLES);
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+    assert_eq!(device.gl_api(), GLApi::GLES);
+    assert_eq!(device.connection().gl_api(), GLApi::GLES);
+
+    match device.create_context_descriptor(&ContextAttributes {
+        version: GLVersion::new(3, 0),
+        flags: ContextAttributeFlags::COMPATIBILITY_PROFILE,
+        priority: ContextPriority::Medium,
+    }) {
+        Err(Error::UnsupportedGLProfile) => {}
+        Ok(_) => panic!("OpenGL ES has no compatibility profile!"),
+        Err(err) => panic!("Unexpected error: {:?}", err),
+    }
+
+    let context_descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::empty(),
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+    let mut context = device.create_context(&context_descriptor, None).unwrap();
+    let surface = make_surface(&mut device, &context);
+    device
+        .bind_surface_to_context(&mut context, surface)
+        .unwrap();
+    let gl = device.context_gl(&context).unwrap();
+    assert!(gl.version().is_embedded);
+    bind_context_fbo(&gl, &device, &context);
+    clear(&gl, &[255, 0, 0, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&gl), [255, 0, 0, 255]);
+    check_gl(&gl);
+    drop(gl);
+
+    device.destroy_context(&mut context).unwrap();
+}
+
 // Tests that all combinations of flags result in the creation of valid context descriptors and
 // contexts.
 #[cfg_attr(not(feature = "sm-test"), test)]
@@ -108,10 +231,16 @@ pub fn test_context_creation() {
     for &version in versions {
         for flag_bits in 0..(ContextAttributeFlags::all().bits() + 1) {
             let flags = ContextAttributeFlags::from_bits_truncate(flag_bits);
-            let attributes = ContextAttributes { version, flags };
+            let attributes = ContextAttributes {
+                version,
+                flags,
+                priority: ContextPriority::Medium,
+            };
             let descriptor = match device.create_context_descriptor(&attributes) {
                 Ok(descriptor) => descriptor,
-                Err(Error::UnsupportedGLProfile) | Err(Error::UnsupportedGLVersion) => {
+                Err(Error::UnsupportedGLProfile)
+                | Err(Error::UnsupportedGLVersion)
+                | Err(Error::RequiredExtensionUnavailable) => {
                     // Nothing we can do about this. Go on to the next one.
                     continue;
                 }
@@ -124,7 +253,12 @@ pub fn test_context_creation() {
                     let actual_descriptor = device.context_descriptor(&context);
                     let actual_attributes =
                         device.context_descriptor_attributes(&actual_descriptor);
-                    if !actual_attributes.flags.contains(attributes.flags) {
+                    // Backends that don't need configs are free to ignore `NO_CONFIG`, and
+                    // `NO_ERROR` is ignored where unsupported.
+                    let expected_flags = attributes.flags
+                        - ContextAttributeFlags::NO_CONFIG
+                        - ContextAttributeFlags::NO_ERROR;
+                    if !actual_attributes.flags.contains(expected_flags) {
                         device.destroy_context(&mut context).unwrap();
                         panic!(
                             "Expected at least attribute flags {:?} for {:?} {:?} but got \
@@ -192,6 +326,7 @@ pub fn test_newly_created_contexts_are_current() {
         .create_context_descriptor(&ContextAttributes {
             version: GLVersion::new(3, 0),
             flags: ContextAttributeFlags::empty(),
+            priority: ContextPriority::Medium,
         })
         .unwrap();
 
@@ -259,6 +394,7 @@ pub fn test_context_sharing() {
         .create_context_descriptor(&ContextAttributes {
             version: GLVersion::new(3, 0),
             flags: ContextAttributeFlags::empty(),
+            priority: ContextPriority::Medium,
         })
         .unwrap();
 
@@ -300,6 +436,7 @@ pub fn test_generic_surface_creation() {
         .create_context_descriptor(&ContextAttributes {
             version: GLVersion::new(3, 0),
             flags: ContextAttributeFlags::empty(),
+            priority: ContextPriority::Medium,
         })
         .unwrap();
 
@@ -514,6 +651,87 @@ pub fn test_surface_texture_blit_framebuffer() {
     }
 }
 
+// Tests that surfaces fall back to copying their pixels when EGL images are unavailable, and that
+// the copies can be blitted from like any other surface texture.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_surface_texture_without_egl_images() {
+    use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+    use crate::platform::generic::egl::ffi::EGLDisplayExtensionFunctions;
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let egl_display = EGL_FUNCTIONS.with(|egl| unsafe { egl.GetCurrentDisplay() });
+    EGLDisplayExtensionFunctions::disable_egl_images(egl_display);
+
+    unsafe {
+        let copied_surface = make_surface(&mut env.device, &env.context);
+        assert!(copied_surface.0.needs_read_back());
+
+        // Render into the copied surface, then swap the main surface back in so that the copied
+        // surface gets its pixels read back.
+        let main_surface = env
+            .device
+            .unbind_surface_from_context(&mut env.context)
+            .unwrap()
+            .unwrap();
+        env.device
+            .bind_surface_to_context(&mut env.context, copied_surface)
+            .unwrap();
+        bind_context_fbo(&env.gl, &env.device, &env.context);
+        clear(&env.gl, &[0, 255, 0, 255]);
+        let copied_surface = env
+            .device
+            .unbind_surface_from_context(&mut env.context)
+            .unwrap()
+            .unwrap();
+        env.device
+            .bind_surface_to_context(&mut env.context, main_surface)
+            .unwrap();
+
+        let copied_surface_texture = env
+            .device
+            .create_surface_texture(&mut env.context, copied_surface)
+            .unwrap();
+        let copied_framebuffer_object = make_fbo(
+            &env.gl,
+            env.device.surface_gl_texture_target(),
+            env.device.surface_texture_object(&copied_surface_texture),
+        );
+
+        let main_framebuffer_object = context_fbo(&env.device, &env.context);
+        env.gl
+            .bind_framebuffer(gl::FRAMEBUFFER, main_framebuffer_object);
+        clear(&env.gl, &[255, 0, 0, 255]);
+        blit_fbo(
+            &env.gl,
+            main_framebuffer_object,
+            Some(copied_framebuffer_object),
+        );
+        env.gl
+            .bind_framebuffer(gl::FRAMEBUFFER, main_framebuffer_object);
+        check_gl(&env.gl);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+
+        // Clean up.
+        env.gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        env.gl.delete_framebuffer(copied_framebuffer_object);
+        let mut copied_surface = env
+            .device
+            .destroy_surface_texture(&mut env.context, copied_surface_texture)
+            .unwrap();
+        env.device
+            .destroy_surface(&mut env.context, &mut copied_surface)
+            .unwrap();
+        EGLDisplayExtensionFunctions::forget(egl_display);
+        env.device.destroy_context(&mut env.context).unwrap();
+    }
+}
+
 #[cfg_attr(not(feature = "sm-test"), test)]
 #[cfg_attr(not(feature = "sm-test"), serial)]
 pub fn test_cross_device_surface_texture_blit_framebuffer() {
@@ -781,6 +999,7 @@ pub fn test_depth_and_stencil() {
         .create_context_descriptor(&ContextAttributes {
             version: GLVersion::new(3, 0),
             flags: ContextAttributeFlags::DEPTH,
+            priority: ContextPriority::Medium,
         })
         .unwrap();
 
@@ -837,6 +1056,7 @@ pub fn test_depth_and_stencil() {
         .create_context_descriptor(&ContextAttributes {
             version: GLVersion::new(3, 0),
             flags: ContextAttributeFlags::STENCIL,
+            priority: ContextPriority::Medium,
         })
         .unwrap();
 
@@ -881,6 +1101,90 @@ pub fn test_depth_and_stencil() {
     device.destroy_context(&mut stencil_context).unwrap();
 }
 
+// Tests that a context created without a config can render to surfaces with different formats.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_no_config_context() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    let context_descriptor = match device.create_context_descriptor(&ContextAttributes {
+        version: GLVersion::new(3, 0),
+        flags: ContextAttributeFlags::NO_CONFIG,
+        priority: ContextPriority::Medium,
+    }) {
+        Ok(context_descriptor) => context_descriptor,
+        Err(Error::RequiredExtensionUnavailable) => return,
+        Err(err) => panic!("Failed to create context descriptor: {:?}", err),
+    };
+    let mut context = device.create_context(&context_descriptor, None).unwrap();
+    let context_attributes =
+        device.context_descriptor_attributes(&device.context_descriptor(&context));
+    assert!(context_attributes
+        .flags
+        .contains(ContextAttributeFlags::NO_CONFIG));
+
+    let gl =
+        unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&context, symbol)) };
+
+    for surface_flags in [
+        ContextAttributeFlags::empty(),
+        ContextAttributeFlags::DEPTH,
+        ContextAttributeFlags::ALPHA,
+    ] {
+        let surface = device
+            .create_surface_with_attributes(
+                &context,
+                SurfaceAccess::GPUOnly,
+                SurfaceType::Generic {
+                    size: Size2D::new(640, 480),
+                },
+                surface_flags,
+            )
+            .unwrap();
+        device
+            .bind_surface_to_context(&mut context, surface)
+            .unwrap();
+        device.make_context_current(&context).unwrap();
+
+        unsafe {
+            bind_context_fbo(&gl, &device, &context);
+            let depth_attachment_type = gl.get_framebuffer_attachment_parameter_i32(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE,
+            );
+            assert_eq!(
+                depth_attachment_type != gl::NONE as i32,
+                surface_flags.contains(ContextAttributeFlags::DEPTH)
+            );
+
+            gl.viewport(0, 0, 640, 480);
+            clear(&gl, &[0, 0, 255, 255]);
+            assert_eq!(get_pixel_from_bottom_row(&gl), [0, 0, 255, 255]);
+            check_gl(&gl);
+        }
+
+        let mut surface = device
+            .unbind_surface_from_context(&mut context)
+            .unwrap()
+            .unwrap();
+        device.destroy_surface(&mut context, &mut surface).unwrap();
+    }
+
+    device.destroy_context(&mut context).unwrap();
+}
+
 // Make sure that the current native context can be fetched and that they can be correctly wrapped
 // in `surfman` contexts.
 #[cfg_attr(not(feature = "sm-test"), test)]
@@ -910,68 +1214,1472 @@ pub fn test_get_native_context() {
     env.device.destroy_context(&mut env.context).unwrap();
 }
 
-fn bind_context_fbo(gl: &Gl, device: &Device, context: &Context) {
+// Tests that `make_context_current_scoped()` restores whatever was current beforehand, including
+// having no context current at all.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_make_context_current_scoped() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let mut other_context = env
+        .device
+        .create_context(&env.context_descriptor, None)
+        .unwrap();
+    let other_surface = make_surface(&mut env.device, &other_context);
+    env.device
+        .bind_surface_to_context(&mut other_context, other_surface)
+        .unwrap();
+    let other_gl = unsafe {
+        Gl::from_loader_function(|symbol| env.device.get_proc_address(&other_context, symbol))
+    };
+
+    env.device.make_context_current(&env.context).unwrap();
+    clear(&env.gl, &[255, 0, 0, 255]);
+
     unsafe {
-        gl.bind_framebuffer(gl::FRAMEBUFFER, context_fbo(device, context));
-        check_gl(gl);
+        let _guard = env
+            .device
+            .make_context_current_scoped(&other_context)
+            .unwrap();
+        bind_context_fbo(&other_gl, &env.device, &other_context);
+        other_gl.viewport(0, 0, 640, 480);
+        clear(&other_gl, &[0, 255, 0, 255]);
+        assert_eq!(get_pixel_from_bottom_row(&other_gl), [0, 255, 0, 255]);
     }
-}
 
-fn context_fbo(device: &Device, context: &Context) -> Option<Framebuffer> {
-    device
-        .context_surface_info(context)
-        .unwrap()
-        .unwrap()
-        .framebuffer_object
-}
+    // The original context should be current again, with its framebuffer untouched.
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
 
-fn make_surface(device: &mut Device, context: &Context) -> Surface {
-    device
-        .create_surface(
-            context,
-            SurfaceAccess::GPUOnly,
-            SurfaceType::Generic {
-                size: Size2D::new(640, 480),
-            },
-        )
-        .unwrap()
+    env.device.make_no_context_current().unwrap();
+    {
+        let _guard = env
+            .device
+            .make_context_current_scoped(&env.context)
+            .unwrap();
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+    }
+
+    // No context was current before, so none should be current any longer.
+    assert!(matches!(
+        NativeContext::current(),
+        Err(Error::NoCurrentContext)
+    ));
+
+    env.device.make_context_current(&env.context).unwrap();
+    env.device.destroy_context(&mut other_context).unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
 }
 
-fn blit_fbo(gl: &Gl, dest_fbo: Option<Framebuffer>, src_fbo: Option<Framebuffer>) {
+// Tests that `GLStateSnapshot` restores the state groups it captured, and only those.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_gl_state_snapshot() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
     unsafe {
-        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, dest_fbo);
+        let gl = &env.gl;
+        gl.clear_color(0.0, 1.0, 0.0, 1.0);
+        gl.enable(gl::SCISSOR_TEST);
+        gl.scissor(1, 2, 3, 4);
+        gl.depth_mask(false);
+
+        let state =
+            GLStateSnapshot::capture(gl, GLStateGroups::CLEAR_VALUES | GLStateGroups::SCISSOR);
+        gl.clear_color(1.0, 0.0, 0.0, 1.0);
+        gl.disable(gl::SCISSOR_TEST);
+        gl.scissor(0, 0, 640, 480);
+        gl.depth_mask(true);
+        state.restore(gl);
+
+        let mut clear_color = [0.0; 4];
+        gl.get_parameter_f32_slice(gl::COLOR_CLEAR_VALUE, &mut clear_color);
+        assert_eq!(clear_color, [0.0, 1.0, 0.0, 1.0]);
+        assert!(gl.is_enabled(gl::SCISSOR_TEST));
+        let mut scissor_box = [0; 4];
+        gl.get_parameter_i32_slice(gl::SCISSOR_BOX, &mut scissor_box);
+        assert_eq!(scissor_box, [1, 2, 3, 4]);
+        // Write masks weren't captured, so they should be left alone.
+        assert!(gl.get_parameter_bool(gl::DEPTH_WRITEMASK));
         check_gl(gl);
-        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, src_fbo);
+
+        // The front and back stencil write masks are restored separately.
+        gl.stencil_mask_separate(gl::FRONT, 0x0f);
+        gl.stencil_mask_separate(gl::BACK, 0xf0);
+        let state = GLStateSnapshot::capture(gl, GLStateGroups::WRITE_MASKS);
+        gl.stencil_mask(0xff);
+        state.restore(gl);
+        let mut stencil_mask = [0];
+        gl.get_parameter_i32_slice(gl::STENCIL_WRITEMASK, &mut stencil_mask);
+        assert_eq!(stencil_mask[0] & 0xff, 0x0f);
+        gl.get_parameter_i32_slice(gl::STENCIL_BACK_WRITEMASK, &mut stencil_mask);
+        assert_eq!(stencil_mask[0] & 0xff, 0xf0);
         check_gl(gl);
-        gl.blit_framebuffer(
-            0,
+    }
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that an application-owned texture can be wrapped in a surface, rendered to, and read from
+// a surface texture, and that destroying the surface leaves the texture alone.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_surface_from_gl_texture() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    unsafe {
+        let texture = env.gl.create_texture().unwrap();
+        env.gl.bind_texture(gl::TEXTURE_2D, Some(texture));
+        env.gl.tex_image_2d(
+            gl::TEXTURE_2D,
             0,
+            gl::RGBA as i32,
             640,
             480,
             0,
-            0,
-            640,
-            480,
-            gl::COLOR_BUFFER_BIT,
-            gl::NEAREST,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            PixelUnpackData::Slice(None),
         );
-        check_gl(gl);
-    }
-}
+        env.gl.bind_texture(gl::TEXTURE_2D, None);
+        check_gl(&env.gl);
 
-fn make_fbo(gl: &Gl, texture_target: u32, texture: Option<Texture>) -> Framebuffer {
-    unsafe {
-        let framebuffer_object = gl.create_framebuffer().unwrap();
-        check_gl(gl);
-        gl.bind_framebuffer(gl::FRAMEBUFFER, Some(framebuffer_object));
-        check_gl(gl);
-        gl.framebuffer_texture_2d(
-            gl::FRAMEBUFFER,
-            gl::COLOR_ATTACHMENT0,
-            texture_target,
+        let texture_surface = match env.device.create_surface_from_gl_texture(
+            &env.context,
+            &Size2D::new(640, 480),
             texture,
-            0,
-        );
+        ) {
+            Ok(surface) => surface,
+            Err(Error::UnsupportedOnThisPlatform) => {
+                env.gl.delete_texture(texture);
+                env.device.destroy_context(&mut env.context).unwrap();
+                return;
+            }
+            Err(err) => panic!("Failed to create surface from texture: {:?}", err),
+        };
+
+        // Render to the texture through the surface.
+        let mut main_surface = env
+            .device
+            .unbind_surface_from_context(&mut env.context)
+            .unwrap()
+            .unwrap();
+        env.device
+            .bind_surface_to_context(&mut env.context, texture_surface)
+            .unwrap();
+        bind_context_fbo(&env.gl, &env.device, &env.context);
+        clear(&env.gl, &[0, 255, 0, 255]);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+        let texture_surface = env
+            .device
+            .unbind_surface_from_context(&mut env.context)
+            .unwrap()
+            .unwrap();
+
+        // The texture itself should contain what we rendered.
+        let texture_framebuffer_object = make_fbo(&env.gl, gl::TEXTURE_2D, Some(texture));
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+        env.gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        env.gl.delete_framebuffer(texture_framebuffer_object);
+
+        // So should a surface texture wrapping the surface.
+        let surface_texture = env
+            .device
+            .create_surface_texture(&mut env.context, texture_surface)
+            .unwrap();
+        let surface_texture_framebuffer_object = make_fbo(
+            &env.gl,
+            env.device.surface_gl_texture_target(),
+            env.device.surface_texture_object(&surface_texture),
+        );
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+        env.gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        env.gl
+            .delete_framebuffer(surface_texture_framebuffer_object);
+        check_gl(&env.gl);
+
+        // Destroying the surface must not delete the texture.
+        let mut texture_surface = env
+            .device
+            .destroy_surface_texture(&mut env.context, surface_texture)
+            .unwrap();
+        env.device
+            .destroy_surface(&mut env.context, &mut texture_surface)
+            .unwrap();
+        assert!(env.gl.is_texture(texture));
+
+        env.gl.delete_texture(texture);
+        env.device
+            .destroy_surface(&mut env.context, &mut main_surface)
+            .unwrap();
+        env.device.destroy_context(&mut env.context).unwrap();
+    }
+}
+
+// Tests that a surface's EGL image can be exported and imported into another surface that shares
+// its contents.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_native_surface_round_trip() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let mut exported_surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    let native_surface = match env.device.native_surface(&exported_surface) {
+        Ok(native_surface) => native_surface,
+        Err(Error::RequiredExtensionUnavailable) => {
+            env.device
+                .destroy_surface(&mut env.context, &mut exported_surface)
+                .unwrap();
+            env.device.destroy_context(&mut env.context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to get native surface: {:?}", err),
+    };
+    assert_eq!(native_surface.size, Size2D::new(640, 480));
+
+    // Render to the image through an imported surface.
+    let imported_surface = unsafe {
+        env.device
+            .create_surface_from_native_surface(&env.context, native_surface)
+            .unwrap()
+    };
+    env.device
+        .bind_surface_to_context(&mut env.context, imported_surface)
+        .unwrap();
+    bind_context_fbo(&env.gl, &env.device, &env.context);
+    clear(&env.gl, &[0, 255, 0, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+    let mut imported_surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    env.device
+        .destroy_surface(&mut env.context, &mut imported_surface)
+        .unwrap();
+
+    // The exported surface should see what we rendered, and its image should have survived the
+    // destruction of the imported surface.
+    let surface_texture = env
+        .device
+        .create_surface_texture(&mut env.context, exported_surface)
+        .unwrap();
+    unsafe {
+        let framebuffer_object = make_fbo(
+            &env.gl,
+            env.device.surface_gl_texture_target(),
+            env.device.surface_texture_object(&surface_texture),
+        );
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+        env.gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        env.gl.delete_framebuffer(framebuffer_object);
+        check_gl(&env.gl);
+    }
+
+    let mut exported_surface = env
+        .device
+        .destroy_surface_texture(&mut env.context, surface_texture)
+        .unwrap();
+    env.device
+        .destroy_surface(&mut env.context, &mut exported_surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that a surface exported to a handle, as if for another process, can be imported again with
+// its contents intact.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_surface_handle_round_trip() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    clear(&env.gl, &[255, 0, 0, 255]);
+    let mut exported_surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    let handle = env
+        .device
+        .export_surface(&env.context, &exported_surface)
+        .unwrap();
+    assert_eq!(handle.size(), Size2D::new(640, 480));
+    env.device
+        .destroy_surface(&mut env.context, &mut exported_surface)
+        .unwrap();
+
+    let imported_surface = env
+        .device
+        .create_surface_from_handle(&env.context, &handle)
+        .unwrap();
+    drop(handle);
+    let surface_texture = env
+        .device
+        .create_surface_texture(&mut env.context, imported_surface)
+        .unwrap();
+    unsafe {
+        let framebuffer_object = make_fbo(
+            &env.gl,
+            env.device.surface_gl_texture_target(),
+            env.device.surface_texture_object(&surface_texture),
+        );
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+        env.gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        env.gl.delete_framebuffer(framebuffer_object);
+        check_gl(&env.gl);
+    }
+
+    let mut imported_surface = env
+        .device
+        .destroy_surface_texture(&mut env.context, surface_texture)
+        .unwrap();
+    env.device
+        .destroy_surface(&mut env.context, &mut imported_surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that shared memory handles whose size doesn't match their memfd, as another process might
+// send, are rejected instead of being read out of bounds.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_surface_handle_bad_size() {
+    use super::surface::SurfaceHandle;
+    use std::fs::File;
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let bad_sizes = [
+        Size2D::new(0, 480),
+        Size2D::new(-640, -480),
+        Size2D::new(i32::MAX, i32::MAX),
+        Size2D::new(640, 480),
+    ];
+    for &size in &bad_sizes {
+        // One row of pixels, which is too short for all of the sizes above.
+        let fd = unsafe { libc::memfd_create(c"surfman-test".as_ptr(), libc::MFD_CLOEXEC) };
+        assert!(fd >= 0);
+        let file = File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+        file.set_len(640 * 4).unwrap();
+        let handle = SurfaceHandle::SharedMemory {
+            size,
+            fd: file.into(),
+        };
+        match env.device.create_surface_from_handle(&env.context, &handle) {
+            Err(Error::SurfaceImportFailed(WindowingApiError::BadParameter)) => {}
+            Err(err) => panic!("Unexpected error importing a bad handle: {:?}", err),
+            Ok(_) => panic!("Importing a handle with a bad size should fail!"),
+        }
+    }
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that memory and semaphores exported from Vulkan can be imported and rendered to. This needs
+// a Vulkan driver for the same device as the context, such as lavapipe alongside llvmpipe.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_external_memory_import() {
+    use super::surface::ExternalMemory;
+    use crate::platform::unix::vulkan_test_support::VulkanDevice;
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let extensions = env.gl.supported_extensions();
+    let memory_supported = extensions.contains("GL_EXT_memory_object_fd");
+    let semaphores_supported = extensions.contains("GL_EXT_semaphore_fd");
+    let vulkan = match gl_device_and_driver_uuids(&env.device, &env.context) {
+        Some((device_uuid, driver_uuid)) if memory_supported => {
+            VulkanDevice::open(&device_uuid, &driver_uuid)
+        }
+        _ => None,
+    };
+    let vulkan = match vulkan {
+        Some(vulkan) => vulkan,
+        None => {
+            env.device.destroy_context(&mut env.context).unwrap();
+            return;
+        }
+    };
+
+    // The context advertises the extension, so importing memory from a matching device must work.
+    let (image, fd) = vulkan.export_image(640, 480);
+    let memory = ExternalMemory {
+        fd,
+        size: image.size,
+        offset: 0,
+        dedicated: true,
+    };
+    let imported_surface = env
+        .device
+        .create_surface_from_external_memory(&env.context, &Size2D::new(640, 480), memory)
+        .unwrap();
+    vulkan.destroy_image(image);
+
+    let original_surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    env.device
+        .bind_surface_to_context(&mut env.context, imported_surface)
+        .unwrap();
+    env.device.make_context_current(&env.context).unwrap();
+    bind_context_fbo(&env.gl, &env.device, &env.context);
+    clear(&env.gl, &[0, 255, 0, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+    check_gl(&env.gl);
+
+    if semaphores_supported {
+        let mut semaphore = env
+            .device
+            .import_semaphore(&env.context, vulkan.export_semaphore())
+            .unwrap();
+        env.device
+            .signal_semaphore(&env.context, &semaphore, None)
+            .unwrap();
+        env.device
+            .destroy_semaphore(&env.context, &mut semaphore)
+            .unwrap();
+        check_gl(&env.gl);
+    }
+
+    let mut imported_surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    env.device
+        .destroy_surface(&mut env.context, &mut imported_surface)
+        .unwrap();
+    env.device
+        .bind_surface_to_context(&mut env.context, original_surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that memory and semaphores that weren't exported by another API are rejected cleanly, or
+// with `RequiredExtensionUnavailable` where the context can't import them at all, and that the
+// context remains usable afterwards.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_external_memory_import_invalid() {
+    use super::surface::ExternalMemory;
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let extensions = env.gl.supported_extensions();
+    let memory_supported = extensions.contains("GL_EXT_memory_object_fd");
+    let semaphores_supported = extensions.contains("GL_EXT_semaphore_fd");
+
+    let memory_size = 640 * 480 * 4;
+    let create_memfd = || unsafe {
+        let fd = libc::memfd_create(c"surfman-test".as_ptr(), libc::MFD_CLOEXEC);
+        assert!(fd >= 0);
+        assert_eq!(libc::ftruncate(fd, memory_size as libc::off_t), 0);
+        OwnedFd::from_raw_fd(fd)
+    };
+
+    let memory = ExternalMemory {
+        fd: create_memfd(),
+        size: memory_size,
+        offset: 0,
+        dedicated: false,
+    };
+    match env.device.create_surface_from_external_memory(
+        &env.context,
+        &Size2D::new(640, 480),
+        memory,
+    ) {
+        Err(Error::SurfaceImportFailed(_)) if memory_supported => {}
+        Err(Error::RequiredExtensionUnavailable) if !memory_supported => {}
+        Err(err) => panic!("Unexpected error importing memory: {:?}", err),
+        Ok(_) => panic!("Importing a memfd as external memory should fail!"),
+    }
+
+    // Drivers differ in whether they reject such semaphores up front, so only check the case
+    // where they can't be imported at all.
+    if !semaphores_supported {
+        match env.device.import_semaphore(&env.context, create_memfd()) {
+            Err(Error::RequiredExtensionUnavailable) => {}
+            Err(err) => panic!("Unexpected error importing semaphore: {:?}", err),
+            Ok(_) => panic!("Importing a semaphore should fail without `GL_EXT_semaphore_fd`!"),
+        }
+    }
+
+    // The context should still render to its own surface.
+    env.device.make_context_current(&env.context).unwrap();
+    bind_context_fbo(&env.gl, &env.device, &env.context);
+    clear(&env.gl, &[0, 0, 255, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 0, 255, 255]);
+    check_gl(&env.gl);
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that the function table surfman loaded for a context can be used to render to it.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_context_gl() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    // The function table makes the context current by itself.
+    env.device.make_no_context_current().unwrap();
+    let gl = env.device.context_gl(&env.context).unwrap();
+    bind_context_fbo(&gl, &env.device, &env.context);
+    clear(&gl, &[255, 0, 255, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&gl), [255, 0, 255, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 255, 255]);
+    check_gl(&gl);
+    drop(gl);
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that contexts created without error checking still render, and that their descriptors
+// report whether the flag was honored.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_no_error_context() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    // `GL_CONTEXT_FLAGS` can't report the flag before OpenGL 3.0 and OpenGL ES 3.2, so old versions
+    // have to round-trip it too.
+    for version in [GLVersion::new(2, 0), GLVersion::new(3, 0)] {
+        let context_descriptor = device
+            .create_context_descriptor(&ContextAttributes {
+                version,
+                flags: ContextAttributeFlags::NO_ERROR,
+                priority: ContextPriority::Medium,
+            })
+            .unwrap();
+        let honored = device
+            .context_descriptor_attributes(&context_descriptor)
+            .flags
+            .contains(ContextAttributeFlags::NO_ERROR);
+
+        let mut context = device.create_context(&context_descriptor, None).unwrap();
+        let surface = make_surface(&mut device, &context);
+        device
+            .bind_surface_to_context(&mut context, surface)
+            .unwrap();
+        device.make_context_current(&context).unwrap();
+
+        let actual_descriptor = device.context_descriptor(&context);
+        assert_eq!(
+            device
+                .context_descriptor_attributes(&actual_descriptor)
+                .flags
+                .contains(ContextAttributeFlags::NO_ERROR),
+            honored
+        );
+
+        let gl = device.context_gl(&context).unwrap();
+        bind_context_fbo(&gl, &device, &context);
+        clear(&gl, &[0, 0, 255, 255]);
+        assert_eq!(get_pixel_from_bottom_row(&gl), [0, 0, 255, 255]);
+        drop(gl);
+
+        device.destroy_context(&mut context).unwrap();
+    }
+}
+
+// Tests that context priorities are either requested or ignored, and that the granted priority can
+// be queried afterward.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_context_priority() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    for &priority in &[ContextPriority::Low, ContextPriority::High] {
+        let context_descriptor = device
+            .create_context_descriptor(&ContextAttributes {
+                version: GLVersion::new(3, 0),
+                flags: ContextAttributeFlags::empty(),
+                priority,
+            })
+            .unwrap();
+        let requested = device
+            .context_descriptor_attributes(&context_descriptor)
+            .priority;
+        assert!(requested == priority || requested == ContextPriority::Medium);
+
+        let mut context = device.create_context(&context_descriptor, None).unwrap();
+        let actual_descriptor = device.context_descriptor(&context);
+        let granted = device
+            .context_descriptor_attributes(&actual_descriptor)
+            .priority;
+        if requested == ContextPriority::Medium {
+            assert_eq!(granted, ContextPriority::Medium);
+        }
+
+        device.destroy_context(&mut context).unwrap();
+    }
+}
+
+// Tests that the directory-backed shader cache returns what was stored under each key.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_directory_shader_cache() {
+    let directory =
+        std::env::temp_dir().join(format!("surfman-shader-cache-{}", std::process::id()));
+    let cache = DirectoryShaderCache::new(&directory);
+
+    assert_eq!(cache.get(b"key"), None);
+    cache.set(b"key", b"value");
+    assert_eq!(cache.get(b"key").as_deref(), Some(&b"value"[..]));
+    cache.set(b"key", b"");
+    assert_eq!(cache.get(b"key").as_deref(), Some(&b""[..]));
+    assert_eq!(cache.get(b"other key"), None);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+// Tests that the driver stores and looks up compiled programs in the shader cache, on displays
+// that support `EGL_ANDROID_blob_cache`.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_shader_cache_callbacks() {
+    use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+    use crate::platform::generic::egl::ffi::EGLDisplayExtensionFunctions;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct CountingShaderCache {
+        entries: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+        gets: AtomicUsize,
+        sets: AtomicUsize,
+    }
+
+    impl ShaderCache for CountingShaderCache {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.gets.fetch_add(1, Ordering::SeqCst);
+            self.entries.lock().unwrap().get(key).cloned()
+        }
+        fn set(&self, key: &[u8], value: &[u8]) {
+            self.sets.fetch_add(1, Ordering::SeqCst);
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(key.to_vec(), value.to_vec());
+        }
+    }
+
+    // The cache is installed when the display is initialized, so set it before connecting.
+    let cache = Arc::new(CountingShaderCache::default());
+    crate::set_shader_cache(cache.clone());
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+    let egl_display = EGL_FUNCTIONS.with(|egl| unsafe { egl.GetCurrentDisplay() });
+    let blob_cache_supported = EGLDisplayExtensionFunctions::get(egl_display)
+        .SetBlobCacheFuncsANDROID
+        .is_some();
+
+    let version = match env.device.gl_api() {
+        GLApi::GL => "#version 130",
+        GLApi::GLES => "#version 300 es\nprecision mediump float;",
+    };
+    // Make the sources unique, so that nothing cached by an earlier run can satisfy the lookup
+    // before the driver stores a new entry.
+    let nonce = format!(
+        "// {}-{:?}",
+        std::process::id(),
+        std::time::SystemTime::now()
+    );
+    let vertex_source = format!(
+        "{}\n{}\nin vec2 position;\nvoid main() {{ gl_Position = vec4(position, 0.0, 1.0); }}\n",
+        version, nonce
+    );
+    let fragment_source = format!(
+        "{}\n{}\nout vec4 color;\nvoid main() {{ color = vec4(1.0, 0.0, 0.0, 1.0); }}\n",
+        version, nonce
+    );
+
+    unsafe {
+        let gl = &env.gl;
+        let program = gl.create_program().unwrap();
+        let mut shaders = vec![];
+        for (shader_type, source) in [
+            (gl::VERTEX_SHADER, &vertex_source),
+            (gl::FRAGMENT_SHADER, &fragment_source),
+        ] {
+            let shader = gl.create_shader(shader_type).unwrap();
+            gl.shader_source(shader, source);
+            gl.compile_shader(shader);
+            assert!(
+                gl.get_shader_compile_status(shader),
+                "{}",
+                gl.get_shader_info_log(shader)
+            );
+            gl.attach_shader(program, shader);
+            shaders.push(shader);
+        }
+        gl.link_program(program);
+        assert!(
+            gl.get_program_link_status(program),
+            "{}",
+            gl.get_program_info_log(program)
+        );
+        for shader in shaders {
+            gl.detach_shader(program, shader);
+            gl.delete_shader(shader);
+        }
+        gl.delete_program(program);
+        check_gl(gl);
+    }
+
+    if blob_cache_supported {
+        assert!(cache.gets.load(Ordering::SeqCst) > 0);
+        assert!(cache.sets.load(Ordering::SeqCst) > 0);
+    }
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that owned contexts and surfaces destroy themselves when dropped, in any order and while
+// unwinding.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_owned_context_and_surface() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+    let context_descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::empty(),
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+    let device = SharedDevice::new(device);
+
+    let context = device.create_context(&context_descriptor, None).unwrap();
+    let surface_type = SurfaceType::Generic {
+        size: Size2D::new(640, 480),
+    };
+    let surface = context
+        .create_surface(SurfaceAccess::GPUOnly, surface_type.clone())
+        .unwrap();
+    if context.bind_surface(surface).is_err() {
+        panic!("Failed to bind surface!");
+    }
+    context.make_current().unwrap();
+    context.with(|device, context| {
+        let gl = device.context_gl(context).unwrap();
+        bind_context_fbo(&gl, device, context);
+        clear(&gl, &[255, 0, 0, 255]);
+        assert_eq!(get_pixel_from_bottom_row(&gl), [255, 0, 0, 255]);
+    });
+
+    // The context outlives its handles for as long as one of its surfaces is alive.
+    let surface = context
+        .create_surface(SurfaceAccess::GPUOnly, surface_type.clone())
+        .unwrap();
+    drop(context);
+    drop(surface);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let context = device.create_context(&context_descriptor, None).unwrap();
+        let _surface = context
+            .create_surface(SurfaceAccess::GPUOnly, surface_type)
+            .unwrap();
+        panic!("Unwinding past an owned context and surface");
+    }));
+    assert!(result.is_err());
+}
+
+// Tests that surfaces and surface textures can be queued for destruction from other threads, and
+// that the queue is drained explicitly or when an owned context is made current.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_destruction_queue() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let queue = DestructionQueue::<Device>::new();
+    let surface = make_surface(&mut env.device, &env.context);
+    let other_surface = make_surface(&mut env.device, &env.context);
+    let surface_texture = match env
+        .device
+        .create_surface_texture(&mut env.context, other_surface)
+    {
+        Ok(surface_texture) => surface_texture,
+        Err(_) => panic!("Failed to create surface texture!"),
+    };
+    let thread_queue = queue.clone();
+    thread::spawn(move || thread_queue.push_surface(surface))
+        .join()
+        .unwrap();
+    queue.push_surface_texture(surface_texture);
+    assert!(!queue.is_empty());
+
+    queue.drain(&env.device, &mut env.context).unwrap();
+    assert!(queue.is_empty());
+
+    // The environment's context is still current.
+    bind_context_fbo(&env.gl, &env.device, &env.context);
+    clear(&env.gl, &[0, 255, 0, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+
+    // Surfaces created for other contexts stay queued until their own context drains them.
+    let context_descriptor = env.device.context_descriptor(&env.context);
+    let mut other_context = env
+        .device
+        .create_context(&context_descriptor, None)
+        .unwrap();
+    queue.push_surface(make_surface(&mut env.device, &other_context));
+    queue.drain(&env.device, &mut env.context).unwrap();
+    assert!(!queue.is_empty());
+    queue.drain(&env.device, &mut other_context).unwrap();
+    assert!(queue.is_empty());
+    env.device.destroy_context(&mut other_context).unwrap();
+
+    let device = SharedDevice::new(
+        env.device
+            .connection()
+            .create_device(&env.device.adapter())
+            .unwrap(),
+    );
+    let context = device.create_context(&context_descriptor, None).unwrap();
+    let surface = context
+        .create_surface(
+            SurfaceAccess::GPUOnly,
+            SurfaceType::Generic {
+                size: Size2D::new(640, 480),
+            },
+        )
+        .unwrap();
+    context
+        .destruction_queue()
+        .push_surface(surface.into_inner());
+    context.make_current().unwrap();
+    assert!(context.destruction_queue().is_empty());
+    drop(context);
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that a context can read from one surface while drawing to another.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_read_surface() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    clear(&env.gl, &[255, 0, 0, 255]);
+    let read_surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    let draw_surface = make_surface(&mut env.device, &env.context);
+    env.device
+        .bind_surface_to_context(&mut env.context, draw_surface)
+        .unwrap();
+    match env
+        .device
+        .bind_read_surface_to_context(&mut env.context, read_surface)
+    {
+        Ok(()) => {}
+        Err((Error::UnsupportedOnThisPlatform, mut read_surface)) => {
+            env.device
+                .destroy_surface(&mut env.context, &mut read_surface)
+                .unwrap();
+            env.device.destroy_context(&mut env.context).unwrap();
+            return;
+        }
+        Err((err, _)) => panic!("Failed to bind read surface: {:?}", err),
+    }
+
+    // The read and draw surfaces' framebuffers should be bound without us binding anything.
+    unsafe {
+        clear(&env.gl, &[0, 255, 0, 255]);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+        env.gl.blit_framebuffer(
+            0,
+            0,
+            640,
+            480,
+            0,
+            0,
+            640,
+            480,
+            gl::COLOR_BUFFER_BIT,
+            gl::NEAREST,
+        );
+        check_gl(&env.gl);
+
+        // Making the context current again should rebind them.
+        env.device.make_context_current(&env.context).unwrap();
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+        check_gl(&env.gl);
+    }
+
+    let mut read_surface = env
+        .device
+        .unbind_read_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    // Now the draw surface is read from, and it should hold the blitted pixels.
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+    check_gl(&env.gl);
+    assert!(env
+        .device
+        .unbind_read_surface_from_context(&mut env.context)
+        .unwrap()
+        .is_none());
+    env.device
+        .destroy_surface(&mut env.context, &mut read_surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that closing other connections, which may share our display, doesn't tear it down, and
+// that the display is terminated once the last connection goes away.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_dropping_other_connections() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+    #[cfg(free_unix)]
+    let egl_display = {
+        use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+        EGL_FUNCTIONS.with(|egl| unsafe { egl.GetCurrentDisplay() })
+    };
+
+    for _ in 0..4 {
+        let connection = Connection::new().unwrap();
+        let adapter = connection.create_low_power_adapter().unwrap();
+        drop(connection.create_device(&adapter).unwrap());
+    }
+
+    env.device.make_context_current(&env.context).unwrap();
+    clear(&env.gl, &[255, 0, 0, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+    check_gl(&env.gl);
+
+    env.device.destroy_context(&mut env.context).unwrap();
+
+    #[cfg(free_unix)]
+    {
+        use crate::platform::generic::egl::device::is_owned_egl_display;
+        assert!(is_owned_egl_display(egl_display));
+        drop(env);
+        assert!(!is_owned_egl_display(egl_display));
+    }
+}
+
+// Tests that contexts on displays with `EGL_KHR_surfaceless_context` are made current without a
+// dummy pbuffer, and still render to bound generic surfaces.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_surfaceless_context() {
+    use crate::egl;
+    use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+    use crate::platform::generic::egl::ffi::EGLDisplayExtensionFunctions;
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+    let egl_display = EGL_FUNCTIONS.with(|egl| unsafe { egl.GetCurrentDisplay() });
+    if !EGLDisplayExtensionFunctions::get(egl_display).surfaceless_context {
+        env.device.destroy_context(&mut env.context).unwrap();
+        return;
+    }
+    let current_draw_surface =
+        || EGL_FUNCTIONS.with(|egl| unsafe { egl.GetCurrentSurface(egl::DRAW as i32) });
+
+    // The generic surface is rendered to through its framebuffer object, with no EGL surface.
+    env.device.make_context_current(&env.context).unwrap();
+    assert_eq!(current_draw_surface(), egl::NO_SURFACE);
+    bind_context_fbo(&env.gl, &env.device, &env.context);
+    clear(&env.gl, &[0, 0, 255, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 0, 255, 255]);
+    check_gl(&env.gl);
+
+    // Without any surface bound, the context stays current with no EGL surface.
+    let mut surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    env.device.make_context_current(&env.context).unwrap();
+    assert_eq!(current_draw_surface(), egl::NO_SURFACE);
+
+    env.device
+        .destroy_surface(&mut env.context, &mut surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that a broadcast swap chain lets every registered consumer read its front buffer at the
+// same time, and only recycles it once all of them have released it.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_swap_chain_broadcast() {
+    use crate::chains::{BroadcastSwapChainAPI, PreserveBuffer, SwapChain};
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let swap_chain =
+        SwapChain::create_attached(&mut env.device, &mut env.context, SurfaceAccess::GPUOnly)
+            .unwrap();
+    let main_view = swap_chain.register_consumer(&env.device, &env.context);
+    let inspector = swap_chain.register_consumer(&env.device, &env.context);
+    assert!(swap_chain
+        .acquire_front_buffer(main_view, &env.device, &mut env.context)
+        .unwrap()
+        .is_none());
+
+    clear(&env.gl, &[0, 255, 0, 255]);
+    swap_chain
+        .swap_buffers(&mut env.device, &mut env.context, PreserveBuffer::No)
+        .unwrap();
+    assert!(swap_chain.take_pending_surface().is_none());
+
+    // Both consumers read the same front buffer at once.
+    let main_view_texture = swap_chain
+        .acquire_front_buffer(main_view, &env.device, &mut env.context)
+        .unwrap();
+    let inspector_texture = swap_chain
+        .acquire_front_buffer(inspector, &env.device, &mut env.context)
+        .unwrap();
+    assert!(main_view_texture.is_some());
+    assert_eq!(main_view_texture, inspector_texture);
+    unsafe {
+        let texture_target = env.device.surface_gl_texture_target();
+        let framebuffer_object = make_fbo(&env.gl, texture_target, inspector_texture);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+        env.gl.delete_framebuffer(framebuffer_object);
+    }
+
+    // A newer front buffer is available while the inspector still reads the old one.
+    swap_chain
+        .swap_buffers(&mut env.device, &mut env.context, PreserveBuffer::No)
+        .unwrap();
+    let newer_texture = swap_chain
+        .acquire_front_buffer(main_view, &env.device, &mut env.context)
+        .unwrap();
+    assert!(newer_texture.is_some());
+    assert_ne!(newer_texture, inspector_texture);
+    assert_eq!(swap_chain.memory_report(&env.device).recycled_surfaces, 0);
+
+    // Once the inspector releases the old front buffer too, it is recycled.
+    swap_chain
+        .release_front_buffer(inspector, &env.device, &mut env.context)
+        .unwrap();
+    assert_eq!(swap_chain.memory_report(&env.device).recycled_surfaces, 1);
+
+    // The swap chain can't be destroyed while a consumer is still reading from it.
+    assert!(swap_chain
+        .destroy(&mut env.device, &mut env.context)
+        .is_err());
+    swap_chain
+        .release_front_buffer(main_view, &env.device, &mut env.context)
+        .unwrap();
+
+    swap_chain.unregister_consumer(main_view);
+    swap_chain.unregister_consumer(inspector);
+    swap_chain
+        .destroy(&mut env.device, &mut env.context)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that consumers in different contexts can read the same front buffer of a broadcast swap
+// chain at the same time.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_swap_chain_broadcast_contexts() {
+    use crate::chains::{BroadcastSwapChainAPI, PreserveBuffer, SwapChain};
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+    let mut inspector_context = env
+        .device
+        .create_context(&env.context_descriptor, None)
+        .unwrap();
+    env.device.make_context_current(&env.context).unwrap();
+
+    let swap_chain =
+        SwapChain::create_attached(&mut env.device, &mut env.context, SurfaceAccess::GPUOnly)
+            .unwrap();
+    let main_view = swap_chain.register_consumer(&env.device, &env.context);
+    let inspector = swap_chain.register_consumer(&env.device, &inspector_context);
+
+    clear(&env.gl, &[0, 255, 0, 255]);
+    swap_chain
+        .swap_buffers(&mut env.device, &mut env.context, PreserveBuffer::No)
+        .unwrap();
+
+    // Each consumer reads the front buffer in its own context.
+    let main_view_texture = swap_chain
+        .acquire_front_buffer(main_view, &env.device, &mut env.context)
+        .unwrap();
+    let inspector_texture = swap_chain
+        .acquire_front_buffer(inspector, &env.device, &mut inspector_context)
+        .unwrap();
+    assert!(main_view_texture.is_some());
+    assert!(inspector_texture.is_some());
+    assert!(swap_chain
+        .acquire_front_buffer(inspector, &env.device, &mut env.context)
+        .is_err());
+    let texture_target = env.device.surface_gl_texture_target();
+    unsafe {
+        let framebuffer_object = make_fbo(&env.gl, texture_target, main_view_texture);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+        env.gl.delete_framebuffer(framebuffer_object);
+    }
+    {
+        let inspector_gl = env.device.context_gl(&inspector_context).unwrap();
+        unsafe {
+            let framebuffer_object = make_fbo(&inspector_gl, texture_target, inspector_texture);
+            assert_eq!(get_pixel_from_bottom_row(&inspector_gl), [0, 255, 0, 255]);
+            inspector_gl.delete_framebuffer(framebuffer_object);
+        }
+    }
+
+    // Both copies are recycled once both consumers have released them and a newer front buffer
+    // exists.
+    swap_chain
+        .swap_buffers(&mut env.device, &mut env.context, PreserveBuffer::No)
+        .unwrap();
+    swap_chain
+        .release_front_buffer(main_view, &env.device, &mut env.context)
+        .unwrap();
+    swap_chain
+        .release_front_buffer(inspector, &env.device, &mut inspector_context)
+        .unwrap();
+    assert_eq!(swap_chain.memory_report(&env.device).recycled_surfaces, 2);
+
+    swap_chain.unregister_consumer(main_view);
+    swap_chain.unregister_consumer(inspector);
+    swap_chain
+        .destroy(&mut env.device, &mut env.context)
+        .unwrap();
+    env.device.destroy_context(&mut inspector_context).unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that swap chains keep the old contents of the back buffer across resizes and swaps when
+// asked to.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_swap_chain_preserve_buffer() {
+    use crate::chains::{PreserveBuffer, SwapChain};
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let swap_chain =
+        SwapChain::create_attached(&mut env.device, &mut env.context, SurfaceAccess::GPUOnly)
+            .unwrap();
+
+    // Draw a red bottom-left quadrant on green, which clipping and scaling treat differently.
+    let draw_pattern = |gl: &Gl, width: i32, height: i32| unsafe {
+        clear(gl, &[0, 255, 0, 255]);
+        gl.scissor(0, 0, width / 2, height / 2);
+        gl.enable(gl::SCISSOR_TEST);
+        clear(gl, &[255, 0, 0, 255]);
+        gl.disable(gl::SCISSOR_TEST);
+    };
+    draw_pattern(&env.gl, 640, 480);
+
+    // Shrinking with scaling shrinks the quadrant too, where clipping would keep only red.
+    swap_chain
+        .resize(
+            &mut env.device,
+            &mut env.context,
+            Size2D::new(320, 240),
+            PreserveBuffer::Scaled,
+        )
+        .unwrap();
+    bind_context_fbo(&env.gl, &env.device, &env.context);
+    assert_eq!(get_pixel(&env.gl, 0, 0), [255, 0, 0, 255]);
+    assert_eq!(get_pixel(&env.gl, 300, 200), [0, 255, 0, 255]);
+
+    // Growing with clipping keeps the contents at the origin, where scaling would grow the
+    // quadrant, and clears the rest.
+    draw_pattern(&env.gl, 320, 240);
+    swap_chain
+        .resize(
+            &mut env.device,
+            &mut env.context,
+            Size2D::new(640, 480),
+            PreserveBuffer::Yes,
+        )
+        .unwrap();
+    bind_context_fbo(&env.gl, &env.device, &env.context);
+    assert_eq!(get_pixel(&env.gl, 0, 0), [255, 0, 0, 255]);
+    assert_eq!(get_pixel(&env.gl, 200, 100), [0, 255, 0, 255]);
+    assert_eq!(get_pixel(&env.gl, 400, 0), [0, 0, 0, 0]);
+    assert_eq!(get_pixel(&env.gl, 400, 300), [0, 0, 0, 0]);
+
+    // Swapping keeps the contents in the new back buffer.
+    swap_chain
+        .swap_buffers(&mut env.device, &mut env.context, PreserveBuffer::Yes)
+        .unwrap();
+    bind_context_fbo(&env.gl, &env.device, &env.context);
+    assert_eq!(get_pixel(&env.gl, 0, 0), [255, 0, 0, 255]);
+    assert_eq!(get_pixel(&env.gl, 200, 100), [0, 255, 0, 255]);
+    assert_eq!(get_pixel(&env.gl, 400, 300), [0, 0, 0, 0]);
+
+    swap_chain
+        .destroy(&mut env.device, &mut env.context)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that swap chains report the surfaces they hold, and release idle ones when trimmed or
+// when the collection exceeds its memory budget.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_swap_chains_memory_budget() {
+    use crate::chains::{PreserveBuffer, SwapChainAPI, SwapChains, SwapChainsAPI};
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let swap_chains: SwapChains<u32, Device> = SwapChains::new();
+    for id in 0..2 {
+        swap_chains
+            .create_detached_swap_chain(
+                id,
+                Size2D::new(64, 64),
+                &mut env.device,
+                &mut env.context,
+                SurfaceAccess::GPUOnly,
+            )
+            .unwrap();
+    }
+    let background = swap_chains.get(0).unwrap();
+    let foreground = swap_chains.get(1).unwrap();
+    background
+        .swap_buffers(&mut env.device, &mut env.context, PreserveBuffer::No)
+        .unwrap();
+    let surface = background.take_surface().unwrap();
+    background.recycle_surface(surface);
+
+    let report = background.memory_report(&env.device);
+    assert_eq!(report.surfaces, 2);
+    assert_eq!(report.bytes, 2 * 64 * 64 * 4);
+    assert_eq!(report.recycled_surfaces, 1);
+    assert_eq!(swap_chains.memory_reports(&env.device).len(), 2);
+
+    // A back buffer taken by the producer is no longer held by the swap chain.
+    let surface_texture = background
+        .take_surface_texture(&env.device, &mut env.context)
+        .unwrap();
+    assert_eq!(background.memory_report(&env.device).surfaces, 1);
+    background
+        .recycle_surface_texture(&env.device, &mut env.context, surface_texture)
+        .unwrap();
+
+    // Swapping the foreground swap chain over budget trims the background one.
+    swap_chains.set_memory_budget(Some(3 * 64 * 64 * 4));
+    foreground
+        .swap_buffers(&mut env.device, &mut env.context, PreserveBuffer::No)
+        .unwrap();
+    let report = background.memory_report(&env.device);
+    assert_eq!(report.surfaces, 1);
+    assert_eq!(report.recycled_surfaces, 0);
+    assert!(swap_chains
+        .trim_to_memory_budget(&mut env.device, &mut env.context)
+        .unwrap());
+
+    // The swap chain that swaps over budget ends up within it too, and one that grows over it
+    // gives up its own idle surfaces.
+    let total_bytes = |device: &Device| -> usize {
+        swap_chains
+            .memory_reports(device)
+            .iter()
+            .map(|(_, report)| report.bytes)
+            .sum()
+    };
+    let surface = foreground.take_surface().unwrap();
+    foreground.recycle_surface(surface);
+    foreground
+        .swap_buffers(&mut env.device, &mut env.context, PreserveBuffer::No)
+        .unwrap();
+    assert_eq!(foreground.memory_report(&env.device).recycled_surfaces, 0);
+    assert!(total_bytes(&env.device) <= 3 * 64 * 64 * 4);
+    let surface = foreground.take_surface().unwrap();
+    foreground.recycle_surface(surface);
+    assert_eq!(foreground.memory_report(&env.device).recycled_surfaces, 1);
+    foreground
+        .resize(
+            &mut env.device,
+            &mut env.context,
+            Size2D::new(128, 128),
+            PreserveBuffer::No,
+        )
+        .unwrap();
+    assert_eq!(foreground.memory_report(&env.device).recycled_surfaces, 0);
+
+    swap_chains
+        .destroy_all(&mut env.device, &mut env.context)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+fn bind_context_fbo(gl: &Gl, device: &Device, context: &Context) {
+    unsafe {
+        gl.bind_framebuffer(gl::FRAMEBUFFER, context_fbo(device, context));
+        check_gl(gl);
+    }
+}
+
+fn context_fbo(device: &Device, context: &Context) -> Option<Framebuffer> {
+    device
+        .context_surface_info(context)
+        .unwrap()
+        .unwrap()
+        .framebuffer_object
+}
+
+fn make_surface(device: &mut Device, context: &Context) -> Surface {
+    device
+        .create_surface(
+            context,
+            SurfaceAccess::GPUOnly,
+            SurfaceType::Generic {
+                size: Size2D::new(640, 480),
+            },
+        )
+        .unwrap()
+}
+
+fn blit_fbo(gl: &Gl, dest_fbo: Option<Framebuffer>, src_fbo: Option<Framebuffer>) {
+    unsafe {
+        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, dest_fbo);
+        check_gl(gl);
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, src_fbo);
+        check_gl(gl);
+        gl.blit_framebuffer(
+            0,
+            0,
+            640,
+            480,
+            0,
+            0,
+            640,
+            480,
+            gl::COLOR_BUFFER_BIT,
+            gl::NEAREST,
+        );
+        check_gl(gl);
+    }
+}
+
+fn make_fbo(gl: &Gl, texture_target: u32, texture: Option<Texture>) -> Framebuffer {
+    unsafe {
+        let framebuffer_object = gl.create_framebuffer().unwrap();
+        check_gl(gl);
+        gl.bind_framebuffer(gl::FRAMEBUFFER, Some(framebuffer_object));
+        check_gl(gl);
+        gl.framebuffer_texture_2d(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            texture_target,
+            texture,
+            0,
+        );
         check_gl(gl);
         assert_eq!(
             gl.check_framebuffer_status(gl::FRAMEBUFFER),
@@ -1009,6 +2717,7 @@ impl BasicEnvironment {
             .create_context_descriptor(&ContextAttributes {
                 version: GLVersion::new(3, 0),
                 flags: ContextAttributeFlags::empty(),
+                priority: ContextPriority::Medium,
             })
             .unwrap();
 
@@ -1087,6 +2796,22 @@ fn get_pixel_from_bottom_row(gl: &Gl) -> [u8; 4] {
     }
 }
 
+fn get_pixel(gl: &Gl, x: i32, y: i32) -> [u8; 4] {
+    unsafe {
+        let mut pixel: [u8; 4] = [0; 4];
+        gl.read_pixels(
+            x,
+            y,
+            1,
+            1,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            PixelPackData::Slice(Some(&mut pixel)),
+        );
+        pixel
+    }
+}
+
 fn get_pixel_from_second_from_bottom_row(gl: &Gl) -> [u8; 4] {
     unsafe {
         let mut pixel: [u8; 4] = [0; 4];
@@ -1112,3 +2837,27 @@ fn check_gl(gl: &Gl) {
 fn approx_eq(a: f32, b: f32) -> bool {
     f32::abs(a - b) < 0.001
 }
+
+// Returns the device and driver UUIDs of the context's implementation, via `GL_EXT_memory_object`.
+#[cfg(free_unix)]
+fn gl_device_and_driver_uuids(device: &Device, context: &Context) -> Option<([u8; 16], [u8; 16])> {
+    const GL_DEVICE_UUID_EXT: u32 = 0x9597;
+    const GL_DRIVER_UUID_EXT: u32 = 0x9598;
+
+    let get_unsigned_bytev = device.get_proc_address(context, "glGetUnsignedBytevEXT");
+    let get_unsigned_bytei_v = device.get_proc_address(context, "glGetUnsignedBytei_vEXT");
+    if get_unsigned_bytev.is_null() || get_unsigned_bytei_v.is_null() {
+        return None;
+    }
+    unsafe {
+        let get_unsigned_bytev: extern "C" fn(u32, *mut u8) =
+            std::mem::transmute(get_unsigned_bytev);
+        let get_unsigned_bytei_v: extern "C" fn(u32, u32, *mut u8) =
+            std::mem::transmute(get_unsigned_bytei_v);
+        let (mut device_uuid, mut driver_uuid) = ([0; 16], [0; 16]);
+        device.make_context_current(context).unwrap();
+        get_unsigned_bytei_v(GL_DEVICE_UUID_EXT, 0, device_uuid.as_mut_ptr());
+        get_unsigned_bytev(GL_DRIVER_UUID_EXT, driver_uuid.as_mut_ptr());
+        Some((device_uuid, driver_uuid))
+    }
+}